@@ -0,0 +1,119 @@
+//! Latency of the steps between a keystroke or command and its output:
+//! reading the index, filtering and drawing the picker and generating
+//! content. Run with
+//! `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline main` and `--baseline main`.
+
+use criterion::{BatchSize, Criterion, black_box, criterion_group, criterion_main};
+
+use lightignore::GenerateBuilder;
+use lightignore::app::{App, AppOptions};
+use lightignore::settings::Settings;
+use lightignore::template::TemplateIndex;
+use lightignore::ui::selection::SelectionState;
+
+/// Several times github/gitignore, as multi-source setups reach
+const TEMPLATE_COUNT: usize = 1500;
+
+fn synthetic_cache() -> (tempfile::TempDir, TemplateIndex) {
+    let dir = tempfile::tempdir().expect("creating a temporary cache");
+    let app = App::new(
+        dir.path().to_path_buf(),
+        Settings::default(),
+        AppOptions::default(),
+    )
+    .expect("creating the app")
+    .with_output(lightignore::ui::Output::buffer());
+    let index = app
+        .update_synthetic(TEMPLATE_COUNT)
+        .expect("writing synthetic templates");
+    (dir, index)
+}
+
+fn index_load(c: &mut Criterion) {
+    let (dir, _) = synthetic_cache();
+    c.bench_function("index_load", |b| {
+        b.iter(|| TemplateIndex::read(black_box(dir.path())).expect("reading the index"))
+    });
+}
+
+fn refresh_filter(c: &mut Criterion) {
+    let (_dir, index) = synthetic_cache();
+    let names = index.list();
+
+    // Typing a query one character at a time, as in the picker
+    c.bench_function("refresh_filter_typing", |b| {
+        b.iter_batched(
+            || SelectionState::new(names.clone()),
+            |mut state| {
+                for ch in "lang01".chars() {
+                    state.push_search_char(ch);
+                }
+                state
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("refresh_filter_clear", |b| {
+        let mut state = SelectionState::new(names.clone());
+        b.iter(|| {
+            state.push_search_char('e');
+            state.clear_search();
+        })
+    });
+}
+
+fn render_frame(c: &mut Criterion) {
+    let (_dir, index) = synthetic_cache();
+    let mut state = SelectionState::new(index.list());
+    state.resize(160, 48);
+    for idx in (0..TEMPLATE_COUNT).step_by(100) {
+        state.select_item(idx);
+    }
+
+    // A keystroke and the frame it paints; each frame after the first only
+    // rewrites the rows that changed
+    let mut frame = Vec::new();
+    c.bench_function("render_frame_typing", |b| {
+        b.iter(|| {
+            frame.clear();
+            state.push_search_char('e');
+            state.render(&mut frame).expect("rendering a frame");
+            state.pop_search_char();
+            state.render(&mut frame).expect("rendering a frame");
+        })
+    });
+
+    c.bench_function("render_frame_scroll", |b| {
+        b.iter(|| {
+            frame.clear();
+            state.page_down();
+            state.render(&mut frame).expect("rendering a frame");
+            state.move_home();
+            state.render(&mut frame).expect("rendering a frame");
+        })
+    });
+}
+
+fn generation(c: &mut Criterion) {
+    let (_dir, index) = synthetic_cache();
+    let selected: Vec<String> = index.list().into_iter().step_by(50).collect();
+    c.bench_function("generate_content", |b| {
+        b.iter(|| {
+            GenerateBuilder::new(&index)
+                .templates(black_box(&selected))
+                .build()
+                .expect("generating content")
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    index_load,
+    refresh_filter,
+    render_frame,
+    generation
+);
+criterion_main!(benches);
@@ -1,47 +1,256 @@
-use crate::ui::theme::get_theme;
 use anyhow::{Context, Result};
-use crossterm::{
-    QueueableCommand,
-    style::{Print, ResetColor, SetForegroundColor},
-};
-use futures::stream::{self, StreamExt};
+use clap::ValueEnum;
 use reqwest::Client;
+use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::future::Future;
-use std::io::{self, Write};
-use std::path::PathBuf;
-use std::pin::Pin;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
 
+use crate::archive::read_archive_file;
+use crate::atomic::write_atomic;
+use crate::bench_data;
+use crate::bundle::{self, Bundle};
+use crate::clipboard::copy_text;
+use crate::companions;
 use crate::config::{
-    build_options_list, build_previous_selection, load_or_default_config, update_and_save_config,
-    validate_config,
+    LignoreConfig, build_options_list, build_previous_selection, find_config_path,
+    load_or_default_config, pattern_similarity, relative_to_config, save_config, shared_outputs,
+    unique_patterns, update_and_save_config, validate_config,
+};
+use crate::detect::{Detected, detect};
+use crate::excludes::{
+    excludes_file_path, read_personal_templates, remove_personal_block, write_personal_block,
+};
+use crate::exit::PartialUpdate;
+use crate::gitignore::{
+    GenerateBuilder, append_missing, ensure_not_quarantined, ensure_output_directory,
+    generate_gitignore_content, generate_gitignore_content_with, load_template_content,
+    put_section, read_sections, refresh_templates_line, remove_section, section_header_line,
+};
+use crate::http::build_client;
+use crate::kind::IgnoreKind;
+use crate::limits::get_limits;
+use crate::lock::{LOCK_FILE, LockedTemplate, ProjectLock, content_hash};
+use crate::managed;
+use crate::matcher::Matcher;
+use crate::metadata::describe;
+use crate::mirror::{CHECKSUMS_FILE, export_mirror, verify_mirror};
+use crate::normalize::{self, LintIssue};
+use crate::policy::{self, PolicyTemplate, ReportFormat};
+use crate::popularity;
+use crate::preset::{self, Preset};
+use crate::progress::{ProgressEvent, ProgressFormat};
+use crate::project_guard::ProjectGuard;
+use crate::rate_limit::RateLimiter;
+use crate::recipe;
+use crate::safety;
+use crate::session::PickerSession;
+use crate::settings::{DefaultCommand, SETTINGS_FILE, Settings, SortOrder};
+use crate::sources::state::{SyncState, now_secs};
+use crate::sources::{FetchedTemplate, SourceContext, SourceRegistry, SourceSummary, UpdateReport};
+use crate::tags::{self, expand_tags};
+use crate::template::{CachedTemplate, SourceTemplates, TemplateEntry, TemplateIndex};
+use crate::tracked::{tracked_but_ignored, untrack_command};
+use crate::ui::display::{
+    ask, confirm, format_age, format_size, print_success_message, print_theme_preview,
+    print_warning,
 };
-use crate::gitignore::{ensure_output_directory, generate_gitignore_content};
-use crate::template::{RateLimit, RepoContent, TemplateIndex};
-use crate::ui::display::print_success_message;
-use crate::ui::{calculate_column_layout, print_columnar_list, select_templates};
+use crate::ui::theme::{Palette, Theme, ThemeKind};
+use crate::ui::{
+    Output, PickerOptions, PickerTab, calculate_column_layout, print_columnar_list,
+    print_json_list, print_long_list, print_success, select_tabs, select_templates,
+};
+use crate::usage::{USAGE_FILE, UsageStats};
 use crate::validation::{validate_output_path, validate_template_key};
+use crate::workspace::{IgnoreFile, find_conflicts, member_config_path, member_dir};
+
+// Security limits; per-template limits live in `limits`
+/// Source name of the templates `update --bench-mode` writes
+const BENCH_SOURCE: &str = "bench";
+
+pub const MAX_ARCHIVE_SIZE: u64 = 50 * 1024 * 1024; // 50MB
+
+/// Caches older than this are reported as stale by `status`
+const STALE_CACHE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Global command line switches that affect how the app behaves
+#[derive(Clone, Debug, Default)]
+pub struct AppOptions {
+    pub http1: bool,
+    pub verbose: bool,
+    /// Fail instead of downloading templates
+    pub offline: bool,
+    /// Project config file given with `--config`, instead of looking for
+    /// lignore.json or .lignore.json
+    pub config: Option<PathBuf>,
+    /// Treat warnings such as failed downloads or templates missing from the
+    /// cache as errors
+    pub strict: bool,
+    /// Format of download progress during updates
+    pub progress: ProgressFormat,
+    /// Answer yes to confirmation prompts, such as before the first download
+    pub assume_yes: bool,
+}
+
+/// Options for writing the generated output
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WriteOptions {
+    /// Also place the generated content on the clipboard
+    pub copy: bool,
+    /// Use the latest content of templates that changed since the last
+    /// generation without asking
+    pub refresh_changed: bool,
+    /// Warn about tracked files the new rules would ignore
+    pub check_tracked: bool,
+    /// Leave lignore.json and lignore.lock unwritten
+    pub skip_config: bool,
+    /// Leave `@os` and `@editor` templates out of the selection unless the
+    /// `personal` setting sends them to the global excludes file
+    pub no_personal: bool,
+    /// Only add patterns missing from an existing output file, never
+    /// changing or removing its lines
+    pub append: bool,
+}
+
+/// What `generate` does when the picker is confirmed with nothing selected
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EmptySelection {
+    /// Leave the output file and lignore.json as they are
+    Keep,
+    /// Write the output file without templates and save the empty selection
+    Empty,
+    /// Empty the template list in lignore.json and leave the output file
+    Clear,
+}
+
+/// How an interactive generation ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickerOutcome {
+    /// The selection was confirmed and handled
+    Done,
+    /// The picker was closed with Esc or Ctrl+C; nothing was written
+    Cancelled,
+}
 
-// Security limits
-pub const MAX_DOWNLOAD_SIZE: u64 = 10 * 1024 * 1024; // 10MB
+/// A file the picker edits in its own tab besides the one being generated
+enum OtherPickerFile {
+    /// Another kind, whose templates are saved in lignore.json
+    Kind(IgnoreKind),
+    /// The user's global excludes file
+    Excludes(PathBuf),
+}
+
+/// Custom templates sharing at least this share of their patterns with an
+/// official template get a warning suggesting the official one
+const SIMILAR_TEMPLATE_THRESHOLD: f64 = 0.9;
+
+/// Share of patterns a template added by an update must have in common with
+/// a removed one to count as its new name
+const RENAME_THRESHOLD: f64 = 0.9;
+
+/// The project's selection and the cached template names before an update
+#[derive(Default)]
+struct RenameBaseline {
+    /// Content of each selected template, by name
+    selected: BTreeMap<String, String>,
+    previous: BTreeSet<String>,
+}
+
+impl RenameBaseline {
+    /// Pairs each selected template the update removed with a template it
+    /// added that has the same, or nearly the same, content
+    fn renames(&self, index: &TemplateIndex) -> Vec<(String, String)> {
+        let added: Vec<(&String, &TemplateEntry)> = index
+            .templates
+            .iter()
+            .filter(|(name, _)| !self.previous.contains(*name))
+            .collect();
+        if added.is_empty() {
+            return Vec::new();
+        }
+
+        let mut renames = Vec::new();
+        for (old, content) in &self.selected {
+            if index.get(old).is_some() {
+                continue;
+            }
+            let hash = content_hash(content);
+            let exact = added
+                .iter()
+                .find(|(_, entry)| entry.hash == hash)
+                .map(|(name, _)| *name);
+            let renamed = exact.or_else(|| {
+                added
+                    .iter()
+                    .filter_map(|(name, entry)| {
+                        let similarity =
+                            pattern_similarity(content, &entry.read_verified(name).ok()?);
+                        (similarity >= RENAME_THRESHOLD).then_some((similarity, *name))
+                    })
+                    .max_by(|a, b| a.0.total_cmp(&b.0))
+                    .map(|(_, name)| name)
+            });
+            if let Some(new) = renamed {
+                renames.push((old.clone(), new.clone()));
+            }
+        }
+        renames
+    }
+}
+
+/// Most templates listed by `stats --usage`
+const MAX_USAGE_REPORT: usize = 15;
 
-pub const GITIGNORE_REPO_API: &str = "https://api.github.com/repos/github/gitignore";
+/// Most `git rm --cached` suggestions printed after a generation
+const MAX_UNTRACK_SUGGESTIONS: usize = 20;
 
 pub struct App {
     client: Client,
     cache_dir: PathBuf,
+    rate_limiter: RateLimiter,
+    registry: SourceRegistry,
+    settings: Settings,
+    options: AppOptions,
+    output: Output,
 }
 
 impl App {
-    pub fn new(cache_dir: PathBuf) -> Result<Self> {
-        let client = Client::builder()
-            .user_agent("lightignore/0.1")
-            .build()
-            .context("building HTTP client")?;
-        Ok(Self { client, cache_dir })
+    pub fn new(cache_dir: PathBuf, settings: Settings, options: AppOptions) -> Result<Self> {
+        let client = build_client(options.http1)?;
+        Ok(Self {
+            client,
+            cache_dir,
+            rate_limiter: RateLimiter::new(),
+            registry: SourceRegistry::default(),
+            settings,
+            options,
+            output: Output::stdout(),
+        })
+    }
+
+    /// Sends messages to `output` instead of stdout. Progress from sources
+    /// and the picker still use the terminal.
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Where messages are printed
+    pub fn out(&self) -> Output {
+        self.output.clone()
+    }
+
+    fn config_path(&self) -> PathBuf {
+        find_config_path(self.options.config.as_deref())
+    }
+
+    /// The lockfile next to the project config
+    fn lock_path(&self) -> PathBuf {
+        relative_to_config(&self.config_path(), Path::new(LOCK_FILE))
     }
 
     fn ensure_cache_dir(&self) -> Result<()> {
@@ -53,295 +262,2741 @@ impl App {
         Ok(())
     }
 
-    async fn fetch_repo_tree(&self, path: &str) -> Result<Vec<RepoContent>> {
-        let url = format!("{}/contents/{}", GITIGNORE_REPO_API, path);
-        let res = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .context("fetching repository contents")?;
-        if !res.status().is_success() {
-            if res.status().as_u16() == 403 {
-                self.display_rate_limit_info().await;
-            }
-            anyhow::bail!("GitHub API returned status {}", res.status());
-        }
-        let contents = res
-            .json::<Vec<RepoContent>>()
-            .await
-            .context("parsing GitHub contents response")?;
-        Ok(contents)
-    }
-
-    async fn fetch_rate_limit_info(&self) -> Result<RateLimit> {
-        use crate::template::RateLimitResponse;
-
-        let url = "https://api.github.com/rate_limit";
-        let res = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .context("fetching rate limit info")?;
-        let data = res
-            .json::<RateLimitResponse>()
-            .await
-            .context("parsing rate limit response")?;
-        Ok(data.resources.core)
-    }
-
-    async fn display_rate_limit_info(&self) {
-        if let Ok(rate_limit) = self.fetch_rate_limit_info().await {
-            let mut stdout = io::stdout();
-            let theme = get_theme();
-            let _ = stdout.queue(SetForegroundColor(theme.header_title));
-            let _ = stdout.queue(Print("\nRate Limit Information:\n"));
-            let _ = stdout.queue(ResetColor);
-
-            let _ = stdout.queue(SetForegroundColor(theme.accent));
-            let _ = stdout.queue(Print(format!("  Limit:     {}\n", rate_limit.limit)));
-            let _ = stdout.queue(Print(format!("  Remaining: {}\n", rate_limit.remaining)));
-
-            // Convert reset timestamp to human-readable format
-            let reset_time = rate_limit.reset;
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
-            let wait_time = if reset_time > now {
-                reset_time - now
-            } else {
-                0
-            };
+    /// Downloads every source's templates and rebuilds the index. Templates
+    /// that fail to download are skipped and listed in the returned report,
+    /// which the caller shows once the update is done.
+    pub async fn update_cache(&self) -> Result<(TemplateIndex, UpdateReport)> {
+        if self.options.offline {
+            anyhow::bail!(
+                "Cannot download templates in offline mode; use `lignore update --from-archive` to seed the cache"
+            );
+        }
+        self.ensure_cache_dir()?;
+        let baseline = self.rename_baseline();
+
+        let previous: BTreeMap<String, usize> = self
+            .read_index()
+            .map(|index| index.into_sources())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|source| (source.source, source.templates.len()))
+            .collect();
 
-            let minutes = wait_time / 60;
-            let seconds = wait_time % 60;
+        let ctx_for = |name: &str| SourceContext {
+            client: &self.client,
+            rate_limiter: &self.rate_limiter,
+            verbose: self.options.verbose,
+            previous_count: previous.get(name).copied(),
+            report: Mutex::default(),
+            progress: self.options.progress,
+        };
 
-            let _ = stdout.queue(Print(format!(
-                "  Reset:     {} (in {}m {}s)\n",
-                reset_time, minutes, seconds
-            )));
-            let _ = stdout.queue(ResetColor);
-            let _ = stdout.flush();
+        let mut state = SyncState::read(&self.cache_dir);
+        let mut results = Vec::new();
+        let mut report = UpdateReport::default();
+        for config in self.settings.sources_by_priority() {
+            let source = self.registry.create(config)?;
+            let ctx = ctx_for(source.name());
+            self.options.progress.report(&ProgressEvent::ScanStarted {
+                source: source.name(),
+            });
+            if self.options.verbose {
+                let metadata = source.metadata();
+                writeln!(
+                    self.out(),
+                    "Updating source {} ({}: {})",
+                    source.name(),
+                    metadata.kind,
+                    metadata.location
+                )?;
+            }
+            let fetched = source.sync(&ctx).await?;
+            let source_failed = ctx.failures();
+            if self.options.strict && source_failed > 0 {
+                return Err(anyhow::Error::new(PartialUpdate {
+                    failed: source_failed,
+                }))
+                .with_context(|| {
+                    format!(
+                        "updating source {}; the cache was left unchanged because of --strict",
+                        source.name()
+                    )
+                });
+            }
+            report.merge(ctx.into_report());
+            let templates = self.install_templates(source.name(), fetched)?;
+            state.record(source.name(), templates.len());
+            self.options
+                .progress
+                .report(&ProgressEvent::SourceCompleted {
+                    source: source.name(),
+                    templates: templates.len(),
+                    failed: source_failed,
+                });
+            report.sources.push(SourceSummary {
+                source: config.name.clone(),
+                templates: templates.len(),
+                failed: source_failed,
+            });
+            results.push(SourceTemplates {
+                source: config.name.clone(),
+                templates,
+            });
         }
+
+        let mut index = TemplateIndex::from_sources(results, self.settings.conflict_policy)?;
+        index.write(&self.cache_dir)?;
+        state.write(&self.cache_dir)?;
+        self.apply_renames(&baseline.renames(&index))?;
+        index.trust(&self.settings.trusted);
+        report.warnings.extend(index.quarantine_warnings());
+        self.options.progress.report(&ProgressEvent::Completed {
+            templates: index.templates.len(),
+            failed: report.failures.len(),
+        });
+        Ok((index, report))
     }
 
-    pub async fn update_cache(&self) -> Result<TemplateIndex> {
-        self.ensure_cache_dir()?;
+    /// Shows the configured theme, or a variation of it, on sample output
+    pub fn preview_theme(
+        &self,
+        background: Option<ThemeKind>,
+        palette: Option<Palette>,
+    ) -> Result<()> {
+        let mut settings = self.settings.theme.clone();
+        if let Some(palette) = palette {
+            settings.palette = palette;
+        }
+        let kind = background.unwrap_or_else(|| settings.kind());
+        let theme = Theme::with_settings(kind, &settings);
+        print_theme_preview(&mut self.out(), &theme, kind, settings.palette.resolve())
+    }
 
-        // Phase 1: Collect all template URLs
-        println!("Scanning gitignore repository...");
-        let templates = self.collect_templates_recursive("").await?;
+    /// Prints each configured source with its type, last sync and template count
+    pub fn list_sources(&self) -> Result<()> {
+        let state = SyncState::read(&self.cache_dir);
+        let index = self.read_index().ok();
+        let now = now_secs();
 
-        println!("Found {} templates. Downloading...", templates.len());
+        for config in self.settings.sources_by_priority() {
+            let source = self.registry.create(config)?;
+            let metadata = source.metadata();
+            let (last_sync, count) = match state.sources.get(&config.name) {
+                Some(record) => (
+                    format!(
+                        "synced {}",
+                        format_age(now.saturating_sub(record.last_sync))
+                    ),
+                    record.templates,
+                ),
+                None => ("never synced".to_string(), 0),
+            };
+            let indexed = index
+                .as_ref()
+                .map(|index| {
+                    index
+                        .templates
+                        .values()
+                        .filter(|entry| entry.source == config.name)
+                        .count()
+                })
+                .unwrap_or(0);
 
-        // Phase 2: Download templates in parallel with progress tracking
-        let counter = Arc::new(AtomicUsize::new(0));
-        let total = templates.len();
+            writeln!(self.out(), "{} ({})", config.name, metadata.kind)?;
+            writeln!(self.out(), "  Location:  {}", metadata.location)?;
+            writeln!(self.out(), "  Priority:  {}", config.priority)?;
+            writeln!(self.out(), "  Last sync: {}", last_sync)?;
+            writeln!(
+                self.out(),
+                "  Templates: {} fetched, {} in index",
+                count,
+                indexed
+            )?;
+        }
+        Ok(())
+    }
 
-        let results = stream::iter(templates)
-            .map(|(key, name, download_url)| {
-                let counter = Arc::clone(&counter);
-                async move {
-                    let result = self.download_template(&key, &download_url).await;
-                    let current = counter.fetch_add(1, Ordering::SeqCst) + 1;
+    /// Prints a summary of the project's selection, its output file and the
+    /// cache, without touching the network. Returns whether the output or
+    /// lockfile has drifted from the cache
+    pub fn status(&self, output: &Path) -> Result<bool> {
+        let config_path = self.config_path();
+        let config = load_or_default_config(&config_path)?;
+        let index = self.read_index().ok();
 
-                    // Print progress every 10 templates or on the last one
-                    if current % 10 == 0 || current == total {
-                        print!("\rDownloaded {}/{} templates", current, total);
-                        let _ = io::stdout().flush();
-                    }
+        writeln!(self.out(), "Project ({})", config_path.display())?;
+        if !config_path.exists() {
+            print_warning(
+                &mut self.out(),
+                &format!(
+                    "No {}; run `lignore generate` to create one",
+                    config_path.display()
+                ),
+            )?;
+        }
+        writeln!(
+            self.out(),
+            "  Templates: {}",
+            join_or_none(&config.templates)
+        )?;
+        let custom: Vec<String> = config.custom.keys().cloned().collect();
+        writeln!(self.out(), "  Custom:    {}", join_or_none(&custom))?;
+        if let Some(pin) = &config.pin {
+            writeln!(self.out(), "  Pinned to: {}", pin)?;
+        }
+        if let Some(index) = &index {
+            let missing: Vec<String> = config
+                .templates
+                .iter()
+                .filter(|name| index.get(name).is_none() && !config.custom.contains_key(*name))
+                .cloned()
+                .collect();
+            if !missing.is_empty() && self.options.strict {
+                anyhow::bail!(
+                    "Templates in {} are not in the cache: {}",
+                    config_path.display(),
+                    missing.join(", ")
+                );
+            }
+            if !missing.is_empty() {
+                print_warning(
+                    &mut self.out(),
+                    &format!("Not in cache: {}", missing.join(", ")),
+                )?;
+            }
+            self.warn_deprecated(index, &config.templates)?;
+        }
 
-                    result.map(|path| (name, path))
+        writeln!(self.out(), "Output ({})", output.display())?;
+        let selected: Vec<String> = custom.iter().chain(&config.templates).cloned().collect();
+        let (selected, personal) = self.split_personal(&selected)?;
+        if !personal.is_empty() {
+            writeln!(
+                self.out(),
+                "  Personal:  {} (global excludes)",
+                personal.join(", ")
+            )?;
+        }
+        let mut drift = false;
+        match (fs::read_to_string(output), &index) {
+            (Err(_), _) => {
+                drift = true;
+                print_warning(
+                    &mut self.out(),
+                    "Missing; run `lignore generate` to create it",
+                )?
+            }
+            (Ok(_), _) if selected.is_empty() => {
+                writeln!(self.out(), "  Exists, no templates selected")?
+            }
+            (Ok(_), None) => writeln!(self.out(), "  Exists, cannot compare without a cache")?,
+            (Ok(current), Some(index)) => {
+                let expected = generate_gitignore_content(&selected, index, &config)?;
+                if current == expected {
+                    print_success(&mut self.out(), "Up to date with the cached templates")?;
+                } else {
+                    drift = true;
+                    print_warning(
+                        &mut self.out(),
+                        "Differs from the cached templates (edited by hand or templates changed); run `lignore generate` to refresh",
+                    )?;
                 }
-            })
-            .buffer_unordered(20) // Download 20 templates concurrently
-            .collect::<Vec<_>>()
-            .await;
-
-        println!(); // New line after progress
-
-        // Build index from results
-        let mut index = TemplateIndex::new();
-        for result in results {
-            match result {
-                Ok((name, path)) => {
-                    index.insert(name, path.to_string_lossy().to_string());
+            }
+        }
+        match (ProjectLock::read(&self.lock_path())?, &index) {
+            (None, _) => writeln!(
+                self.out(),
+                "  No {}; it is written on the next generate",
+                LOCK_FILE
+            )?,
+            (Some(_), None) => writeln!(self.out(), "  Locked in {}", LOCK_FILE)?,
+            (Some(lock), Some(index)) => {
+                let mut changed = Vec::new();
+                for (name, locked) in &lock.templates {
+                    if index.get(name).is_none() {
+                        continue;
+                    }
+                    if let Ok(content) = load_template_content(name, index, &config)
+                        && content_hash(&content) != locked.hash
+                    {
+                        changed.push(name.clone());
+                    }
                 }
-                Err(e) => {
-                    eprintln!("Warning: Failed to download template: {}", e);
+                if changed.is_empty() {
+                    writeln!(self.out(), "  Locked templates unchanged in the cache")?;
+                } else {
+                    drift = true;
+                    print_warning(
+                        &mut self.out(),
+                        &format!("Changed since the last generation: {}", changed.join(", ")),
+                    )?;
                 }
             }
         }
 
-        index.write(&self.cache_dir)?;
-        Ok(index)
-    }
-
-    // Collect all template information without downloading
-    fn collect_templates_recursive<'a>(
-        &'a self,
-        path: &'a str,
-    ) -> Pin<Box<dyn Future<Output = Result<Vec<(String, String, String)>>> + 'a>> {
-        Box::pin(async move {
-            let contents = self.fetch_repo_tree(path).await?;
-            let mut templates = Vec::new();
-
-            for entry in contents {
-                if entry.content_type == "file" && entry.name.ends_with(".gitignore") {
-                    if let Some(download_url) = entry.download_url {
-                        let name = entry.name.trim_end_matches(".gitignore").to_string();
-                        // Use the full path as the cache key to avoid conflicts
-                        let cache_key = if path.is_empty() {
-                            name.clone()
-                        } else {
-                            format!("{}/{}", path, name)
-                        };
-                        templates.push((cache_key, name, download_url));
+        writeln!(self.out(), "Cache ({})", self.cache_dir.display())?;
+        if index.is_none() {
+            print_warning(&mut self.out(), "Not downloaded; run `lignore update`")?;
+            return Ok(drift);
+        }
+        let state = SyncState::read(&self.cache_dir);
+        let now = now_secs();
+        for config in self.settings.sources_by_priority() {
+            match state.sources.get(&config.name) {
+                Some(record) => {
+                    let age = now.saturating_sub(record.last_sync);
+                    let line = format!(
+                        "{}: {} templates, synced {}",
+                        config.name,
+                        record.templates,
+                        format_age(age)
+                    );
+                    if age > STALE_CACHE_SECS {
+                        print_warning(&mut self.out(), &format!("{}; run `lignore update`", line))?;
+                    } else {
+                        writeln!(self.out(), "  {}", line)?;
                     }
-                } else if entry.content_type == "dir" {
-                    let mut sub_templates = self.collect_templates_recursive(&entry.path).await?;
-                    templates.append(&mut sub_templates);
                 }
+                None => print_warning(&mut self.out(), &format!("{}: never synced", config.name))?,
             }
+        }
+        Ok(drift)
+    }
 
-            Ok(templates)
-        })
+    /// Generates the .gitignore of the root project and of each workspace
+    /// member in memory, and warns about member rules that conflict with the
+    /// rules of a file in a directory above them
+    pub fn check_workspace(&self, index: &TemplateIndex) -> Result<()> {
+        let config_path = self.config_path();
+        let root = load_or_default_config(&config_path)?;
+        if root.members.is_empty() {
+            anyhow::bail!(
+                "{} lists no workspace members; add their directories to 'members'",
+                config_path.display()
+            );
+        }
+
+        let selected = |config: &LignoreConfig| -> Vec<String> {
+            config
+                .custom
+                .keys()
+                .chain(&config.templates)
+                .cloned()
+                .collect()
+        };
+        let content = generate_gitignore_content(&selected(&root), index, &root)?;
+        let mut files = vec![IgnoreFile {
+            dir: String::new(),
+            path: PathBuf::from(".gitignore"),
+            matcher: Matcher::new(&content),
+        }];
+        for member in &root.members {
+            let dir = relative_to_config(&config_path, member);
+            let member_config = member_config_path(&dir).with_context(|| {
+                format!("workspace member {} has no lignore.json", member.display())
+            })?;
+            let config = load_or_default_config(&member_config)?;
+            let content = generate_gitignore_content(&selected(&config), index, &config)
+                .with_context(|| format!("generating for workspace member {}", member.display()))?;
+            files.push(IgnoreFile {
+                dir: member_dir(member),
+                path: member.join(".gitignore"),
+                matcher: Matcher::new(&content),
+            });
+        }
+
+        let conflicts = find_conflicts(&files);
+        for conflict in &conflicts {
+            print_warning(&mut self.out(), &conflict.to_string())?;
+        }
+        if conflicts.is_empty() {
+            print_success(
+                &mut self.out(),
+                &format!(
+                    "No conflicting rules across {} workspace members",
+                    root.members.len()
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// What the selection in `config` generates from the cached templates,
+    /// leaving out personal templates
+    fn expected_output(&self, index: &TemplateIndex, config: &LignoreConfig) -> Result<String> {
+        let selected: Vec<String> = config
+            .custom
+            .keys()
+            .chain(&config.templates)
+            .cloned()
+            .collect();
+        let (selected, _) = self.split_personal(&selected)?;
+        generate_gitignore_content(&selected, index, config)
     }
 
-    async fn download_template(&self, key: &str, url: &str) -> Result<PathBuf> {
-        // Validate key to prevent path traversal
-        validate_template_key(key)?;
+    /// Whether `lignore` without a subcommand shows the status instead of
+    /// opening the picker: as the `default_command` setting says, or for
+    /// `auto` when the project has lignore.json and an up-to-date output
+    pub fn status_by_default(&self, output: &Path) -> bool {
+        match self.settings.default_command {
+            DefaultCommand::Generate => false,
+            DefaultCommand::Status => true,
+            DefaultCommand::Auto => self.is_up_to_date(output),
+        }
+    }
 
-        if !url.starts_with("https://") {
-            anyhow::bail!("Download URL must use HTTPS: {}", url);
+    /// Whether the output file matches what lignore.json generates from the
+    /// cache, reading only what is already on disk
+    fn is_up_to_date(&self, output: &Path) -> bool {
+        let config_path = self.config_path();
+        if !config_path.exists() {
+            return false;
         }
+        let (Ok(index), Ok(current), Ok(config)) = (
+            self.read_index(),
+            fs::read_to_string(output),
+            load_or_default_config(&config_path),
+        ) else {
+            return false;
+        };
+        (!config.templates.is_empty() || !config.custom.is_empty())
+            && self
+                .expected_output(&index, &config)
+                .is_ok_and(|expected| expected == current)
+    }
 
-        let sanitized_key = key.replace('/', "_");
-        let file_path = self.cache_dir.join(format!("{}.gitignore", sanitized_key));
+    /// Prints a unified diff from the output file to what the selection in
+    /// lignore.json generates from the cached templates, returning whether
+    /// they differ
+    pub fn diff(&self, index: &TemplateIndex, output: &Path) -> Result<bool> {
+        let config_path = self.config_path();
+        if !config_path.exists() {
+            anyhow::bail!(
+                "No {}; run `lignore generate` to create one",
+                config_path.display()
+            );
+        }
+        let config = load_or_default_config(&config_path)?;
+        let expected = self.expected_output(index, &config)?;
 
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .with_context(|| format!("downloading template {}", key))?;
+        let (current, old_header) = match fs::read_to_string(output) {
+            Ok(current) => (current, output.display().to_string()),
+            Err(_) => (String::new(), "/dev/null".to_string()),
+        };
+        if current == expected {
+            print_success(
+                &mut self.out(),
+                &format!("{} matches the cached templates", output.display()),
+            )?;
+            return Ok(false);
+        }
+        let new_header = format!("{} (regenerated)", output.display());
+        let diff = TextDiff::from_lines(&current, &expected);
+        write!(
+            self.out(),
+            "{}",
+            diff.unified_diff().header(&old_header, &new_header)
+        )?;
+        Ok(true)
+    }
 
-        if !response.status().is_success() {
-            if response.status().as_u16() == 403 {
-                self.display_rate_limit_info().await;
-            }
+    /// Checks that each source (or only `name`) can list its templates
+    pub async fn test_sources(&self, name: Option<&str>) -> Result<()> {
+        let configs: Vec<_> = self
+            .settings
+            .sources_by_priority()
+            .into_iter()
+            .filter(|config| name.is_none_or(|name| config.name == name))
+            .collect();
+        if configs.is_empty() {
             anyhow::bail!(
-                "failed to download template {}: status {}",
-                key,
-                response.status()
+                "No source named '{}' is configured",
+                name.unwrap_or_default()
             );
         }
 
-        if let Some(content_length) = response.content_length() {
-            if content_length > MAX_DOWNLOAD_SIZE {
-                anyhow::bail!(
-                    "Template {} is too large: {} bytes (max: {} bytes)",
-                    key,
-                    content_length,
-                    MAX_DOWNLOAD_SIZE
-                );
+        let mut failures = 0;
+        for config in configs {
+            let source = self.registry.create(config)?;
+            let ctx = SourceContext {
+                client: &self.client,
+                rate_limiter: &self.rate_limiter,
+                verbose: self.options.verbose,
+                previous_count: None,
+                report: Mutex::default(),
+                progress: self.options.progress,
+            };
+            let started = Instant::now();
+            match source.list(&ctx).await {
+                Ok(templates) => print_success(
+                    &mut self.out(),
+                    &format!(
+                        "{}: reachable, {} templates ({:.2?})",
+                        config.name,
+                        templates.len(),
+                        started.elapsed()
+                    ),
+                )?,
+                Err(e) => {
+                    failures += 1;
+                    print_warning(&mut self.out(), &format!("{}: {:#}", config.name, e))?;
+                }
             }
         }
 
-        let content = response.text().await?;
+        if failures > 0 {
+            anyhow::bail!("{} source(s) failed the connectivity test", failures);
+        }
+        Ok(())
+    }
+
+    /// Seeds the first configured source from a local repository archive,
+    /// without touching the network
+    pub fn update_from_archive(&self, archive: &Path) -> Result<TemplateIndex> {
+        self.ensure_cache_dir()?;
+        let baseline = self.rename_baseline();
+
+        let source = self
+            .settings
+            .sources
+            .first()
+            .context("no template source configured")?;
 
-        // Double-check size after download
-        if content.len() > MAX_DOWNLOAD_SIZE as usize {
-            anyhow::bail!(
-                "Template {} exceeds size limit: {} bytes (max: {} bytes)",
-                key,
-                content.len(),
-                MAX_DOWNLOAD_SIZE
+        writeln!(
+            self.out(),
+            "Reading templates from {}...",
+            archive.display()
+        )?;
+        let templates = read_archive_file(archive)?;
+        if templates.is_empty() {
+            anyhow::bail!("No .gitignore templates found in {}", archive.display());
+        }
+        let written = self.install_templates(&source.name, templates)?;
+        writeln!(
+            self.out(),
+            "Imported {} templates into source {}",
+            written.len(),
+            source.name
+        )?;
+        let mut state = SyncState::read(&self.cache_dir);
+        state.record(&source.name, written.len());
+
+        // Keep templates that belong to other sources
+        let mut previous: BTreeMap<String, Vec<CachedTemplate>> = self
+            .read_index()
+            .map(|index| index.into_sources())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|source| (source.source, source.templates))
+            .collect();
+        previous.insert(source.name.clone(), written);
+
+        let results = self
+            .settings
+            .sources_by_priority()
+            .into_iter()
+            .filter_map(|source| {
+                previous
+                    .remove(&source.name)
+                    .map(|templates| SourceTemplates {
+                        source: source.name.clone(),
+                        templates,
+                    })
+            })
+            .collect();
+        let mut index = TemplateIndex::from_sources(results, self.settings.conflict_policy)?;
+        index.write(&self.cache_dir)?;
+        state.write(&self.cache_dir)?;
+        self.apply_renames(&baseline.renames(&index))?;
+        index.trust(&self.settings.trusted);
+        for warning in index.quarantine_warnings() {
+            print_warning(&mut self.out(), &warning)?;
+        }
+        Ok(index)
+    }
+
+    /// Replaces the cache with `count` synthetic templates in a `bench`
+    /// source, for measuring the picker and generation at scale
+    pub fn update_synthetic(&self, count: usize) -> Result<TemplateIndex> {
+        self.ensure_cache_dir()?;
+        let templates = bench_data::synthetic_templates(count);
+        let written = self.install_templates(BENCH_SOURCE, templates)?;
+        let index = TemplateIndex::from_sources(
+            vec![SourceTemplates {
+                source: BENCH_SOURCE.to_string(),
+                templates: written,
+            }],
+            self.settings.conflict_policy,
+        )?;
+        index.write(&self.cache_dir)?;
+        writeln!(
+            self.out(),
+            "Wrote {} synthetic templates to {}",
+            index.templates.len(),
+            self.cache_dir.display()
+        )?;
+        Ok(index)
+    }
+
+    /// Captures the project's selected templates before an update, so
+    /// templates it renames upstream can be recognised by their content
+    fn rename_baseline(&self) -> RenameBaseline {
+        let (Ok(index), Ok(config)) = (
+            self.read_index(),
+            load_or_default_config(&self.config_path()),
+        ) else {
+            return RenameBaseline::default();
+        };
+        let selected = config
+            .templates
+            .iter()
+            .filter_map(|name| {
+                let content = index.get(name)?.read_verified(name).ok()?;
+                Some((name.clone(), content))
+            })
+            .collect();
+        RenameBaseline {
+            selected,
+            previous: index.templates.into_keys().collect(),
+        }
+    }
+
+    /// Reports templates an update renamed and points lignore.json at their
+    /// new names
+    fn apply_renames(&self, renames: &[(String, String)]) -> Result<()> {
+        if renames.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out(), "Renamed upstream:")?;
+        for (old, new) in renames {
+            writeln!(self.out(), "  {} -> {}", old, new)?;
+        }
+
+        let config_path = self.config_path();
+        if !self.settings.save_config {
+            return print_warning(
+                &mut self.out(),
+                &format!(
+                    "Saving lignore.json is turned off; use the new names in {} yourself",
+                    config_path.display()
+                ),
             );
         }
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        let mut templates: Vec<String> = Vec::with_capacity(config.templates.len());
+        for name in &config.templates {
+            let name = renames
+                .iter()
+                .find(|(old, _)| old == name)
+                .map_or(name, |(_, new)| new);
+            if !templates.contains(name) {
+                templates.push(name.clone());
+            }
+        }
+        update_and_save_config(&config_path, &mut config, &templates)?;
+        print_success(
+            &mut self.out(),
+            &format!("Updated {} to the new names", config_path.display()),
+        )
+    }
 
-        fs::write(&file_path, content)
-            .with_context(|| format!("writing template {} to cache", key))?;
+    /// Writes fetched templates into the source's cache directory
+    fn install_templates(
+        &self,
+        source: &str,
+        templates: Vec<FetchedTemplate>,
+    ) -> Result<Vec<CachedTemplate>> {
+        let source_dir = self.cache_dir.join(source);
+        fs::create_dir_all(&source_dir)
+            .with_context(|| format!("creating cache directory {}", source_dir.display()))?;
 
-        Ok(file_path)
+        let mut written = Vec::with_capacity(templates.len());
+        for template in templates {
+            // Validate key to prevent path traversal
+            validate_template_key(&template.key)?;
+
+            let sanitized_key = template.key.replace('/', "_");
+            let file_path = source_dir.join(format!("{}.gitignore", sanitized_key));
+            fs::write(&file_path, &template.content)
+                .with_context(|| format!("writing template {} to cache", template.key))?;
+            written.push(CachedTemplate {
+                hash: content_hash(&template.content),
+                notes: describe(&template.key, &template.content),
+                name: template.name,
+                key: template.key,
+                path: file_path,
+            });
+        }
+        Ok(written)
     }
 
     pub fn read_index(&self) -> Result<TemplateIndex> {
         TemplateIndex::read(&self.cache_dir)
     }
 
-    /// Read index from cache, or automatically update cache if it doesn't exist
+    /// Read index from cache, or automatically update cache if it doesn't
+    /// exist. Templates the `hidden` setting lists are left out.
     pub fn read_index_or_update(&self, rt: &tokio::runtime::Runtime) -> Result<TemplateIndex> {
-        match self.read_index() {
-            Ok(index) => Ok(index),
+        let mut index = match self.read_index() {
+            Ok(index) => index,
+            Err(_) if self.options.offline => anyhow::bail!(
+                "No templates are cached yet and --offline forbids downloading them.\nRun `lignore update`, or seed the cache with `lignore update --from-archive`."
+            ),
             Err(_) => {
-                println!("No cache found. Downloading templates for the first time...");
-                println!(
-                    "(This is a one-time setup and will be much faster with parallel downloads)\n"
+                self.confirm_first_download()?;
+                let (index, report) = rt.block_on(self.update_cache())?;
+                report.render(&mut self.out(), self.options.progress)?;
+                index
+            }
+        };
+        index.hide(&self.settings.hidden);
+        index.trust(&self.settings.trusted);
+        Ok(index)
+    }
+
+    /// Tells what the first download costs and asks before starting it,
+    /// unless every source is local or `--yes` was given
+    fn confirm_first_download(&self) -> Result<()> {
+        let mut requests = 0;
+        let mut bytes = 0;
+        let mut unknown = Vec::new();
+        for config in self.settings.sources_by_priority() {
+            let source = self.registry.create(config)?;
+            match source.estimate() {
+                Some(estimate) => {
+                    requests += estimate.requests;
+                    bytes += estimate.bytes;
+                }
+                None => unknown.push(source.name().to_string()),
+            }
+        }
+        if requests == 0 && unknown.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(self.out(), "No templates are cached yet.")?;
+        if requests > 0 {
+            writeln!(
+                self.out(),
+                "Downloading them takes about {} request(s) and {}.",
+                requests,
+                format_size(bytes)
+            )?;
+        }
+        if !unknown.is_empty() {
+            writeln!(
+                self.out(),
+                "Sources that are sized only once listed: {}",
+                unknown.join(", ")
+            )?;
+        }
+
+        if !self.options.assume_yes {
+            if !io::stdin().is_terminal() {
+                anyhow::bail!(
+                    "Cannot ask before downloading because stdin is not a terminal.\nRun `lignore update` first, or re-run with --yes."
                 );
-                rt.block_on(self.update_cache())
+            }
+            if !confirm("Download them now? [Y/n] ")? {
+                anyhow::bail!("Download cancelled; run `lignore update` when ready.");
             }
         }
+        writeln!(self.out())?;
+        Ok(())
     }
 
-    pub fn list_templates(&self, index: &TemplateIndex) -> Result<()> {
-        let items = index.list();
-        if items.is_empty() {
-            println!("No templates found. Run `lignore update` first.");
-            return Ok(());
+    /// Like [`App::read_index_or_update`], but first downloads again any
+    /// cached template that is missing or no longer matches the hash
+    /// recorded in the index, such as after an interrupted write
+    pub fn read_verified_index(&self, rt: &tokio::runtime::Runtime) -> Result<TemplateIndex> {
+        let mut index = self.read_index_or_update(rt)?;
+        let damaged: Vec<String> = index
+            .templates
+            .iter()
+            .filter(|(_, entry)| !entry.is_intact())
+            .map(|(name, _)| name.clone())
+            .collect();
+        if damaged.is_empty() {
+            return Ok(index);
         }
 
-        let layout = calculate_column_layout(&items)?;
-        print_columnar_list(&items, &layout)
+        if self.options.offline {
+            anyhow::bail!(
+                "Cached templates are missing or do not match their recorded hashes: {}\nRun `lignore update` without --offline to download them again.",
+                damaged.join(", ")
+            );
+        }
+        print_warning(
+            &mut self.out(),
+            &format!(
+                "Cached templates are missing or do not match their recorded hashes, downloading again: {}",
+                damaged.join(", ")
+            ),
+        )?;
+        rt.block_on(self.redownload(&mut index, &damaged))?;
+        Ok(index)
     }
 
-    pub fn generate_interactive(&self, index: &TemplateIndex, output: PathBuf) -> Result<()> {
-        // Validate output path
-        validate_output_path(&output)
-            .with_context(|| format!("validating output path: {}", output.display()))?;
+    /// Checks the cache for templates whose files are missing or modified
+    /// and for template files the index does not reference, then downloads
+    /// the damaged templates again and removes the unreferenced files
+    pub fn repair_cache(&self, rt: &tokio::runtime::Runtime, dry_run: bool) -> Result<()> {
+        let mut index = self.read_index()?;
 
-        let options = index.list();
-        if options.is_empty() {
-            println!("No templates available. Run `lignore update` first.");
+        let mut missing = Vec::new();
+        let mut modified = Vec::new();
+        for (name, entry) in &index.templates {
+            if !Path::new(&entry.path).exists() {
+                missing.push(name.clone());
+            } else if !entry.is_intact() {
+                modified.push(name.clone());
+            }
+        }
+
+        // Only look inside source directories: the cache directory may be
+        // shared with other programs
+        let referenced: BTreeSet<PathBuf> = index
+            .templates
+            .values()
+            .map(|entry| PathBuf::from(&entry.path))
+            .collect();
+        let sources: BTreeSet<&str> = self
+            .settings
+            .sources
+            .iter()
+            .map(|config| config.name.as_str())
+            .chain(index.templates.values().map(|entry| entry.source.as_str()))
+            .filter(|source| !source.is_empty())
+            .collect();
+        let mut orphaned = Vec::new();
+        for source in sources {
+            let Ok(entries) = fs::read_dir(self.cache_dir.join(source)) else {
+                continue;
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "gitignore")
+                    && !referenced.contains(&path)
+                {
+                    orphaned.push(path);
+                }
+            }
+        }
+        orphaned.sort();
+
+        if missing.is_empty() && modified.is_empty() && orphaned.is_empty() {
+            print_success(
+                &mut self.out(),
+                &format!("Cache is intact ({} templates)", index.templates.len()),
+            )?;
             return Ok(());
         }
+        if !missing.is_empty() {
+            print_warning(
+                &mut self.out(),
+                &format!("Missing files: {}", missing.join(", ")),
+            )?;
+        }
+        if !modified.is_empty() {
+            print_warning(
+                &mut self.out(),
+                &format!("Hash mismatches: {}", modified.join(", ")),
+            )?;
+        }
+        if !orphaned.is_empty() {
+            print_warning(
+                &mut self.out(),
+                &format!("Files not in the index: {}", orphaned.len()),
+            )?;
+            for path in &orphaned {
+                writeln!(self.out(), "  {}", path.display())?;
+            }
+        }
+        if dry_run {
+            writeln!(self.out(), "Dry run; nothing was changed.")?;
+            return Ok(());
+        }
+
+        let damaged: Vec<String> = missing.into_iter().chain(modified).collect();
+        if !damaged.is_empty() {
+            if self.options.offline {
+                anyhow::bail!(
+                    "Cannot download damaged templates in offline mode; run `lignore cache repair` without --offline"
+                );
+            }
+            rt.block_on(self.redownload(&mut index, &damaged))?;
+            print_success(
+                &mut self.out(),
+                &format!("Downloaded {} templates again", damaged.len()),
+            )?;
+        }
+        for path in &orphaned {
+            fs::remove_file(path).with_context(|| format!("removing {}", path.display()))?;
+        }
+        if !orphaned.is_empty() {
+            print_success(
+                &mut self.out(),
+                &format!("Removed {} unreferenced files", orphaned.len()),
+            )?;
+        }
+        Ok(())
+    }
 
-        // Load and validate config
-        let config_path = PathBuf::from("lignore.json");
-        let mut config = load_or_default_config(&config_path);
-        validate_config(&options, &config)?;
+    /// Downloads the given templates again from their sources and records
+    /// the hashes of the new content
+    async fn redownload(&self, index: &mut TemplateIndex, names: &[String]) -> Result<()> {
+        let mut by_source: BTreeMap<String, Vec<&String>> = BTreeMap::new();
+        for name in names {
+            if let Some(entry) = index.get(name) {
+                by_source
+                    .entry(entry.source.clone())
+                    .or_default()
+                    .push(name);
+            }
+        }
 
-        // Build options and selection lists
-        let all_options = build_options_list(&options, &config);
-        let previous_selection = build_previous_selection(&options, &config);
+        for (source_name, names) in by_source {
+            let config = self
+                .settings
+                .sources
+                .iter()
+                .find(|config| config.name == source_name)
+                .with_context(|| {
+                    format!(
+                        "source '{}' is no longer configured; run `lignore update`",
+                        source_name
+                    )
+                })?;
+            let source = self.registry.create(config)?;
+            let ctx = SourceContext {
+                client: &self.client,
+                rate_limiter: &self.rate_limiter,
+                verbose: self.options.verbose,
+                previous_count: None,
+                report: Mutex::default(),
+                progress: self.options.progress,
+            };
+            let listed = source.list(&ctx).await?;
+            for name in names {
+                let Some(entry) = index.templates.get_mut(name) else {
+                    continue;
+                };
+                let key = if entry.key.is_empty() {
+                    name.as_str()
+                } else {
+                    entry.key.as_str()
+                };
+                let remote = listed
+                    .iter()
+                    .find(|template| template.key == key)
+                    .with_context(|| {
+                        format!(
+                            "{} is no longer provided by source {}; run `lignore update`",
+                            name, source_name
+                        )
+                    })?;
+                let content = source.fetch(&ctx, remote).await?;
+                fs::write(&entry.path, &content)
+                    .with_context(|| format!("writing template {} to cache", name))?;
+                entry.hash = content_hash(&content);
+                entry.notes = describe(key, &content);
+            }
+        }
 
-        // Interactive selection
-        let selected = match select_templates(&all_options, &previous_selection)? {
-            Some(selection) => selection,
-            None => {
-                println!("Selection cancelled.");
-                return Ok(());
+        // `index` may have hidden templates left out, so the new hashes go
+        // into the index on disk rather than replacing it
+        let mut stored = self.read_index()?;
+        for name in names {
+            if let (Some(entry), Some(updated)) =
+                (stored.templates.get_mut(name), index.templates.get(name))
+            {
+                entry.hash = updated.hash.clone();
+                entry.notes = updated.notes.clone();
             }
-        };
-        if selected.is_empty() {
-            println!("No templates selected.");
+        }
+        stored.write(&self.cache_dir)
+    }
+
+    /// Exports the cache as a static template set with an index.json manifest
+    pub fn mirror(&self, index: &TemplateIndex, dir: &Path) -> Result<()> {
+        let manifest = export_mirror(index, dir)?;
+        writeln!(
+            self.out(),
+            "Exported {} templates to {}",
+            manifest.templates.len(),
+            dir.display()
+        )?;
+        Ok(())
+    }
+
+    /// Checks a mirror copied from elsewhere before a source reads it
+    pub fn verify_mirror(&self, dir: &Path) -> Result<()> {
+        let checked = verify_mirror(dir)?;
+        print_success(
+            &mut self.out(),
+            &format!(
+                "All {} files in {} match {}",
+                checked,
+                dir.display(),
+                CHECKSUMS_FILE
+            ),
+        )?;
+        Ok(())
+    }
+
+    /// Local usage statistics, empty when the `usage_stats` setting is off
+    fn usage_stats(&self) -> UsageStats {
+        if self.settings.usage_stats {
+            UsageStats::read(&self.cache_dir)
+        } else {
+            UsageStats::default()
+        }
+    }
+
+    /// Prints how often templates were generated, from the statistics kept
+    /// in the cache directory
+    pub fn usage_report(&self) -> Result<()> {
+        if !self.settings.usage_stats {
+            writeln!(
+                self.out(),
+                "Usage statistics are turned off; set \"usage_stats\": true in {} to keep them",
+                SETTINGS_FILE
+            )?;
+            return Ok(());
+        }
+        let usage = UsageStats::read(&self.cache_dir);
+        if usage.generations == 0 {
+            writeln!(self.out(), "No generations recorded yet.")?;
             return Ok(());
         }
 
-        // Update and save config
-        update_and_save_config(&config_path, &mut config, &selected)?;
+        let now = now_secs();
+        match usage.since {
+            Some(since) => writeln!(
+                self.out(),
+                "Generations: {} (since {})",
+                usage.generations,
+                format_age(now.saturating_sub(since))
+            )?,
+            None => writeln!(self.out(), "Generations: {}", usage.generations)?,
+        }
+        let most_used = usage.most_used();
+        let width = most_used
+            .iter()
+            .take(MAX_USAGE_REPORT)
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        writeln!(self.out(), "Most used templates:")?;
+        for (name, template) in most_used.iter().take(MAX_USAGE_REPORT) {
+            writeln!(
+                self.out(),
+                "  {:<width$}  {:>4}x, last {}",
+                name,
+                template.count,
+                format_age(now.saturating_sub(template.last_used)),
+                width = width
+            )?;
+        }
+        writeln!(
+            self.out(),
+            "Kept only on this machine, in {}",
+            self.cache_dir.join(USAGE_FILE).display()
+        )?;
+        Ok(())
+    }
 
-        // Ensure output directory exists
-        ensure_output_directory(&output)?;
+    /// Lists templates that usually go with the project's selection and are
+    /// not selected yet
+    pub fn suggest(&self, index: &TemplateIndex) -> Result<()> {
+        let config = load_or_default_config(&self.config_path())?;
+        let available = build_options_list(&index.list(), &config);
+        let selected = build_previous_selection(&index.list(), &config);
+        let suggestions = companions::suggest(&selected, &available);
+        if suggestions.is_empty() {
+            writeln!(self.out(), "No suggestions for the current selection.")?;
+            return Ok(());
+        }
+        for suggestion in &suggestions {
+            writeln!(
+                self.out(),
+                "{} (goes well with {})",
+                suggestion.name,
+                suggestion.because_of
+            )?;
+        }
+        let names: Vec<&str> = suggestions.iter().map(|s| s.name.as_str()).collect();
+        writeln!(
+            self.out(),
+            "\nAdd them with `lignore add {}`",
+            names.join(" ")
+        )?;
+        Ok(())
+    }
 
-        // Generate gitignore content
-        let content = generate_gitignore_content(&selected, index, &config)?;
-        fs::write(&output, content)
-            .with_context(|| format!("writing output file {}", output.display()))?;
+    /// Lists the cached and custom templates containing `pattern`, or a
+    /// pattern equivalent to it such as `**/foo` for `foo`, with the line
+    /// it is on
+    pub fn which(&self, index: &TemplateIndex, pattern: &str) -> Result<()> {
+        let wanted = normalize::normalize_pattern(pattern.trim());
+        if wanted.is_empty() || wanted.starts_with('#') {
+            anyhow::bail!("'{}' is not an ignore pattern", pattern);
+        }
+        let config = load_or_default_config(&self.config_path())?;
+        let mut templates = Vec::new();
+        for name in index.list() {
+            // Quarantined templates are searched too, to find what they hold
+            if let Some(Ok(content)) = index.get(&name).map(|entry| entry.read_verified(&name)) {
+                templates.push((name, content));
+            }
+        }
+        for name in config.custom.keys() {
+            templates.push((
+                format!("{} (custom)", name),
+                load_template_content(name, index, &config)?,
+            ));
+        }
 
-        print_success_message(&output)?;
+        let mut found = false;
+        for (name, content) in &templates {
+            for (number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty()
+                    || line.starts_with('#')
+                    || normalize::normalize_pattern(line) != wanted
+                {
+                    continue;
+                }
+                found = true;
+                if line == pattern.trim() {
+                    writeln!(self.out(), "{} (line {})", name, number + 1)?;
+                } else {
+                    writeln!(self.out(), "{} (line {}: {})", name, number + 1, line)?;
+                }
+            }
+        }
+        if !found {
+            writeln!(self.out(), "No template contains {}", pattern.trim())?;
+        }
         Ok(())
     }
+
+    /// Prints what is known about one template followed by its content
+    pub fn show_template(
+        &self,
+        index: &TemplateIndex,
+        name: &str,
+        with_header: bool,
+    ) -> Result<()> {
+        let config = load_or_default_config(&self.config_path())?;
+        if config.custom.contains_key(name) {
+            writeln!(self.out(), "{} (custom)", name)?;
+            writeln!(
+                self.out(),
+                "  Description: Custom template from lignore.json"
+            )?;
+            writeln!(self.out())?;
+            if with_header {
+                writeln!(self.out(), "{}", section_header_line(name))?;
+            }
+            let content = load_template_content(name, index, &config)?;
+            writeln!(self.out(), "{}", content.trim_end())?;
+            return Ok(());
+        }
+
+        let info = index
+            .resolve(name)
+            .and_then(|name| index.info(name))
+            .with_context(|| {
+                format!(
+                    "Template '{}' does not exist. Run `lignore list` to see available templates.",
+                    name
+                )
+            })?;
+        if info.source.is_empty() {
+            writeln!(self.out(), "{}", info.name)?;
+        } else {
+            writeln!(self.out(), "{} ({})", info.name, info.source)?;
+        }
+        if !info.description.is_empty() {
+            writeln!(self.out(), "  Description: {}", info.description)?;
+        }
+        if let Some(note) = &info.deprecated {
+            let message = match &info.replacement {
+                Some(replacement) => format!("Deprecated: {}; use {} instead", note, replacement),
+                None => format!("Deprecated: {}", note),
+            };
+            print_warning(&mut self.out(), &message)?;
+        }
+        if !info.quarantine.is_empty() {
+            print_warning(
+                &mut self.out(),
+                &format!(
+                    "Quarantined: {}. Add it to `trusted` in the global config to generate it.",
+                    info.quarantine.join("; ")
+                ),
+            )?;
+        }
+        writeln!(self.out(), "  Key:         {}", info.key)?;
+        let tags: Vec<String> = info.tags.iter().map(|tag| format!("@{}", tag)).collect();
+        writeln!(self.out(), "  Tags:        {}", join_or_none(&tags))?;
+        writeln!(self.out(), "  Path:        {}", info.path)?;
+        writeln!(self.out())?;
+        // Reviewing a quarantined template must not replay what it hides
+        let content = match index.get(&info.name) {
+            Some(entry) if entry.is_quarantined() => {
+                safety::reveal(&entry.read_verified(&info.name)?)
+            }
+            _ => load_template_content(&info.name, index, &config)?,
+        };
+        if with_header {
+            writeln!(self.out(), "{}", section_header_line(&info.name))?;
+        }
+        writeln!(self.out(), "{}", content.trim_end())?;
+        Ok(())
+    }
+
+    pub fn list_templates(
+        &self,
+        index: &TemplateIndex,
+        long: bool,
+        json: bool,
+        sort: Option<SortOrder>,
+    ) -> Result<()> {
+        let mut items = index.list();
+        if items.is_empty() && !json {
+            writeln!(
+                self.out(),
+                "No templates found. Run `lignore update` first."
+            )?;
+            return Ok(());
+        }
+
+        match sort.unwrap_or(self.settings.sort) {
+            SortOrder::Name => {}
+            SortOrder::Popular => items.sort_by(|a, b| popularity::compare(a, b, index)),
+            SortOrder::Recent => self.usage_stats().sort_recent(&mut items),
+        }
+
+        if json {
+            return print_json_list(&mut self.out(), index, &items);
+        }
+        if long {
+            return print_long_list(&mut self.out(), index, &items);
+        }
+
+        // One plain name per line for pipelines such as
+        // `lignore list | fzf -m | lignore generate --stdin-templates`
+        if !io::stdout().is_terminal() {
+            let mut out = self.out();
+            for item in &items {
+                match writeln!(out, "{}", item) {
+                    Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                    result => result?,
+                }
+            }
+            return Ok(());
+        }
+
+        let layout = calculate_column_layout(&items)?;
+        print_columnar_list(&mut self.out(), &items, &layout)
+    }
+
+    /// Opens the picker to filter, preview and copy templates without
+    /// selecting any; nothing is written, not even the picker session
+    pub fn browse_templates(&self, index: &TemplateIndex) -> Result<()> {
+        let options = index.list();
+        if options.is_empty() {
+            writeln!(
+                self.out(),
+                "No templates available. Run `lignore update` first."
+            )?;
+            return Ok(());
+        }
+        let config = load_or_default_config(&self.config_path())?;
+        let all_options = build_options_list(&options, &config);
+        // Reviewing a quarantined template must not replay what it hides
+        let load_content = |name: &str| match index.get(name) {
+            Some(entry) if entry.is_quarantined() => {
+                Ok(safety::reveal(&entry.read_verified(name)?))
+            }
+            _ => load_template_content(name, index, &config),
+        };
+        let picker = PickerOptions {
+            popularity: popularity::ranks(&all_options, index),
+            recent: self.usage_stats().recent_ranks(&all_options),
+            descriptions: picker_descriptions(index, &all_options),
+            sort: self.settings.sort,
+            load_content: &load_content,
+            browse: true,
+        };
+        select_templates(&all_options, &[], &picker, &mut PickerSession::default())?;
+        Ok(())
+    }
+
+    pub fn generate_interactive(
+        &self,
+        index: &TemplateIndex,
+        output: PathBuf,
+        kind: IgnoreKind,
+        write: WriteOptions,
+        on_empty: Option<EmptySelection>,
+    ) -> Result<PickerOutcome> {
+        // Validate output path
+        validate_output_path(&output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let options = index.list();
+        if options.is_empty() {
+            writeln!(
+                self.out(),
+                "No templates available. Run `lignore update` first."
+            )?;
+            return Ok(PickerOutcome::Done);
+        }
+
+        // Load and validate config; the lock is held while the picker is
+        // open so a concurrent run cannot change the config underneath it
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        self.validate_project(&options, index, &config)?;
+
+        // Build options and selection lists
+        let all_options = build_options_list(&options, &config);
+        let previous_selection = build_previous_selection(&options, &config);
+        let others = self.other_picker_files(kind, write);
+        let mut tabs = vec![PickerTab {
+            label: output.display().to_string(),
+            selection: previous_selection,
+        }];
+        for file in &others {
+            tabs.push(self.other_picker_tab(file, &all_options, &options, &config, &config_path));
+        }
+        let initial: Vec<BTreeSet<String>> = tabs
+            .iter()
+            .map(|tab| tab.selection.iter().cloned().collect())
+            .collect();
+
+        // Interactive selection
+        let load_content = |name: &str| load_template_content(name, index, &config);
+        let picker = PickerOptions {
+            popularity: popularity::ranks(&all_options, index),
+            recent: self.usage_stats().recent_ranks(&all_options),
+            descriptions: picker_descriptions(index, &all_options),
+            sort: self.settings.sort,
+            load_content: &load_content,
+            browse: false,
+        };
+        let project = std::env::current_dir().context("reading the current directory")?;
+        let mut session = PickerSession::read(&self.cache_dir, &project);
+        let picked = select_tabs(&all_options, tabs, &picker, &mut session)?;
+        if let Err(e) = session.write(&self.cache_dir, &project) {
+            print_warning(
+                &mut self.out(),
+                &format!("Could not save the picker session: {:#}", e),
+            )?;
+        }
+        let mut selections = match picked {
+            Some(selections) => selections,
+            // Nothing has been written yet, and nothing will be
+            None => return Ok(PickerOutcome::Cancelled),
+        };
+        let selected = selections.remove(0);
+
+        // Files in other tabs go first, so personal templates picked for
+        // the .gitignore are added to the excludes block picked here
+        let mut kinds = Vec::new();
+        for ((file, before), after) in others.iter().zip(&initial[1..]).zip(selections) {
+            if after.iter().cloned().collect::<BTreeSet<_>>() == *before {
+                continue;
+            }
+            match file {
+                OtherPickerFile::Kind(other) => {
+                    let templates = after
+                        .into_iter()
+                        .filter(|name| !config.custom.contains_key(name))
+                        .collect();
+                    config.kind_templates.insert(*other, templates);
+                    kinds.push(*other);
+                }
+                OtherPickerFile::Excludes(path) => {
+                    self.replace_personal(index, &config, path, &after)?
+                }
+            }
+        }
+        if !kinds.is_empty() {
+            save_config(&config_path, &config)?;
+            self.write_kinds(index, &config_path, &config, &kinds, &[], write)?;
+        }
+
+        if !selected.is_empty() {
+            self.write_selection(index, &config_path, &mut config, &selected, &output, write)?;
+            return Ok(PickerOutcome::Done);
+        }
+
+        let choice = match on_empty {
+            Some(choice) => choice,
+            None => ask_empty_selection(&mut self.out())?,
+        };
+        match choice {
+            EmptySelection::Keep => {
+                writeln!(self.out(), "No templates selected; nothing was changed.")?
+            }
+            EmptySelection::Empty => {
+                self.write_selection(index, &config_path, &mut config, &[], &output, write)?
+            }
+            EmptySelection::Clear if !self.saves_config(write) => print_warning(
+                &mut self.out(),
+                "Saving lignore.json is turned off; nothing was changed",
+            )?,
+            EmptySelection::Clear => {
+                update_and_save_config(&config_path, &mut config, &[])?;
+                print_success(
+                    &mut self.out(),
+                    &format!(
+                        "Cleared the template list in {}; {} was left as is",
+                        config_path.display(),
+                        output.display()
+                    ),
+                )?;
+            }
+        }
+        Ok(PickerOutcome::Done)
+    }
+
+    /// Generates without the picker from a preset, given as a token, a
+    /// comma-separated list or names read from stdin
+    pub fn generate_from(
+        &self,
+        index: &TemplateIndex,
+        preset: Preset,
+        output: PathBuf,
+        write: WriteOptions,
+    ) -> Result<()> {
+        validate_output_path(&output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        config.custom.extend(preset.custom);
+        self.validate_project(&index.list(), index, &config)?;
+
+        // Custom templates already in lignore.json may be named too
+        let preset = Preset {
+            templates: preset.templates,
+            custom: config.custom.clone(),
+        };
+        let selected = preset::resolve_templates(&preset, index)?;
+        self.write_selection(index, &config_path, &mut config, &selected, &output, write)
+    }
+
+    /// Writes a first lignore.json with the templates the project's files
+    /// suggest, such as Rust for Cargo.toml, and generates the output from
+    /// it. An output file lightignore did not write keeps its lines and
+    /// gets the missing patterns appended.
+    pub fn init(&self, index: &TemplateIndex, output: PathBuf) -> Result<()> {
+        let config_path = self.config_path();
+        if config_path.exists() {
+            anyhow::bail!(
+                "{} already exists; run `lignore generate` to change its templates",
+                config_path.display()
+            );
+        }
+        let dir = config_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let detected: Vec<Detected> = detect(dir)
+            .into_iter()
+            .filter(|found| index.resolve(found.template).is_some())
+            .collect();
+        if detected.is_empty() {
+            anyhow::bail!(
+                "No files here suggest a template; run `lignore generate` to pick them yourself"
+            );
+        }
+
+        writeln!(self.out(), "Detected:")?;
+        for found in &detected {
+            writeln!(self.out(), "  {} ({})", found.template, found.marker)?;
+        }
+        if !self.options.assume_yes {
+            if !io::stdin().is_terminal() {
+                anyhow::bail!(
+                    "Cannot ask before writing {} because stdin is not a terminal; re-run with --yes",
+                    config_path.display()
+                );
+            }
+            let prompt = format!(
+                "Create {} and {} with these templates? [Y/n] ",
+                config_path.display(),
+                output.display()
+            );
+            if !confirm(&prompt)? {
+                writeln!(self.out(), "Nothing was written.")?;
+                return Ok(());
+            }
+        }
+
+        let hand_written = fs::read_to_string(&output)
+            .is_ok_and(|content| !content.starts_with(managed::GENERATED_HEADER));
+        let preset = Preset {
+            templates: detected
+                .iter()
+                .map(|found| found.template.to_string())
+                .collect(),
+            custom: BTreeMap::new(),
+        };
+        let write = WriteOptions {
+            append: hand_written,
+            ..Default::default()
+        };
+        self.generate_from(index, preset, output, write)
+    }
+
+    /// The templates of a recipe, from the `recipes` setting or built in
+    pub fn recipe(&self, name: &str) -> Result<Preset> {
+        recipe::preset(name, &self.settings.recipes)
+    }
+
+    /// Whether `preset`, and what lignore.json already selects, consist only
+    /// of custom templates, so generating needs nothing from the cache
+    pub fn is_custom_only(&self, preset: &Preset) -> Result<bool> {
+        let config = load_or_default_config(&self.config_path())?;
+        let is_custom =
+            |name: &String| preset.custom.contains_key(name) || config.custom.contains_key(name);
+        Ok(!preset.templates.is_empty()
+            && preset.templates.iter().all(is_custom)
+            && config.templates.iter().all(is_custom))
+    }
+
+    /// Generates the output from every custom template in lignore.json,
+    /// without reading or downloading the template cache
+    pub fn generate_custom_only(&self, output: PathBuf, write: WriteOptions) -> Result<()> {
+        validate_output_path(&output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        if config.custom.is_empty() {
+            anyhow::bail!(
+                "{} defines no custom templates; add them to its 'custom' section",
+                config_path.display()
+            );
+        }
+        let official: Vec<&str> = config
+            .templates
+            .iter()
+            .filter(|name| !config.custom.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !official.is_empty() {
+            anyhow::bail!(
+                "{} also selects templates from the cache: {}\nRun without --only-custom to include them.",
+                config_path.display(),
+                official.join(", ")
+            );
+        }
+
+        self.warn_shared_outputs(&config)?;
+
+        let index = TemplateIndex::new();
+        let selected: Vec<String> = config.custom.keys().cloned().collect();
+        self.write_selection(&index, &config_path, &mut config, &selected, &output, write)
+    }
+
+    /// Generates the file of each of `kinds` from lignore.json in one run,
+    /// using `kind_templates` for kinds that have their own list. Every
+    /// template is read from the cache once for all kinds, and the files are
+    /// built in parallel. Cached content is used as is, as with
+    /// `--refresh-changed`.
+    pub fn generate_kinds(
+        &self,
+        index: &TemplateIndex,
+        kinds: &[IgnoreKind],
+        write: WriteOptions,
+    ) -> Result<()> {
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let config = load_or_default_config(&config_path)?;
+        self.validate_project(&index.list(), index, &config)?;
+
+        let (_, personal) = self.split_personal(&config.templates)?;
+        self.write_kinds(index, &config_path, &config, kinds, &personal, write)?;
+
+        if kinds.contains(&IgnoreKind::Gitignore) && !personal.is_empty() {
+            self.write_personal(index, &config, &personal)?;
+        }
+        if self.settings.usage_stats {
+            let mut usage = UsageStats::read(&self.cache_dir);
+            usage.record(&config.templates);
+            if let Err(e) = usage.write(&self.cache_dir) {
+                print_warning(
+                    &mut self.out(),
+                    &format!("Could not save usage statistics: {:#}", e),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates and writes the file of each of `kinds` from `config`,
+    /// leaving `personal` templates out of the .gitignore
+    fn write_kinds(
+        &self,
+        index: &TemplateIndex,
+        config_path: &Path,
+        config: &LignoreConfig,
+        kinds: &[IgnoreKind],
+        personal: &[String],
+        write: WriteOptions,
+    ) -> Result<()> {
+        let mut jobs = Vec::new();
+        for &kind in kinds.iter().collect::<BTreeSet<_>>() {
+            let output = config.output_for(kind, config_path);
+            validate_output_path(&output)
+                .with_context(|| format!("validating output path: {}", output.display()))?;
+            // Personal templates go to git's excludes file instead
+            let templates: Vec<String> = config
+                .templates_for(kind)
+                .iter()
+                .filter(|name| kind != IgnoreKind::Gitignore || !personal.contains(name))
+                .cloned()
+                .collect();
+            jobs.push((kind, output, templates));
+        }
+
+        let mut contents = BTreeMap::new();
+        for (_, _, templates) in &jobs {
+            for name in templates {
+                if !contents.contains_key(name) {
+                    contents.insert(name.clone(), load_template_content(name, index, config)?);
+                }
+            }
+        }
+
+        let generated: Vec<Result<String>> = thread::scope(|scope| {
+            let handles: Vec<_> = jobs
+                .iter()
+                .map(|(_, _, templates)| {
+                    let contents = &contents;
+                    scope.spawn(move || {
+                        let builder = GenerateBuilder::new(index)
+                            .config(config)
+                            .normalize(config.normalize)
+                            .templates(templates);
+                        let builder = templates.iter().fold(builder, |builder, name| {
+                            builder.content_for(name, contents[name].as_str())
+                        });
+                        Ok(builder.build()?.content)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("generating panicked")))
+                })
+                .collect()
+        });
+
+        for ((kind, output, templates), content) in jobs.iter().zip(generated) {
+            let content = content.with_context(|| format!("generating {}", kind.file_name()))?;
+            ensure_output_directory(output)?;
+            write_atomic(output, &content)
+                .with_context(|| format!("writing output file {}", output.display()))?;
+            if *kind == IgnoreKind::Gitignore && self.saves_config(write) {
+                let mut lock = ProjectLock::default();
+                for name in templates {
+                    if let Some(entry) = index.get(name) {
+                        let locked = LockedTemplate {
+                            hash: content_hash(&contents[name]),
+                            source: entry.source.clone(),
+                        };
+                        lock.templates.insert(name.clone(), locked);
+                    }
+                }
+                lock.write(&self.lock_path())?;
+            }
+            print_success_message(&mut self.out(), output)?;
+            self.warn_if_large(output, &content)?;
+            if *kind == IgnoreKind::Gitignore && write.check_tracked {
+                warn_tracked_ignored(&mut self.out(), output)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the current project's selection as a shareable preset token
+    pub fn export_preset(&self) -> Result<()> {
+        let config_path = self.config_path();
+        if !config_path.exists() {
+            anyhow::bail!(
+                "No {} found. Run `lignore generate` to create one first.",
+                config_path.display()
+            );
+        }
+        let config = load_or_default_config(&config_path)?;
+        let token = preset::encode(&Preset::from_config(&config))?;
+        writeln!(self.out(), "{}", token)?;
+        writeln!(self.out())?;
+        writeln!(self.out(), "Generate from it with:")?;
+        writeln!(self.out(), "  lignore generate --from {}", token)?;
+        Ok(())
+    }
+
+    /// Describes the project's selected templates for its documentation,
+    /// printed or written to `report`
+    pub fn policy_report(
+        &self,
+        index: &TemplateIndex,
+        format: ReportFormat,
+        output: &Path,
+        report: Option<&Path>,
+    ) -> Result<()> {
+        let config = load_or_default_config(&self.config_path())?;
+        let mut templates = Vec::new();
+        for name in &config.templates {
+            let content = load_template_content(name, index, &config)?;
+            let template = if config.custom.contains_key(name) {
+                PolicyTemplate {
+                    name: name.clone(),
+                    origin: "lignore.json".to_string(),
+                    description: "Custom template from lignore.json".to_string(),
+                    deprecated: None,
+                    content,
+                }
+            } else if let Some(info) = index.info(name) {
+                PolicyTemplate {
+                    name: name.clone(),
+                    origin: info.source,
+                    description: info.description,
+                    deprecated: info.deprecated,
+                    content,
+                }
+            } else {
+                print_warning(
+                    &mut self.out(),
+                    &format!("{} is not in the cache; left out of the report", name),
+                )?;
+                continue;
+            };
+            templates.push(template);
+        }
+
+        let text = match format {
+            ReportFormat::Markdown => {
+                policy::render_markdown(&output.display().to_string(), &templates)
+            }
+        };
+        match report {
+            Some(path) => {
+                validate_output_path(path)
+                    .with_context(|| format!("validating report path: {}", path.display()))?;
+                write_atomic(path, &text)
+                    .with_context(|| format!("writing report {}", path.display()))?;
+                print_success(&mut self.out(), &format!("Wrote {}", path.display()))?;
+            }
+            None => write!(self.out(), "{}", text)?,
+        }
+        Ok(())
+    }
+
+    /// Reports patterns in the output file that are not in canonical form
+    /// or repeat an earlier one, and rewrites the file with `fix`. Returns
+    /// whether issues were found and left in place.
+    pub fn lint(&self, output: &Path, fix: bool) -> Result<bool> {
+        let content =
+            fs::read_to_string(output).with_context(|| format!("reading {}", output.display()))?;
+        let lint = normalize::lint(&content);
+        if lint.issues.is_empty() {
+            print_success(
+                &mut self.out(),
+                &format!("{} has no patterns to normalize", output.display()),
+            )?;
+            return Ok(false);
+        }
+
+        for issue in &lint.issues {
+            let message = match issue {
+                LintIssue::Rewrite {
+                    line,
+                    pattern,
+                    canonical,
+                } => format!("line {}: {} can be written {}", line, pattern, canonical),
+                LintIssue::Duplicate {
+                    line,
+                    pattern,
+                    first,
+                } => format!("line {}: {} repeats line {}", line, pattern, first),
+            };
+            writeln!(self.out(), "{}: {}", output.display(), message)?;
+        }
+        if !fix {
+            writeln!(
+                self.out(),
+                "{} issue(s); run `lignore lint --fix` to fix them",
+                lint.issues.len()
+            )?;
+            return Ok(true);
+        }
+
+        validate_output_path(output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+        write_atomic(output, &lint.fixed)
+            .with_context(|| format!("writing output file {}", output.display()))?;
+        print_success(
+            &mut self.out(),
+            &format!(
+                "Fixed {} issue(s) in {}",
+                lint.issues.len(),
+                output.display()
+            ),
+        )?;
+        Ok(false)
+    }
+
+    /// Removes what lightignore generated in the output file, and
+    /// lignore.json when `remove_config` is set
+    pub fn clean(&self, output: &Path, remove_config: bool) -> Result<()> {
+        let _guard = ProjectGuard::acquire(&self.config_path())?;
+        match fs::read_to_string(output) {
+            Err(_) => writeln!(self.out(), "{} does not exist", output.display())?,
+            Ok(content) => {
+                if let Some(rest) = managed::remove(&content, &managed::PROJECT) {
+                    if rest.trim().is_empty() {
+                        fs::remove_file(output)
+                            .with_context(|| format!("removing {}", output.display()))?;
+                        print_success(&mut self.out(), &format!("Removed {}", output.display()))?;
+                    } else {
+                        write_atomic(output, rest)?;
+                        print_success(
+                            &mut self.out(),
+                            &format!("Removed the lightignore block from {}", output.display()),
+                        )?;
+                    }
+                } else if content.starts_with(managed::GENERATED_HEADER) {
+                    // Lines edited or added by hand would be lost with the file
+                    if !self.is_up_to_date(output) && !self.confirm_clean_edited(output)? {
+                        writeln!(self.out(), "{} was left untouched", output.display())?;
+                        return Ok(());
+                    }
+                    fs::remove_file(output)
+                        .with_context(|| format!("removing {}", output.display()))?;
+                    print_success(&mut self.out(), &format!("Removed {}", output.display()))?;
+                } else {
+                    print_warning(
+                        &mut self.out(),
+                        &format!(
+                            "{} was not generated by lightignore; left untouched",
+                            output.display()
+                        ),
+                    )?;
+                }
+            }
+        }
+
+        if remove_config {
+            for path in [self.config_path(), self.lock_path()] {
+                if path.exists() {
+                    fs::remove_file(&path)
+                        .with_context(|| format!("removing {}", path.display()))?;
+                    print_success(&mut self.out(), &format!("Removed {}", path.display()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks before `clean` removes a generated file that no longer matches
+    /// what lignore.json generates, unless --yes was given
+    fn confirm_clean_edited(&self, output: &Path) -> Result<bool> {
+        if self.options.assume_yes {
+            return Ok(true);
+        }
+        if !io::stdin().is_terminal() {
+            anyhow::bail!(
+                "{} has changes that regenerating it would not make; see them with `lignore diff`, or re-run with --yes to remove it anyway",
+                output.display()
+            );
+        }
+        let answer = ask(&format!(
+            "{} has changes that regenerating it would not make. Remove it anyway? [y/N] ",
+            output.display()
+        ))?;
+        Ok(answer == "y" || answer == "yes")
+    }
+
+    /// Splits a selection into templates for the repository and personal
+    /// ones configured to go to the global excludes file
+    fn split_personal(&self, selected: &[String]) -> Result<(Vec<String>, Vec<String>)> {
+        if self.settings.personal.is_empty() {
+            return Ok((selected.to_vec(), Vec::new()));
+        }
+        let personal = expand_tags(&self.settings.personal, selected)?;
+        Ok(selected
+            .iter()
+            .cloned()
+            .partition(|name| !personal.contains(name)))
+    }
+
+    /// Leaves out of `selected` the operating system and editor templates
+    /// that would be written to the repository, for `--no-personal`
+    fn without_personal(&self, selected: &[String]) -> Result<Vec<String>> {
+        let (_, personal) = self.split_personal(selected)?;
+        let (kept, dropped): (Vec<String>, Vec<String>) = selected
+            .iter()
+            .cloned()
+            .partition(|name| personal.contains(name) || !tags::is_personal(name));
+        if !dropped.is_empty() {
+            print_warning(
+                &mut self.out(),
+                &format!(
+                    "Left out {}: OS and editor files belong in your global excludes; list them under `personal` in the global config to have lignore put them there",
+                    dropped.join(", ")
+                ),
+            )?;
+        }
+        Ok(kept)
+    }
+
+    /// Adds personal templates to the managed block of the user's global
+    /// excludes file, keeping ones added from other projects
+    fn write_personal(
+        &self,
+        index: &TemplateIndex,
+        config: &LignoreConfig,
+        personal: &[String],
+    ) -> Result<()> {
+        let path = excludes_file_path()?;
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let mut names: Vec<String> = read_personal_templates(&existing)
+            .into_iter()
+            .filter(|name| index.get(name).is_some() || config.custom.contains_key(name))
+            .collect();
+        for name in personal {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+
+        let content = generate_gitignore_content(&names, index, config)?;
+        write_personal_block(&path, &content)?;
+        print_success(
+            &mut self.out(),
+            &format!(
+                "Added {} to global excludes {}",
+                personal.join(", "),
+                path.display()
+            ),
+        )
+    }
+
+    /// Files the picker edits in tabs besides `kind`'s: from a .gitignore,
+    /// the other kinds of lignore.json and the global excludes file
+    fn other_picker_files(&self, kind: IgnoreKind, write: WriteOptions) -> Vec<OtherPickerFile> {
+        if kind != IgnoreKind::Gitignore || write.append {
+            return Vec::new();
+        }
+        let mut files = Vec::new();
+        // The selections of other kinds are remembered in lignore.json
+        if self.saves_config(write) {
+            for other in [IgnoreKind::Dockerignore, IgnoreKind::Npmignore] {
+                files.push(OtherPickerFile::Kind(other));
+            }
+        }
+        if let Ok(path) = excludes_file_path() {
+            files.push(OtherPickerFile::Excludes(path));
+        }
+        files
+    }
+
+    /// The tab for `file`, checking what it currently holds
+    fn other_picker_tab(
+        &self,
+        file: &OtherPickerFile,
+        all_options: &[String],
+        options: &[String],
+        config: &LignoreConfig,
+        config_path: &Path,
+    ) -> PickerTab {
+        match file {
+            OtherPickerFile::Kind(kind) => PickerTab {
+                label: config.output_for(*kind, config_path).display().to_string(),
+                // Custom templates go into every kind, as into the .gitignore
+                selection: config
+                    .templates_for(*kind)
+                    .iter()
+                    .filter(|name| options.contains(name))
+                    .chain(config.custom.keys())
+                    .cloned()
+                    .collect(),
+            },
+            OtherPickerFile::Excludes(path) => {
+                let existing = fs::read_to_string(path).unwrap_or_default();
+                PickerTab {
+                    label: "global excludes".to_string(),
+                    selection: read_personal_templates(&existing)
+                        .into_iter()
+                        .filter(|name| all_options.contains(name))
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    /// Replaces the templates in the managed block of the global excludes
+    /// file with `names`, removing the block when there are none
+    fn replace_personal(
+        &self,
+        index: &TemplateIndex,
+        config: &LignoreConfig,
+        path: &PathBuf,
+        names: &[String],
+    ) -> Result<()> {
+        if names.is_empty() {
+            remove_personal_block(path)?;
+            return print_success(
+                &mut self.out(),
+                &format!(
+                    "Removed every template from global excludes {}",
+                    path.display()
+                ),
+            );
+        }
+        let content = generate_gitignore_content(names, index, config)?;
+        write_personal_block(path, &content)?;
+        print_success(
+            &mut self.out(),
+            &format!(
+                "Updated global excludes {}: {}",
+                path.display(),
+                names.join(", ")
+            ),
+        )
+    }
+
+    /// Compares the selected templates with the lockfile and, for those that
+    /// changed since the last generation, either takes the new content or
+    /// keeps the section already in the output file. Returns the kept
+    /// sections and the lock describing the content that will be written.
+    fn resolve_changed(
+        &self,
+        index: &TemplateIndex,
+        config: &LignoreConfig,
+        selected: &[String],
+        output: &Path,
+        refresh: bool,
+    ) -> Result<(BTreeMap<String, String>, ProjectLock)> {
+        let previous = ProjectLock::read(&self.lock_path())?.unwrap_or_default();
+        let mut lock = ProjectLock::default();
+        let mut changed = Vec::new();
+        for name in selected {
+            let Some(entry) = index.get(name) else {
+                continue;
+            };
+            let content = load_template_content(name, index, config)?;
+            let locked = LockedTemplate {
+                hash: content_hash(&content),
+                source: entry.source.clone(),
+            };
+            if let Some(old) = previous.templates.get(name)
+                && old.hash != locked.hash
+            {
+                changed.push((name.clone(), old.clone()));
+            }
+            lock.templates.insert(name.clone(), locked);
+        }
+
+        let mut overrides = BTreeMap::new();
+        if changed.is_empty() {
+            return Ok((overrides, lock));
+        }
+
+        let names: Vec<&str> = changed.iter().map(|(name, _)| name.as_str()).collect();
+        writeln!(
+            self.out(),
+            "Changed upstream since the last generation: {}",
+            names.join(", ")
+        )?;
+        let refresh = if refresh {
+            true
+        } else if io::stdin().is_terminal() {
+            confirm("Update them to the latest content? [Y/n] ")?
+        } else {
+            writeln!(
+                self.out(),
+                "Keeping their previous content; pass --refresh-changed to update them."
+            )?;
+            false
+        };
+        if refresh {
+            return Ok((overrides, lock));
+        }
+
+        let sections = fs::read_to_string(output)
+            .map(|content| read_sections(&content))
+            .unwrap_or_default();
+        for (name, old) in changed {
+            match sections.get(&name) {
+                Some(section) => {
+                    overrides.insert(name.clone(), section.clone());
+                    lock.templates.insert(name, old);
+                }
+                None => print_warning(
+                    &mut self.out(),
+                    &format!(
+                        "{} has no previous section in {}; using the latest content",
+                        name,
+                        output.display()
+                    ),
+                )?,
+            }
+        }
+        Ok((overrides, lock))
+    }
+
+    /// Warns about kinds whose files would be written to the same path, or
+    /// refuses them with `--strict`
+    fn warn_shared_outputs(&self, config: &LignoreConfig) -> Result<()> {
+        for (first, second, path) in shared_outputs(config) {
+            let message = format!(
+                "{} and {} are both written to {}; generating one replaces the other",
+                first,
+                second,
+                path.display()
+            );
+            if self.options.strict {
+                anyhow::bail!("{}", message);
+            }
+            print_warning(&mut self.out(), &message)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the project config, then warns about kinds configured to
+    /// share an output file and about custom templates that nearly duplicate
+    /// an official one and so miss its upstream fixes
+    fn validate_project(
+        &self,
+        options: &[String],
+        index: &TemplateIndex,
+        config: &LignoreConfig,
+    ) -> Result<()> {
+        validate_config(options, config)?;
+        self.warn_shared_outputs(config)?;
+        for (name, lines) in &config.custom {
+            let custom = lines.join("\n");
+            let closest = options
+                .iter()
+                .filter_map(|official| {
+                    let content = load_template_content(official, index, config).ok()?;
+                    Some((official, pattern_similarity(&custom, &content), content))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((official, similarity, content)) = closest else {
+                continue;
+            };
+            if similarity < SIMILAR_TEMPLATE_THRESHOLD {
+                continue;
+            }
+            let extra = unique_patterns(&custom, &content);
+            let suggestion = if extra.is_empty() {
+                format!("select {} instead", official)
+            } else {
+                format!(
+                    "select {} and keep only its {} extra pattern(s) ({}) in {}",
+                    official,
+                    extra.len(),
+                    extra.join(", "),
+                    name
+                )
+            };
+            print_warning(
+                &mut self.out(),
+                &format!(
+                    "Custom template {} is {:.0}% identical to the official {}; {}",
+                    name,
+                    similarity * 100.0,
+                    official,
+                    suggestion
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Warns about selected templates that upstream deprecated, naming the
+    /// template to select instead
+    fn warn_deprecated(&self, index: &TemplateIndex, selected: &[String]) -> Result<()> {
+        for name in selected {
+            if let Some(notice) = index.deprecation_notice(name) {
+                print_warning(&mut self.out(), &notice)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a generation records its selection, which `--no-save-config`
+    /// or the `save_config` setting turn off
+    fn saves_config(&self, write: WriteOptions) -> bool {
+        self.settings.save_config && !write.skip_config
+    }
+
+    /// Saves the selection to lignore.json and writes the .gitignore
+    fn write_selection(
+        &self,
+        index: &TemplateIndex,
+        config_path: &PathBuf,
+        config: &mut LignoreConfig,
+        selected: &[String],
+        output: &Path,
+        write: WriteOptions,
+    ) -> Result<()> {
+        let kept;
+        let selected = if write.no_personal {
+            kept = self.without_personal(selected)?;
+            &kept
+        } else {
+            selected
+        };
+        // Before lignore.json records a selection that cannot be generated
+        for name in selected {
+            ensure_not_quarantined(name, index)?;
+        }
+        self.warn_deprecated(index, selected)?;
+        let save_config = self.saves_config(write);
+        if save_config {
+            update_and_save_config(config_path, config, selected)?;
+        }
+
+        // Ensure output directory exists
+        ensure_output_directory(output)?;
+
+        // Generate gitignore content
+        let (repo, personal) = self.split_personal(selected)?;
+        let (overrides, lock) =
+            self.resolve_changed(index, config, &repo, output, write.refresh_changed)?;
+        let mut content = generate_gitignore_content_with(&repo, index, config, &overrides)?;
+        let mut appended = None;
+        if write.append && output.exists() {
+            let existing = fs::read_to_string(output)
+                .with_context(|| format!("reading output file {}", output.display()))?;
+            let (merged, added) = append_missing(&existing, &content);
+            content = merged;
+            appended = Some(added);
+        }
+        if appended != Some(0) {
+            write_atomic(output, &content)
+                .with_context(|| format!("writing output file {}", output.display()))?;
+        }
+        if save_config {
+            lock.write(&self.lock_path())?;
+        }
+        if self.settings.usage_stats {
+            let mut usage = UsageStats::read(&self.cache_dir);
+            usage.record(selected);
+            if let Err(e) = usage.write(&self.cache_dir) {
+                print_warning(
+                    &mut self.out(),
+                    &format!("Could not save usage statistics: {:#}", e),
+                )?;
+            }
+        }
+
+        match appended {
+            None => print_success_message(&mut self.out(), output)?,
+            Some(0) => print_success(
+                &mut self.out(),
+                &format!(
+                    "{} already has every pattern of the selection; nothing was added",
+                    output.display()
+                ),
+            )?,
+            Some(added) => print_success(
+                &mut self.out(),
+                &format!("Added {} pattern(s) to {}", added, output.display()),
+            )?,
+        }
+        if appended != Some(0) {
+            self.warn_if_large(output, &content)?;
+        }
+        if !personal.is_empty() {
+            self.write_personal(index, config, &personal)?;
+        }
+        if write.check_tracked {
+            warn_tracked_ignored(&mut self.out(), output)?;
+        }
+        if write.copy {
+            // The file is already written, so a missing clipboard is not fatal
+            match copy_text(&content) {
+                Ok(()) => print_success(&mut self.out(), "Copied to clipboard")?,
+                Err(e) => print_warning(
+                    &mut self.out(),
+                    &format!("Could not copy to clipboard: {:#}", e),
+                )?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Warns when the generated file exceeds the `warn_output_lines` or
+    /// `warn_output_size` limits, naming the templates that add the most
+    fn warn_if_large(&self, output: &Path, content: &str) -> Result<()> {
+        let limits = get_limits();
+        let lines = content.lines().count();
+        let size = content.len() as u64;
+        if lines <= limits.warn_output_lines && size <= limits.warn_output_size {
+            return Ok(());
+        }
+
+        let mut sections: Vec<(String, usize)> = read_sections(content)
+            .into_iter()
+            .map(|(name, body)| (name, body.lines().count()))
+            .collect();
+        sections.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let largest: Vec<String> = sections
+            .iter()
+            .take(3)
+            .map(|(name, lines)| format!("{} ({} lines)", name, lines))
+            .collect();
+        print_warning(
+            &mut self.out(),
+            &format!(
+                "{} has {} lines ({}); ignore files this large slow down `git status` in big repositories",
+                output.display(),
+                lines,
+                format_size(size)
+            ),
+        )?;
+        writeln!(
+            self.out(),
+            "  Set \"normalize\": true in lignore.json to drop equivalent patterns, or deselect templates you do not need. Largest: {}",
+            join_or_none(&largest)
+        )?;
+        Ok(())
+    }
+
+    /// Adds templates (or whole `@tag` groups) to lignore.json without the
+    /// picker and appends their sections to the output file, leaving every
+    /// other line as it is. Templates whose section the file already has
+    /// are refused, or with `force` get their section replaced.
+    pub fn add_templates(
+        &self,
+        index: &TemplateIndex,
+        names: &[String],
+        output: PathBuf,
+        force: bool,
+    ) -> Result<()> {
+        validate_output_path(&output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let options = index.list();
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        self.validate_project(&options, index, &config)?;
+
+        let all_options = build_options_list(&options, &config);
+        let requested = expand_tags(names, &all_options)?;
+        if requested.is_empty() {
+            writeln!(self.out(), "No available templates match the given tags.")?;
+            return Ok(());
+        }
+
+        let missing: Vec<&String> = requested
+            .iter()
+            .filter(|name| !all_options.contains(name))
+            .collect();
+        if !missing.is_empty() {
+            let mut error_msg = String::from("The following templates do not exist:\n");
+            for name in missing {
+                error_msg.push_str(&format!("  - {}\n", name));
+            }
+            error_msg.push_str("\nRun `lignore list` to see available templates.");
+            anyhow::bail!(error_msg);
+        }
+
+        let existing = if output.exists() {
+            fs::read_to_string(&output)
+                .with_context(|| format!("reading output file {}", output.display()))?
+        } else {
+            String::new()
+        };
+        let present = read_sections(&existing);
+        let already: Vec<&str> = requested
+            .iter()
+            .filter(|name| present.contains_key(*name))
+            .map(String::as_str)
+            .collect();
+        if !already.is_empty() && !force {
+            anyhow::bail!(
+                "{} already has a section for {}; pass --force to replace it",
+                output.display(),
+                already.join(", ")
+            );
+        }
+        for name in &requested {
+            ensure_not_quarantined(name, index)?;
+        }
+        self.warn_deprecated(index, &requested)?;
+
+        let mut selected = build_previous_selection(&options, &config);
+        for name in &requested {
+            if !selected.contains(name) {
+                selected.push(name.clone());
+            }
+        }
+        let (repo, _) = self.split_personal(&selected)?;
+        let (added, personal) = self.split_personal(&requested)?;
+
+        // Generated with the sections already in the file, so the new ones
+        // leave out patterns those have, as a full generation would
+        let in_file: Vec<String> = repo
+            .iter()
+            .filter(|name| present.contains_key(*name) || added.contains(name))
+            .cloned()
+            .collect();
+        let sections = read_sections(&generate_gitignore_content(&in_file, index, &config)?);
+        let mut content = existing;
+        for name in &added {
+            let body = sections.get(name).map(String::as_str).unwrap_or_default();
+            let template = load_template_content(name, index, &config)?;
+            content = put_section(&content, name, body, &template);
+        }
+
+        if !added.is_empty() {
+            ensure_output_directory(&output)?;
+            write_atomic(&output, refresh_templates_line(&content))
+                .with_context(|| format!("writing output file {}", output.display()))?;
+        }
+        if self.saves_config(WriteOptions::default()) {
+            update_and_save_config(&config_path, &mut config, &selected)?;
+            let mut lock = ProjectLock::read(&self.lock_path())?.unwrap_or_default();
+            for name in &added {
+                if let Some(entry) = index.get(name) {
+                    let locked = LockedTemplate {
+                        hash: content_hash(&load_template_content(name, index, &config)?),
+                        source: entry.source.clone(),
+                    };
+                    lock.templates.insert(name.clone(), locked);
+                }
+            }
+            lock.write(&self.lock_path())?;
+        }
+        if self.settings.usage_stats {
+            let mut usage = UsageStats::read(&self.cache_dir);
+            usage.record(&requested);
+            if let Err(e) = usage.write(&self.cache_dir) {
+                print_warning(
+                    &mut self.out(),
+                    &format!("Could not save usage statistics: {:#}", e),
+                )?;
+            }
+        }
+
+        let (replaced, appended): (Vec<&String>, Vec<&String>) =
+            added.iter().partition(|name| present.contains_key(*name));
+        if !appended.is_empty() {
+            let names: Vec<&str> = appended.iter().map(|name| name.as_str()).collect();
+            print_success(
+                &mut self.out(),
+                &format!("Added {} to {}", names.join(", "), output.display()),
+            )?;
+        }
+        if !replaced.is_empty() {
+            let names: Vec<&str> = replaced.iter().map(|name| name.as_str()).collect();
+            print_success(
+                &mut self.out(),
+                &format!("Replaced {} in {}", names.join(", "), output.display()),
+            )?;
+        }
+        if !personal.is_empty() {
+            self.write_personal(index, &config, &personal)?;
+        }
+        Ok(())
+    }
+
+    /// Removes the sections of templates (or selected `@tag` groups) from
+    /// the output file and drops them from lignore.json and lignore.lock,
+    /// leaving every other line as it is
+    pub fn remove_templates(&self, names: &[String], output: &Path) -> Result<()> {
+        validate_output_path(output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        let existing = if output.exists() {
+            fs::read_to_string(output)
+                .with_context(|| format!("reading output file {}", output.display()))?
+        } else {
+            String::new()
+        };
+        let present = read_sections(&existing);
+
+        let mut known: Vec<String> = config.templates.clone();
+        for name in present.keys() {
+            if !known.contains(name) {
+                known.push(name.clone());
+            }
+        }
+        let requested = expand_tags(names, &known)?;
+        if let Some(name) = requested
+            .iter()
+            .find(|name| config.custom.contains_key(*name))
+        {
+            anyhow::bail!(
+                "{} is a custom template; delete it from `custom` in {} instead",
+                name,
+                config_path.display()
+            );
+        }
+        let unknown: Vec<&str> = requested
+            .iter()
+            .filter(|name| !known.contains(name))
+            .map(String::as_str)
+            .collect();
+        if !unknown.is_empty() {
+            anyhow::bail!(
+                "Neither {} nor {} has {}",
+                output.display(),
+                config_path.display(),
+                unknown.join(", ")
+            );
+        }
+
+        // The cached templates tell where the last section ends; without
+        // them it ends at its first blank line
+        let index = self.read_index().ok();
+        let mut content = existing;
+        let mut removed = Vec::new();
+        for name in &requested {
+            let template = index
+                .as_ref()
+                .and_then(|index| load_template_content(name, index, &config).ok())
+                .filter(|template| !template.is_empty());
+            if let Some(rest) = remove_section(&content, name, template.as_deref()) {
+                content = rest;
+                removed.push(name.as_str());
+            }
+        }
+        if !removed.is_empty() {
+            write_atomic(output, refresh_templates_line(&content))
+                .with_context(|| format!("writing output file {}", output.display()))?;
+        }
+        if self.saves_config(WriteOptions::default()) && config_path.exists() {
+            config.templates.retain(|name| !requested.contains(name));
+            save_config(&config_path, &config)?;
+            if let Some(mut lock) = ProjectLock::read(&self.lock_path())? {
+                lock.templates.retain(|name, _| !requested.contains(name));
+                lock.write(&self.lock_path())?;
+            }
+        }
+
+        if removed.is_empty() {
+            writeln!(
+                self.out(),
+                "{} has no section for {}",
+                output.display(),
+                requested.join(", ")
+            )?;
+        } else {
+            print_success(
+                &mut self.out(),
+                &format!("Removed {} from {}", removed.join(", "), output.display()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a bundle manifest from a URL or file
+    pub async fn read_bundle(&self, location: &str) -> Result<Bundle> {
+        if self.options.offline && location.starts_with("https://") {
+            anyhow::bail!("Cannot download bundles in offline mode");
+        }
+        bundle::read_bundle(&self.client, location).await
+    }
+
+    /// Installs a bundle: adds its templates to lignore.json in the order
+    /// the bundle lists them, stores its patterns as a custom template named
+    /// after the bundle and regenerates the output file
+    pub fn add_bundle(
+        &self,
+        index: &TemplateIndex,
+        bundle: &Bundle,
+        output: PathBuf,
+    ) -> Result<()> {
+        validate_output_path(&output)
+            .with_context(|| format!("validating output path: {}", output.display()))?;
+
+        let options = index.list();
+        let config_path = self.config_path();
+        let _guard = ProjectGuard::acquire(&config_path)?;
+        let mut config = load_or_default_config(&config_path)?;
+        self.validate_project(&options, index, &config)?;
+
+        let mut requested = Vec::new();
+        let mut missing = Vec::new();
+        for name in &bundle.templates {
+            match index.resolve(name) {
+                Some(found) => requested.push(found.to_string()),
+                None => missing.push(name.as_str()),
+            }
+        }
+        if !missing.is_empty() {
+            let mut error_msg = format!(
+                "The following templates in bundle '{}' do not exist:\n",
+                bundle.name
+            );
+            for name in missing {
+                error_msg.push_str(&format!("  - {}\n", name));
+            }
+            error_msg.push_str("\nRun `lignore update` to refresh the cache or `lignore list` to see available templates.");
+            anyhow::bail!(error_msg);
+        }
+
+        writeln!(self.out(), "Installing bundle {}", bundle.name)?;
+        if !bundle.description.is_empty() {
+            writeln!(self.out(), "  {}", bundle.description)?;
+        }
+
+        // Keep the order build_previous_selection uses: templates, then custom
+        let mut selected: Vec<String> = config
+            .templates
+            .iter()
+            .filter(|template| options.contains(template))
+            .cloned()
+            .collect();
+        for name in requested {
+            if selected.contains(&name) {
+                writeln!(self.out(), "{} is already selected", name)?;
+            } else {
+                writeln!(self.out(), "Adding {}", name)?;
+                selected.push(name);
+            }
+        }
+        if !bundle.patterns.is_empty() {
+            let replaced = config
+                .custom
+                .insert(bundle.name.clone(), bundle.patterns.clone());
+            if replaced.is_some() {
+                writeln!(self.out(), "Updating the patterns of {}", bundle.name)?;
+            } else {
+                writeln!(
+                    self.out(),
+                    "Adding {} patterns as {}",
+                    bundle.patterns.len(),
+                    bundle.name
+                )?;
+            }
+            validate_config(&options, &config)?;
+        }
+        selected.extend(config.custom.keys().cloned());
+
+        self.write_selection(
+            index,
+            &config_path,
+            &mut config,
+            &selected,
+            &output,
+            WriteOptions::default(),
+        )
+    }
+}
+
+/// The footer line of each picker option: its description, or why it is
+/// quarantined or deprecated
+fn picker_descriptions(index: &TemplateIndex, options: &[String]) -> Vec<String> {
+    options
+        .iter()
+        .map(|name| match index.get(name) {
+            Some(entry) if entry.is_quarantined() => {
+                format!("Quarantined: {}", entry.notes.quarantine.join("; "))
+            }
+            Some(entry) if !entry.notes.deprecated.is_empty() => {
+                format!(
+                    "{} (deprecated: {})",
+                    entry.notes.description, entry.notes.deprecated
+                )
+            }
+            Some(entry) => entry.notes.description.clone(),
+            None => "Custom template from lignore.json".to_string(),
+        })
+        .collect()
+}
+
+fn join_or_none(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Asks what to do with an empty selection, keeping everything when there
+/// is no one to ask
+fn ask_empty_selection(out: &mut Output) -> Result<EmptySelection> {
+    if !io::stdin().is_terminal() {
+        return Ok(EmptySelection::Keep);
+    }
+    loop {
+        let answer = ask(
+            "No templates selected. Keep the existing files, write an empty file, \
+             or clear lignore.json? [K/e/c] ",
+        )?;
+        match answer.as_str() {
+            "" | "k" | "keep" => return Ok(EmptySelection::Keep),
+            "e" | "empty" => return Ok(EmptySelection::Empty),
+            "c" | "clear" => return Ok(EmptySelection::Clear),
+            _ => writeln!(out, "Please answer k, e or c.")?,
+        }
+    }
+}
+
+/// Warns about tracked files that the rules in `output` would ignore, since
+/// git keeps tracking them regardless
+fn warn_tracked_ignored(out: &mut impl Write, output: &Path) -> Result<()> {
+    let files = match tracked_but_ignored(output) {
+        Ok(Some(files)) => files,
+        Ok(None) => {
+            print_warning(
+                out,
+                "Not inside a git repository; skipping the tracked file check",
+            )?;
+            return Ok(());
+        }
+        Err(e) => {
+            print_warning(out, &format!("Could not check tracked files: {:#}", e))?;
+            return Ok(());
+        }
+    };
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    print_warning(
+        out,
+        &format!(
+            "{} tracked file(s) match the new rules and will stay tracked. To stop tracking them:",
+            files.len()
+        ),
+    )?;
+    for file in files.iter().take(MAX_UNTRACK_SUGGESTIONS) {
+        writeln!(out, "  {}", untrack_command(file))?;
+    }
+    if files.len() > MAX_UNTRACK_SUGGESTIONS {
+        writeln!(
+            out,
+            "  ... and {} more",
+            files.len() - MAX_UNTRACK_SUGGESTIONS
+        )?;
+    }
+    Ok(())
 }
@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::{Component, Path};
+use zip::read::ZipArchive;
+
+use crate::limits::get_limits;
+use crate::sources::FetchedTemplate;
+use crate::validation::validate_template_key;
+
+/// Reads every `.gitignore` file from a gzipped repository tarball.
+///
+/// GitHub wraps the repository in a single `owner-repo-sha/` directory,
+/// which is stripped so keys match the paths returned by the contents API.
+pub fn read_tar_gz<R: Read>(reader: R) -> Result<Vec<FetchedTemplate>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(reader));
+    let mut templates = Vec::new();
+
+    for entry in archive.entries().context("reading tar archive")? {
+        let mut entry = entry.context("reading tar entry")?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry.path().context("reading tar entry path")?.into_owned();
+        let Some(key) = template_key(&path) else {
+            continue;
+        };
+        if entry.size() > get_limits().max_download_size {
+            eprintln!(
+                "Warning: Skipping template {}: {} bytes exceeds size limit",
+                key,
+                entry.size()
+            );
+            continue;
+        }
+
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .with_context(|| format!("reading template {} from archive", key))?;
+        templates.push(FetchedTemplate {
+            name: template_name(&key),
+            key,
+            content,
+        });
+    }
+
+    Ok(templates)
+}
+
+/// Reads every `.gitignore` file from a zip download of the repository
+pub fn read_zip<R: Read + Seek>(reader: R) -> Result<Vec<FetchedTemplate>> {
+    let mut archive = ZipArchive::new(reader).context("reading zip archive")?;
+    let mut templates = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).context("reading zip entry")?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(path) = entry.enclosed_name().map(Path::to_path_buf) else {
+            continue;
+        };
+        let Some(key) = template_key(&path) else {
+            continue;
+        };
+        if entry.size() > get_limits().max_download_size {
+            eprintln!(
+                "Warning: Skipping template {}: {} bytes exceeds size limit",
+                key,
+                entry.size()
+            );
+            continue;
+        }
+
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .with_context(|| format!("reading template {} from archive", key))?;
+        templates.push(FetchedTemplate {
+            name: template_name(&key),
+            key,
+            content,
+        });
+    }
+
+    Ok(templates)
+}
+
+/// Reads a locally downloaded `.tar.gz`/`.tgz` or `.zip` of the repository
+pub fn read_archive_file(path: &Path) -> Result<Vec<FetchedTemplate>> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let file = File::open(path).with_context(|| format!("opening {}", path.display()))?;
+
+    if file_name.ends_with(".zip") {
+        read_zip(file)
+    } else if file_name.ends_with(".tar.gz") || file_name.ends_with(".tgz") {
+        read_tar_gz(file)
+    } else {
+        anyhow::bail!(
+            "Unsupported archive format: {} (expected .tar.gz, .tgz or .zip)",
+            path.display()
+        )
+    }
+}
+
+/// Maps an archive path like `github-gitignore-abc123/Global/macOS.gitignore`
+/// to the template key `Global/macOS`
+fn template_key(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    if !file_name.ends_with(".gitignore") || file_name == ".gitignore" {
+        return None;
+    }
+
+    let parts: Vec<&str> = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    if parts.len() < 2 {
+        return None;
+    }
+
+    let key = parts[1..].join("/");
+    let key = key.trim_end_matches(".gitignore").to_string();
+    validate_template_key(&key).ok()?;
+    Some(key)
+}
+
+fn template_name(key: &str) -> String {
+    key.rsplit('/').next().unwrap_or(key).to_string()
+}
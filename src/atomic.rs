@@ -0,0 +1,117 @@
+//! Replacing files so that an interrupted write never leaves them truncated
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes `contents` to a temporary file beside `path` and renames it into
+/// place. An existing file keeps its permissions and, where allowed, its
+/// owner; a new one gets the usual permissions for the umask.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let target = resolve_symlink(path.as_ref())?;
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let existing = fs::metadata(&target).ok();
+
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".lignore-").suffix(".tmp");
+    #[cfg(unix)]
+    if existing.is_none() {
+        use std::os::unix::fs::PermissionsExt;
+        // Created with 0666 minus the umask, like `fs::write` would
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let mut file = builder
+        .tempfile_in(dir)
+        .with_context(|| format!("creating a temporary file in {}", dir.display()))?;
+    file.write_all(contents.as_ref())
+        .and_then(|()| match &existing {
+            Some(metadata) => copy_metadata(file.as_file(), metadata),
+            None => Ok(()),
+        })
+        .and_then(|()| file.as_file().sync_all())
+        .with_context(|| format!("writing {}", file.path().display()))?;
+    file.persist(&target)
+        .with_context(|| format!("replacing {}", target.display()))?;
+    Ok(())
+}
+
+/// Replaces the file a symlink points to rather than the link itself
+fn resolve_symlink(path: &Path) -> Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            fs::canonicalize(path).with_context(|| format!("resolving {}", path.display()))
+        }
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn copy_metadata(file: &File, existing: &Metadata) -> io::Result<()> {
+    file.set_permissions(existing.permissions())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{MetadataExt, fchown};
+        // Only root may give a file away; otherwise it stays with the current user
+        let _ = fchown(file, Some(existing.uid()), Some(existing.gid()));
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o777
+    }
+
+    #[test]
+    fn overwriting_keeps_the_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".gitignore");
+        fs::write(&path, "old\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path, "new\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new\n");
+        assert_eq!(mode(&path), 0o640);
+    }
+
+    #[test]
+    fn new_file_gets_the_umask_mode() {
+        let dir = tempfile::tempdir().unwrap();
+        // `fs::write` creates files with 0666 minus the umask
+        let reference = dir.path().join("reference");
+        fs::write(&reference, "").unwrap();
+        let path = dir.path().join(".gitignore");
+
+        write_atomic(&path, "new\n").unwrap();
+
+        assert_eq!(mode(&path), mode(&reference));
+    }
+
+    #[test]
+    fn writing_through_a_symlink_replaces_the_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("shared.gitignore");
+        fs::write(&target, "old\n").unwrap();
+        let link = dir.path().join(".gitignore");
+        symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "new\n").unwrap();
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_link(&link).unwrap(), target);
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new\n");
+    }
+}
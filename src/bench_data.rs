@@ -0,0 +1,52 @@
+//! Synthetic templates for the benchmarks and for trying the picker with
+//! far more templates than github/gitignore has (`update --bench-mode`)
+
+use crate::sources::FetchedTemplate;
+
+/// Directories templates are spread over, as in github/gitignore
+const CATEGORIES: [&str; 3] = ["", "Global/", "community/"];
+
+/// Patterns shared by many templates, so deduplication has work to do
+const COMMON_PATTERNS: [&str; 8] = [
+    "*.log",
+    ".DS_Store",
+    "Thumbs.db",
+    "*.tmp",
+    "*.swp",
+    ".idea/",
+    ".vscode/",
+    "node_modules/",
+];
+
+/// Builds `count` templates with stable names such as `Lang0042` and
+/// `Global/Editor0043`, each with a description header, its own patterns
+/// and some patterns shared with the others
+pub fn synthetic_templates(count: usize) -> Vec<FetchedTemplate> {
+    (0..count)
+        .map(|i| {
+            let category = CATEGORIES[i % CATEGORIES.len()];
+            let name = match category {
+                "Global/" => format!("Editor{:04}", i),
+                "community/" => format!("Framework{:04}", i),
+                _ => format!("Lang{:04}", i),
+            };
+            let mut content = format!("# Synthetic template {} for benchmarks\n\n", name);
+            for j in 0..20 {
+                content.push_str(&format!("/{}-build-{}/\n", name.to_lowercase(), j));
+                content.push_str(&format!("*.{}{}\n", name.to_lowercase(), j));
+            }
+            content.push('\n');
+            for (j, pattern) in COMMON_PATTERNS.iter().enumerate() {
+                if (i + j) % 3 == 0 {
+                    content.push_str(pattern);
+                    content.push('\n');
+                }
+            }
+            FetchedTemplate {
+                key: format!("{}{}", category, name),
+                name,
+                content,
+            }
+        })
+        .collect()
+}
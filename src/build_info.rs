@@ -0,0 +1,48 @@
+//! Details about how this binary was built, for `--version --verbose` and
+//! bug reports
+
+use std::fmt::Write;
+
+use crate::settings::Settings;
+use crate::sources::SourceRegistry;
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Abbreviated commit the binary was built from, or "unknown" outside a checkout
+pub const GIT_COMMIT: &str = env!("LIGNORE_GIT_COMMIT");
+/// UTC build date as YYYY-MM-DD
+pub const BUILD_DATE: &str = env!("LIGNORE_BUILD_DATE");
+/// Target triple, as used to pick release assets
+pub const TARGET: &str = env!("LIGNORE_TARGET");
+/// Enabled cargo features, comma-separated
+pub const FEATURES: &str = env!("LIGNORE_FEATURES");
+
+/// The version line, followed by build details and the source templates are
+/// read from first when `verbose` is set
+pub fn describe(verbose: bool, settings: Option<&Settings>) -> String {
+    let mut out = format!("lightignore {}\n", VERSION);
+    if !verbose {
+        return out;
+    }
+    let features = if FEATURES.is_empty() {
+        "none"
+    } else {
+        FEATURES
+    };
+    let source = settings
+        .and_then(|settings| settings.sources_by_priority().first().copied())
+        .and_then(|config| {
+            let source = SourceRegistry::default().create(config).ok()?;
+            let metadata = source.metadata();
+            Some(format!(
+                "{} ({}: {})",
+                config.name, metadata.kind, metadata.location
+            ))
+        })
+        .unwrap_or_else(|| "unavailable (settings could not be read)".to_string());
+    let _ = writeln!(out, "commit:   {}", GIT_COMMIT);
+    let _ = writeln!(out, "built:    {}", BUILD_DATE);
+    let _ = writeln!(out, "target:   {}", TARGET);
+    let _ = writeln!(out, "features: {}", features);
+    let _ = writeln!(out, "source:   {}", source);
+    out
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::config::validate_custom_template;
+use crate::http::send;
+use crate::limits::get_limits;
+use crate::validation::validate_template_key;
+
+/// A recommended ignore set that a framework can distribute: templates in
+/// the order they should appear, plus patterns of its own
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Bundle {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default)]
+    pub templates: Vec<String>,
+    /// Extra patterns, installed as a custom template named after the bundle
+    #[serde(default)]
+    pub patterns: Vec<String>,
+}
+
+/// Parses and validates a bundle manifest
+pub fn parse_bundle(content: &str) -> Result<Bundle> {
+    let bundle: Bundle = serde_json::from_str(content).context("parsing bundle manifest")?;
+    validate_template_key(&bundle.name)
+        .with_context(|| format!("invalid bundle name '{}'", bundle.name))?;
+    if bundle.templates.is_empty() && bundle.patterns.is_empty() {
+        anyhow::bail!("Bundle '{}' has no templates or patterns", bundle.name);
+    }
+    validate_custom_template(&bundle.name, &bundle.patterns)
+        .with_context(|| format!("validating patterns of bundle '{}'", bundle.name))?;
+    Ok(bundle)
+}
+
+/// Reads a bundle manifest from an `https://` URL or a local file
+pub async fn read_bundle(client: &Client, location: &str) -> Result<Bundle> {
+    let content = if location.starts_with("https://") {
+        download(client, location).await?
+    } else if location.starts_with("http://") {
+        anyhow::bail!("Bundle URL must use HTTPS: {}", location);
+    } else {
+        let path = Path::new(location);
+        let max_size = get_limits().max_download_size;
+        let size = fs::metadata(path)
+            .with_context(|| format!("reading {}", path.display()))?
+            .len();
+        if size > max_size {
+            anyhow::bail!(
+                "{} is too large: {} bytes (max: {} bytes)",
+                path.display(),
+                size,
+                max_size
+            );
+        }
+        fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?
+    };
+    parse_bundle(&content).with_context(|| format!("loading bundle from {}", location))
+}
+
+async fn download(client: &Client, url: &str) -> Result<String> {
+    let url = Url::parse(url).with_context(|| format!("parsing bundle URL {}", url))?;
+    let response = send(client.get(url.clone()))
+        .await
+        .with_context(|| format!("downloading {}", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("failed to download {}: status {}", url, response.status());
+    }
+
+    let max_size = get_limits().max_download_size;
+    if let Some(content_length) = response.content_length()
+        && content_length > max_size
+    {
+        anyhow::bail!(
+            "{} is too large: {} bytes (max: {} bytes)",
+            url,
+            content_length,
+            max_size
+        );
+    }
+    let content = response.text().await?;
+    if content.len() > max_size as usize {
+        anyhow::bail!(
+            "{} exceeds size limit: {} bytes (max: {} bytes)",
+            url,
+            content.len(),
+            max_size
+        );
+    }
+    Ok(content)
+}
@@ -1,17 +1,76 @@
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use lightignore::app::EmptySelection;
+use lightignore::kind::IgnoreKind;
+use lightignore::policy::ReportFormat;
+use lightignore::progress::ProgressFormat;
+use lightignore::settings::SortOrder;
+use lightignore::ui::theme::{Palette, ThemeKind};
+
 #[derive(Parser, Debug)]
 #[command(
     author,
     version,
+    disable_version_flag = true,
     about = "lightignore - Interactive gitignore generator"
 )]
 pub struct Cli {
-    /// Cache directory for downloaded templates
+    /// Print version; with --verbose, also the commit, build date, target,
+    /// features and default source for bug reports
+    #[arg(short = 'V', long)]
+    pub version: bool,
+
+    /// Cache directory for downloaded templates; relative paths resolve against
+    /// the current directory. Overrides "cache_dir" in lignore.json
     #[arg(short, long)]
     pub cache_dir: Option<PathBuf>,
 
+    /// Project config file to use instead of lignore.json or .lignore.json
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Force HTTP/1.1 instead of negotiating HTTP/2
+    #[arg(long, global = true)]
+    pub http1: bool,
+
+    /// Log every HTTP request and response to stderr, with credentials redacted
+    #[arg(long, global = true)]
+    pub debug_http: bool,
+
+    /// Send GitHub API requests to this root instead of api.github.com, such
+    /// as a GitHub Enterprise server or a local fixture server
+    #[arg(long, global = true, value_name = "URL")]
+    pub api_url: Option<String>,
+
+    /// Print detailed progress such as per-request transfer timing
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// Which ignore file to work on; its default path comes from `outputs`
+    /// in lignore.json, otherwise the kind's usual file name
+    #[arg(long, value_enum, global = true, default_value_t = IgnoreKind::Gitignore)]
+    pub kind: IgnoreKind,
+
+    /// Answer yes to confirmation prompts, such as the one before the first
+    /// download of templates or before installing an update
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Never download; fail when the cache is missing or damaged
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Treat warnings such as failed downloads or templates missing from the
+    /// cache as errors, for CI
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// How to report download progress; `json` writes one event per line to
+    /// stderr for tools wrapping lignore
+    #[arg(long, value_enum, global = true, default_value_t = ProgressFormat::Human)]
+    pub progress: ProgressFormat,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -19,15 +78,301 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Update the local cache of gitignore templates
-    Update,
+    Update {
+        /// Seed the cache from a downloaded .tar.gz or .zip of the template repository
+        #[arg(long, value_name = "FILE")]
+        from_archive: Option<PathBuf>,
+        /// Replace the cache with this many synthetic templates, for
+        /// benchmarking; combine with --cache-dir to keep the real cache
+        #[arg(
+            long,
+            value_name = "COUNT",
+            hide = true,
+            conflicts_with = "from_archive"
+        )]
+        bench_mode: Option<usize>,
+    },
     /// List available templates
-    List,
+    List {
+        /// Show one template per line with its source, category, size, blob SHA, cached path and description
+        #[arg(short, long)]
+        long: bool,
+        /// Print template details as JSON for other tools
+        #[arg(long, conflicts_with = "long")]
+        json: bool,
+        /// Ordering of templates (default from config, otherwise name)
+        #[arg(long, value_enum)]
+        sort: Option<SortOrder>,
+        /// Browse templates in the picker: filter, preview and copy them
+        /// without selecting or writing anything
+        #[arg(short, long, conflicts_with_all = ["long", "json"])]
+        interactive: bool,
+    },
+    /// Show a template's description, deprecation note and content
+    Show {
+        /// Template name or path in its source, such as "global/macos", or
+        /// a custom template from lignore.json
+        name: String,
+        /// Print the `# ===== Name =====` header that generated files put
+        /// above the template's section
+        #[arg(long)]
+        with_header: bool,
+    },
+    /// List the templates containing a pattern, such as "*.pyc", or an
+    /// equivalent form of it
+    Which {
+        /// Pattern to look for, as written in an ignore file
+        pattern: String,
+    },
     /// Interactively build a .gitignore
     Generate {
-        /// Output file path (default: ./.gitignore)
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Skip the picker and use a preset token or comma-separated
+        /// templates such as "rust,node,global/macos"
+        #[arg(long, value_name = "TEMPLATES|TOKEN")]
+        from: Option<String>,
+        /// Skip the picker and read template names from stdin, one per line
+        #[arg(long, conflicts_with = "from")]
+        stdin_templates: bool,
+        /// Skip the picker and use a curated template set such as rust-cli,
+        /// node-web or python-ml; `recipes` in the global config adds more
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["from", "stdin_templates"])]
+        recipe: Option<String>,
+        /// Also place the generated content on the system clipboard
+        #[arg(long)]
+        copy: bool,
+        /// Update templates that changed since the last generation without asking
+        #[arg(long)]
+        refresh_changed: bool,
+        /// Warn about tracked files that the generated rules would ignore
+        #[arg(long)]
+        check_tracked: bool,
+        /// What to do when nothing is selected in the picker (default: ask,
+        /// or keep when stdin is not a terminal)
+        #[arg(long, value_enum, conflicts_with_all = ["from", "stdin_templates", "recipe"])]
+        on_empty: Option<EmptySelection>,
+        /// Write only the output file, not lignore.json or lignore.lock
+        #[arg(long)]
+        no_save_config: bool,
+        /// Leave OS and editor templates (@os, @editor) out of the
+        /// selection; ones listed in `personal` still go to global excludes
+        #[arg(long, conflicts_with_all = ["only_custom", "kinds"])]
+        no_personal: bool,
+        /// Only add patterns the output file is missing, never changing or
+        /// removing its existing lines
+        #[arg(long)]
+        append: bool,
+        /// Generate from the custom templates in lignore.json alone, without
+        /// reading or downloading the template cache
+        #[arg(long, conflicts_with_all = ["from", "stdin_templates", "recipe", "on_empty", "kinds"])]
+        only_custom: bool,
+        /// Generate the file of each kind, such as
+        /// `--kinds gitignore,dockerignore`, from lignore.json in one run;
+        /// `kind_templates` there gives a kind its own templates
+        #[arg(
+            long,
+            value_enum,
+            value_delimiter = ',',
+            conflicts_with_all = ["output", "from", "stdin_templates", "recipe", "on_empty", "append", "copy", "refresh_changed"]
+        )]
+        kinds: Vec<IgnoreKind>,
+    },
+    /// Detect the project's stack from files such as Cargo.toml or
+    /// package.json, write lignore.json and generate the first .gitignore
+    Init {
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Add templates to lignore.json and append their sections to the .gitignore without the picker
+    Add {
+        /// Template names, or tags such as @os, @editor, @language and @vcs
+        #[arg(required = true)]
+        templates: Vec<String>,
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
         #[arg(short, long)]
         output: Option<PathBuf>,
+        /// Replace the section of a template the file already has instead
+        /// of refusing to add it
+        #[arg(long)]
+        force: bool,
+    },
+    /// Remove templates' sections from the .gitignore and drop them from lignore.json
+    Remove {
+        /// Template names, or tags such as @os and @editor for the selected
+        /// templates carrying them
+        #[arg(required = true)]
+        templates: Vec<String>,
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Remove generated output so the project no longer uses lightignore
+    Clean {
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Also remove lignore.json
+        #[arg(long)]
+        config: bool,
+    },
+    /// Summarize the project's templates, output file and cache
+    Status {
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print a unified diff from the output file to what lignore.json
+    /// generates from the cached templates
+    Diff {
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Report patterns that have a canonical form, such as `**/foo` for
+    /// `foo`, or repeat an earlier pattern
+    Lint {
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Rewrite the file with canonical patterns and without repeats
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Describe the selected templates and their notable patterns, for a
+    /// project's CONTRIBUTING file or docs
+    Report {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+        /// Write the report to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Suggest templates that usually go with the project's selection
+    Suggest,
+    /// Print template names one per line for completion scripts, fzf and
+    /// editor plugins; reads only the cache and never downloads
+    CompletionData {
+        /// Only print names or paths starting with this, ignoring case; a
+        /// prefix starting with @ completes tags
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+    /// Show statistics kept on this machine
+    Stats {
+        /// How often each template was generated and when it was last used
+        #[arg(long, required = true)]
+        usage: bool,
+    },
+    /// Work with a workspace of projects listed under `members`
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    /// Export the cache as a static template set for use as a `local` source
+    Mirror {
+        /// Directory to write templates, index.json and SHA256SUMS into
+        dir: PathBuf,
+        /// Check a received mirror against its SHA256SUMS instead of
+        /// writing one
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Inspect and maintain the template cache
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+    /// Install recommended template sets distributed as bundles
+    Bundle {
+        #[command(subcommand)]
+        command: BundleCommands,
+    },
+    /// Share template selections
+    Preset {
+        #[command(subcommand)]
+        command: PresetCommands,
+    },
+    /// Inspect configured template sources
+    Source {
+        #[command(subcommand)]
+        command: SourceCommands,
+    },
+    /// Try out the colors set in the `theme` setting
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommands,
     },
     /// Update lightignore to the latest version
     SelfUpdate,
 }
+
+#[derive(Subcommand, Debug)]
+pub enum WorkspaceCommands {
+    /// Warn about rules in a member's .gitignore that conflict with the
+    /// root's or an enclosing member's
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Download missing or modified templates again and remove files the index does not reference
+    Repair {
+        /// Only report what would be fixed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BundleCommands {
+    /// Add a bundle's templates and patterns to lignore.json and regenerate
+    Add {
+        /// Path or https:// URL of the bundle manifest
+        location: String,
+        /// Output file path (default: from `outputs` in lignore.json, or
+        /// the --kind file such as ./.gitignore)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PresetCommands {
+    /// Print the selection in lignore.json as a token for `generate --from`
+    Export,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SourceCommands {
+    /// Show configured sources with their last sync and template counts
+    List,
+    /// Check that sources are reachable and can list their templates
+    Test {
+        /// Only test the source with this name
+        name: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ThemeCommands {
+    /// Show a sample of every themed element of the picker and messages
+    Preview {
+        /// Preview for this background instead of the configured or detected one
+        #[arg(long, value_enum)]
+        background: Option<ThemeKind>,
+        /// Preview this palette instead of the configured one
+        #[arg(long, value_enum)]
+        palette: Option<Palette>,
+    },
+}
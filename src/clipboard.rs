@@ -0,0 +1,11 @@
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+
+/// Places text on the system clipboard
+pub fn copy_text(text: &str) -> Result<()> {
+    let mut clipboard = Clipboard::new().context("opening the system clipboard")?;
+    clipboard
+        .set_text(text)
+        .context("writing to the system clipboard")?;
+    Ok(())
+}
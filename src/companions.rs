@@ -0,0 +1,55 @@
+/// Templates that usually go together, so selecting the first suggests the
+/// others. Names are matched without namespace, like tags.
+const COMPANIONS: &[(&str, &[&str])] = &[
+    ("Android", &["Gradle", "Kotlin", "JetBrains"]),
+    ("C", &["CMake"]),
+    ("C++", &["CMake"]),
+    ("Flutter", &["Dart", "Android"]),
+    ("Java", &["Gradle", "Maven", "JetBrains"]),
+    ("Kotlin", &["Gradle", "JetBrains"]),
+    ("Laravel", &["Composer"]),
+    ("Node", &["Yarn"]),
+    ("Objective-C", &["Xcode"]),
+    ("Python", &["JupyterNotebooks", "VirtualEnv"]),
+    ("Rails", &["Ruby"]),
+    ("Swift", &["Xcode", "macOS"]),
+    ("Symfony", &["Composer"]),
+    ("Unity", &["JetBrains", "VisualStudio"]),
+    ("UnrealEngine", &["VisualStudio", "JetBrains"]),
+];
+
+/// A template that goes well with one that is already selected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub name: String,
+    /// The selected template that led to the suggestion
+    pub because_of: String,
+}
+
+/// Companions of the selected templates that are available but not
+/// selected yet, in selection order and without repeats
+pub fn suggest(selected: &[String], available: &[String]) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = Vec::new();
+    for chosen in selected {
+        let base = chosen.rsplit('/').next().unwrap_or(chosen);
+        let companions = COMPANIONS
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(base))
+            .flat_map(|(_, companions)| companions.iter());
+        for companion in companions {
+            let Some(name) = available
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(companion))
+            else {
+                continue;
+            };
+            if !selected.contains(name) && !suggestions.iter().any(|s| &s.name == name) {
+                suggestions.push(Suggestion {
+                    name: name.clone(),
+                    because_of: chosen.clone(),
+                });
+            }
+        }
+    }
+    suggestions
+}
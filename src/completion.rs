@@ -0,0 +1,46 @@
+//! Words for shell completion scripts, fzf pipelines and editor plugins,
+//! which run `lignore completion-data` on every keystroke. Only the cached
+//! index and lignore.json are read: nothing is downloaded and nothing asks.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::tags::tag_names;
+use crate::template::TemplateIndex;
+
+/// Template names, custom templates and paths in their source such as
+/// `Global/macOS` that start with `prefix`, ignoring case, sorted and
+/// without repeats. A prefix starting with `@` completes tags instead.
+/// A missing or unreadable cache completes nothing rather than failing.
+pub fn candidates<'a>(
+    cache_dir: &Path,
+    hidden: &[String],
+    custom: impl IntoIterator<Item = &'a String>,
+    prefix: &str,
+) -> Vec<String> {
+    let lowered = prefix.to_lowercase();
+    let matches = |word: &str| word.to_lowercase().starts_with(&lowered);
+
+    if let Some(tag) = lowered.strip_prefix('@') {
+        return tag_names()
+            .filter(|name| name.starts_with(tag))
+            .map(|name| format!("@{}", name))
+            .collect();
+    }
+
+    let mut words = BTreeSet::new();
+    if let Ok(mut index) = TemplateIndex::read(cache_dir) {
+        index.hide(hidden);
+        for (name, entry) in &index.templates {
+            // Print what the user is typing: the name, or the path when
+            // only that matches, as `add` and `--from` accept both
+            if matches(name) {
+                words.insert(name.clone());
+            } else if !entry.key.is_empty() && matches(&entry.key) {
+                words.insert(entry.key.clone());
+            }
+        }
+    }
+    words.extend(custom.into_iter().filter(|name| matches(name)).cloned());
+    words.into_iter().collect()
+}
@@ -0,0 +1,59 @@
+//! Conditions guarding patterns in lignore.json, such as
+//! `has_file:docker-compose.yml`, so one shared config adapts its output to
+//! the layout of each repository
+
+use anyhow::Result;
+use std::path::Path;
+
+/// A parsed `when` condition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// `has_file:<path>`: a file exists at the path
+    HasFile(String),
+    /// `has_dir:<path>`: a directory exists at the path
+    HasDir(String),
+    /// `!<condition>`: the condition does not hold
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    /// Parses `has_file:<path>` or `has_dir:<path>`, optionally negated
+    /// with a leading `!`. Paths are relative and may not leave the project.
+    pub fn parse(when: &str) -> Result<Self> {
+        let when = when.trim();
+        if let Some(rest) = when.strip_prefix('!') {
+            return Ok(Condition::Not(Box::new(Condition::parse(rest)?)));
+        }
+        let (kind, path) = when.split_once(':').ok_or_else(|| {
+            anyhow::anyhow!(
+                "Condition '{}' should look like has_file:<path> or has_dir:<path>",
+                when
+            )
+        })?;
+        let path = path.trim();
+        if path.is_empty()
+            || Path::new(path).is_absolute()
+            || path.split('/').any(|part| part == "..")
+        {
+            anyhow::bail!("Condition '{}' must name a path inside the project", when);
+        }
+        match kind.trim() {
+            "has_file" => Ok(Condition::HasFile(path.to_string())),
+            "has_dir" => Ok(Condition::HasDir(path.to_string())),
+            other => anyhow::bail!(
+                "Unknown condition '{}' in '{}'; use has_file or has_dir",
+                other,
+                when
+            ),
+        }
+    }
+
+    /// Whether the condition holds for the project in `dir`
+    pub fn holds(&self, dir: &Path) -> bool {
+        match self {
+            Condition::HasFile(path) => dir.join(path).is_file(),
+            Condition::HasDir(path) => dir.join(path).is_dir(),
+            Condition::Not(condition) => !condition.holds(dir),
+        }
+    }
+}
@@ -1,12 +1,21 @@
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
-// Security limits
-pub const MAX_CUSTOM_TEMPLATE_SIZE: usize = 100 * 1024; // 100KB
-pub const MAX_CUSTOM_TEMPLATE_LINES: usize = 10000;
+use crate::atomic::write_atomic;
+use crate::conditions::Condition;
+use crate::jsonc::{self, Comments};
+use crate::kind::IgnoreKind;
+use crate::limits::get_limits;
+
+/// Project config file created by default
+pub const CONFIG_FILE: &str = "lignore.json";
+
+/// Dotfile-style name that may be used instead of [`CONFIG_FILE`]
+pub const HIDDEN_CONFIG_FILE: &str = ".lignore.json";
 
 #[derive(Deserialize, Serialize, Debug, Default)]
 pub struct LignoreConfig {
@@ -14,24 +23,146 @@ pub struct LignoreConfig {
     pub templates: Vec<String>,
     #[serde(default)]
     pub custom: BTreeMap<String, Vec<String>>,
+    /// Values for `{{name}}` placeholders in custom templates, overriding
+    /// the built-in `project_name`, `author` and `author_email`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// Patterns written only when their condition holds for the project,
+    /// after every template
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditional: Vec<ConditionalPatterns>,
+    /// Project-specific template cache, relative to the project directory,
+    /// used instead of the user-level cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_dir: Option<PathBuf>,
+    /// Commit of the GitHub sources that updates fetch for this project, so
+    /// every contributor generates from the same template versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pin: Option<String>,
+    /// Directories of workspace members, relative to this file, each with
+    /// its own lignore.json and .gitignore
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<PathBuf>,
+    /// Output file for each kind, relative to the directory of lignore.json,
+    /// used when `-o` is not given
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<IgnoreKind, PathBuf>,
+    /// Templates for kinds that should not get the whole selection, such
+    /// as a shorter list for .dockerignore; other kinds use `templates`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub kind_templates: BTreeMap<IgnoreKind, Vec<String>>,
+    /// Write patterns in canonical form and treat equivalent ones, such as
+    /// `**/foo` and `foo`, as duplicates
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub normalize: bool,
+    /// Keys this version does not know, such as ones written by a newer
+    /// version or another tool, kept so saving does not drop them
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+    /// Directory of lignore.json, where conditions are checked; empty for
+    /// the current directory
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+/// Patterns guarded by a condition such as `has_file:docker-compose.yml`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ConditionalPatterns {
+    /// `has_file:<path>` or `has_dir:<path>`, relative to the directory of
+    /// lignore.json; a leading `!` negates it
+    pub when: String,
+    pub patterns: Vec<String>,
+}
+
+/// Resolves a relative `path` from the project config against the directory
+/// holding `config_path`, so `--config` from elsewhere finds the same files
+pub fn relative_to_config(config_path: &Path, path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .map(|dir| dir.join(path))
+        .unwrap_or_else(|| path.to_path_buf())
 }
 
-/// Loads config or returns default if file doesn't exist
-pub fn load_or_default_config(config_path: &PathBuf) -> LignoreConfig {
-    if config_path.exists() {
-        load_config(config_path).unwrap_or_default()
+impl LignoreConfig {
+    /// Where a `kind` file is written by default: the path configured in
+    /// `outputs`, otherwise the kind's usual file name in the current
+    /// directory
+    pub fn output_for(&self, kind: IgnoreKind, config_path: &Path) -> PathBuf {
+        match self.outputs.get(&kind) {
+            Some(path) => relative_to_config(config_path, path),
+            None => PathBuf::from(kind.file_name()),
+        }
+    }
+
+    /// Templates generated into a `kind` file
+    pub fn templates_for(&self, kind: IgnoreKind) -> &[String] {
+        self.kind_templates.get(&kind).unwrap_or(&self.templates)
+    }
+}
+
+/// Finds the project config: `explicit` when given, otherwise the first of
+/// lignore.json and .lignore.json that exists. A project with neither gets a
+/// new lignore.json.
+pub fn find_config_path(explicit: Option<&Path>) -> PathBuf {
+    if let Some(path) = explicit {
+        return path.to_path_buf();
+    }
+    [CONFIG_FILE, HIDDEN_CONFIG_FILE]
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| path.exists())
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE))
+}
+
+/// Loads config or returns default if file doesn't exist. A file that
+/// cannot be read or parsed is an error rather than an empty selection, so
+/// a typo never makes a later save drop the user's templates.
+pub fn load_or_default_config(config_path: &PathBuf) -> Result<LignoreConfig> {
+    let mut config = if config_path.exists() {
+        load_config(config_path)?
     } else {
         LignoreConfig::default()
-    }
+    };
+    config.dir = config_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    Ok(config)
 }
 
 /// Validates configuration
 pub fn validate_config(options: &[String], config: &LignoreConfig) -> Result<()> {
     check_invalid_templates(options, config).context("Invalid template configuration")?;
     check_shadowed_templates(options, config).context("Template name conflict detected")?;
+    for group in &config.conditional {
+        Condition::parse(&group.when).context("Invalid conditional patterns")?;
+    }
     Ok(())
 }
 
+/// Pairs of kinds whose output paths name the same file, so generating one
+/// replaces the other, with that path
+pub fn shared_outputs(config: &LignoreConfig) -> Vec<(IgnoreKind, IgnoreKind, PathBuf)> {
+    let mut seen: BTreeMap<PathBuf, IgnoreKind> = BTreeMap::new();
+    let mut shared = Vec::new();
+    for &kind in IgnoreKind::value_variants() {
+        // Compared relative to the project directory, where the default file
+        // names land when lignore runs there
+        let path: PathBuf = config
+            .output_for(kind, Path::new(CONFIG_FILE))
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect();
+        match seen.get(&path) {
+            Some(&first) => shared.push((first, kind, path)),
+            None => {
+                seen.insert(path, kind);
+            }
+        }
+    }
+    shared
+}
+
 /// Builds the complete options list from official and custom templates
 pub fn build_options_list(options: &[String], config: &LignoreConfig) -> Vec<String> {
     let mut all_options = Vec::new();
@@ -87,52 +218,92 @@ pub fn update_and_save_config(
 }
 
 fn load_config(path: &PathBuf) -> Result<LignoreConfig> {
-    let content = fs::read_to_string(path)?;
+    let content = jsonc::strip(&fs::read_to_string(path)?);
 
     // Try to parse as new format first
-    if let Ok(config) = serde_json::from_str::<LignoreConfig>(&content) {
-        for (name, lines) in &config.custom {
-            validate_custom_template(name, lines)
-                .with_context(|| format!("validating custom template '{}'", name))?;
+    let error = match serde_json::from_str::<LignoreConfig>(&content) {
+        Ok(config) => {
+            for (name, lines) in &config.custom {
+                validate_custom_template(name, lines)
+                    .with_context(|| format!("validating custom template '{}'", name))?;
+            }
+            return Ok(config);
         }
-        return Ok(config);
-    }
+        Err(e) => e,
+    };
 
     // Fall back to old format (simple array)
     if let Ok(templates) = serde_json::from_str::<Vec<String>>(&content) {
         return Ok(LignoreConfig {
             templates,
-            custom: BTreeMap::new(),
+            ..Default::default()
         });
     }
 
-    anyhow::bail!("Failed to parse lignore.json")
+    anyhow::bail!("Failed to parse {}: {}", path.display(), error)
 }
 
-fn save_config(path: &PathBuf, config: &LignoreConfig) -> Result<()> {
-    let content = serde_json::to_string_pretty(config)?;
-    fs::write(path, content)?;
-    Ok(())
+/// Writes `config`, keeping comments from the file it replaces
+pub fn save_config(path: &PathBuf, config: &LignoreConfig) -> Result<()> {
+    let mut content = serde_json::to_string_pretty(config)?;
+    if let Ok(existing) = fs::read_to_string(path) {
+        let comments = Comments::collect(&existing);
+        if !comments.is_empty() {
+            content = comments.apply(&content);
+        }
+    }
+    write_atomic(path, content)
+}
+
+/// Share of patterns two templates have in common, between 0 and 1, ignoring
+/// comments, blank lines and order
+pub fn pattern_similarity(a: &str, b: &str) -> f64 {
+    let a = patterns(a);
+    let b = patterns(b);
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Patterns of a template that `other` does not have
+pub fn unique_patterns(template: &str, other: &str) -> Vec<String> {
+    let other = patterns(other);
+    patterns(template)
+        .into_iter()
+        .filter(|pattern| !other.contains(pattern))
+        .map(str::to_string)
+        .collect()
+}
+
+fn patterns(content: &str) -> BTreeSet<&str> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect()
 }
 
 /// Validates custom template content
 pub fn validate_custom_template(name: &str, lines: &[String]) -> Result<()> {
-    if lines.len() > MAX_CUSTOM_TEMPLATE_LINES {
+    let limits = get_limits();
+    if lines.len() > limits.max_custom_template_lines {
         anyhow::bail!(
             "Custom template '{}' has too many lines: {} (max: {})",
             name,
             lines.len(),
-            MAX_CUSTOM_TEMPLATE_LINES
+            limits.max_custom_template_lines
         );
     }
 
     let total_size: usize = lines.iter().map(|l| l.len()).sum();
-    if total_size > MAX_CUSTOM_TEMPLATE_SIZE {
+    if total_size > limits.max_custom_template_size {
         anyhow::bail!(
             "Custom template '{}' is too large: {} bytes (max: {} bytes)",
             name,
             total_size,
-            MAX_CUSTOM_TEMPLATE_SIZE
+            limits.max_custom_template_size
         );
     }
 
@@ -156,7 +327,7 @@ fn check_shadowed_templates(official_templates: &[String], config: &LignoreConfi
         let mut map: BTreeMap<String, Vec<String>> = BTreeMap::new();
         for template in official_templates {
             map.entry(template.to_lowercase())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(template.clone());
         }
         map
@@ -200,9 +371,10 @@ fn check_shadowed_templates(official_templates: &[String], config: &LignoreConfi
 
 /// Checks for invalid template references and returns an error if found
 fn check_invalid_templates(available_templates: &[String], config: &LignoreConfig) -> Result<()> {
-    let invalid_templates: Vec<_> = config
+    let invalid_templates: BTreeSet<_> = config
         .templates
         .iter()
+        .chain(config.kind_templates.values().flatten())
         .filter(|template| {
             !available_templates.contains(template) && !config.custom.contains_key(*template)
         })
@@ -220,3 +392,25 @@ fn check_invalid_templates(available_templates: &[String], config: &LignoreConfi
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_config_is_the_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = load_or_default_config(&dir.path().join(CONFIG_FILE)).unwrap();
+        assert!(config.templates.is_empty());
+        assert_eq!(config.dir, dir.path());
+    }
+
+    #[test]
+    fn malformed_config_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        fs::write(&path, r#"{"templates": ["Rust" "Node"]}"#).unwrap();
+        let error = load_or_default_config(&path).unwrap_err();
+        assert!(error.to_string().starts_with("Failed to parse"));
+    }
+}
@@ -0,0 +1,47 @@
+//! Guesses a project's templates from the files in its directory, for
+//! `lignore init`
+
+use std::path::Path;
+
+/// Files or directories (ending in `/`) whose presence suggests a template
+const MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Rust"),
+    ("package.json", "Node"),
+    ("go.mod", "Go"),
+    ("pyproject.toml", "Python"),
+    ("requirements.txt", "Python"),
+    ("setup.py", "Python"),
+    ("Gemfile", "Ruby"),
+    ("composer.json", "Composer"),
+    ("pom.xml", "Maven"),
+    ("build.gradle", "Gradle"),
+    ("build.gradle.kts", "Gradle"),
+    ("CMakeLists.txt", "CMake"),
+    ("pubspec.yaml", "Dart"),
+    ("mix.exs", "Elixir"),
+    (".idea/", "JetBrains"),
+    (".vscode/", "VisualStudioCode"),
+];
+
+/// A template suggested for a project and the file that suggested it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detected {
+    pub template: &'static str,
+    pub marker: &'static str,
+}
+
+/// Templates the files in `dir` suggest, each once, in the order of
+/// [`MARKERS`]
+pub fn detect(dir: &Path) -> Vec<Detected> {
+    let mut detected: Vec<Detected> = Vec::new();
+    for &(marker, template) in MARKERS {
+        let present = match marker.strip_suffix('/') {
+            Some(name) => dir.join(name).is_dir(),
+            None => dir.join(marker).is_file(),
+        };
+        if present && !detected.iter().any(|d| d.template == template) {
+            detected.push(Detected { template, marker });
+        }
+    }
+    detected
+}
@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::atomic::write_atomic;
+use crate::managed::{self, PERSONAL};
+
+const TEMPLATES_PREFIX: &str = "# Templates: ";
+
+/// Location of the user's global excludes file: `core.excludesFile` when
+/// set, otherwise git's default `$XDG_CONFIG_HOME/git/ignore`
+pub fn excludes_file_path() -> Result<PathBuf> {
+    let configured = Command::new("git")
+        .args(["config", "--global", "--get", "core.excludesFile"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|path| !path.is_empty());
+
+    if let Some(path) = configured {
+        if let Some(rest) = path.strip_prefix("~/") {
+            let home = dirs::home_dir().context("locating home directory")?;
+            return Ok(home.join(rest));
+        }
+        return Ok(PathBuf::from(path));
+    }
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .context("locating the git config directory")?;
+    Ok(config_dir.join("git").join("ignore"))
+}
+
+/// Templates recorded in the managed block of the excludes file
+pub fn read_personal_templates(content: &str) -> Vec<String> {
+    managed::inner(content, &PERSONAL)
+        .and_then(|block| {
+            block
+                .lines()
+                .find_map(|line| line.strip_prefix(TEMPLATES_PREFIX))
+        })
+        .map(|names| {
+            names
+                .split(", ")
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Replaces the managed block in the excludes file with `generated`,
+/// leaving everything the user wrote outside it untouched
+pub fn write_personal_block(path: &PathBuf, generated: &str) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let content = managed::replace_or_append(&existing, &PERSONAL, generated);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating directory {}", parent.display()))?;
+    }
+    write_atomic(path, content)
+}
+
+/// Removes the managed block from the excludes file, if it has one
+pub fn remove_personal_block(path: &PathBuf) -> Result<()> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    match managed::remove(&existing, &PERSONAL) {
+        Some(content) => write_atomic(path, content),
+        None => Ok(()),
+    }
+}
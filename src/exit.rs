@@ -0,0 +1,66 @@
+//! Exit statuses `lignore` reports, so scripts and CI can tell failures apart
+
+use std::fmt;
+
+pub const SUCCESS: u8 = 0;
+/// Any error not covered by a more specific status
+pub const ERROR: u8 = 1;
+/// `status` found the output or lockfile out of date with the cache, or
+/// `diff` found the output differing from what it would generate
+pub const DRIFT: u8 = 2;
+/// GitHub refused a request because of its rate limits
+pub const RATE_LIMITED: u8 = 3;
+/// `update` finished, but some templates could not be downloaded
+pub const PARTIAL_UPDATE: u8 = 4;
+/// `lint` found patterns to normalize or remove and `--fix` was not given
+pub const LINT_ISSUES: u8 = 5;
+/// The picker was cancelled, following the shell convention for Ctrl+C
+pub const CANCELLED: u8 = 130;
+
+/// GitHub refused a request because of its rate limits
+#[derive(Debug)]
+pub enum RateLimited {
+    /// The hourly quota is used up until its reset
+    Quota,
+    /// The secondary (abuse detection) limit throttled a burst of requests
+    Secondary,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RateLimited::Quota => write!(f, "the GitHub API rate limit is exhausted"),
+            RateLimited::Secondary => write!(f, "GitHub's secondary rate limit was hit"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Some templates failed to download, and `--strict` refused to write a
+/// partial cache
+#[derive(Debug)]
+pub struct PartialUpdate {
+    pub failed: usize,
+}
+
+impl fmt::Display for PartialUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} template(s) failed to download", self.failed)
+    }
+}
+
+impl std::error::Error for PartialUpdate {}
+
+/// Status for an error, from the most specific cause in its chain
+pub fn code_for(error: &anyhow::Error) -> u8 {
+    for cause in error.chain() {
+        if cause.is::<RateLimited>() {
+            return RATE_LIMITED;
+        }
+        if cause.is::<PartialUpdate>() {
+            return PARTIAL_UPDATE;
+        }
+    }
+    ERROR
+}
@@ -1,9 +1,13 @@
 use anyhow::{Context, Result};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 
+use crate::conditions::Condition;
 use crate::config::LignoreConfig;
+use crate::managed::{self, GENERATED_HEADER};
+use crate::normalize::normalize_pattern;
 use crate::template::TemplateIndex;
+use crate::variables::substitute;
 
 /// Generates gitignore file content from selected templates
 pub fn generate_gitignore_content(
@@ -11,75 +15,592 @@ pub fn generate_gitignore_content(
     index: &TemplateIndex,
     config: &LignoreConfig,
 ) -> Result<String> {
-    let mut output_lines = vec![
-        "# Generated by Lightignore".to_string(),
-        format!("# Templates: {}", selected.join(", ")),
-        String::new(),
-    ];
+    generate_gitignore_content_with(selected, index, config, &BTreeMap::new())
+}
+
+/// Like [`generate_gitignore_content`], but uses `overrides` instead of the
+/// cached content for the templates it contains
+pub fn generate_gitignore_content_with(
+    selected: &[String],
+    index: &TemplateIndex,
+    config: &LignoreConfig,
+    overrides: &BTreeMap<String, String>,
+) -> Result<String> {
+    let mut builder = GenerateBuilder::new(index)
+        .config(config)
+        .normalize(config.normalize)
+        .templates(selected);
+    for (name, content) in overrides {
+        builder = builder.content_for(name, content);
+    }
+    Ok(builder.build()?.content)
+}
+
+/// Name of the section holding patterns added with
+/// [`GenerateBuilder::extra_patterns`]
+pub const EXTRA_SECTION: &str = "Extra";
+
+/// Name of the section holding the `conditional` patterns of lignore.json
+/// whose condition holds
+pub const CONDITIONAL_SECTION: &str = "Conditional";
+
+/// How generated content is combined with an existing file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The generated content replaces the file
+    #[default]
+    Overwrite,
+    /// The generated content goes into a lightignore block, keeping
+    /// everything written outside it
+    ManagedBlock,
+}
+
+/// Generated content together with what went into it
+#[derive(Debug)]
+pub struct Generated {
+    pub content: String,
+    pub report: GenerateReport,
+}
+
+#[derive(Debug, Default)]
+pub struct GenerateReport {
+    pub sections: Vec<SectionReport>,
+    /// Selected names that are neither cached nor custom templates; their
+    /// sections are empty
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SectionReport {
+    pub name: String,
+    /// Patterns written for this section
+    pub patterns: usize,
+    /// Patterns left out because an earlier section already had them
+    pub duplicates: Vec<String>,
+}
+
+/// Builds .gitignore content from cached and custom templates plus extra
+/// patterns, for tools that generate without the CLI
+#[derive(Debug)]
+pub struct GenerateBuilder<'a> {
+    index: &'a TemplateIndex,
+    config: Option<&'a LignoreConfig>,
+    templates: Vec<String>,
+    overrides: BTreeMap<String, String>,
+    extra: Vec<String>,
+    dedup: bool,
+    annotate: bool,
+    minify: bool,
+    normalize: bool,
+    merge: MergeStrategy,
+    existing: String,
+}
+
+impl<'a> GenerateBuilder<'a> {
+    /// Starts with no templates selected, deduplicated and annotated output,
+    /// and [`MergeStrategy::Overwrite`]
+    pub fn new(index: &'a TemplateIndex) -> Self {
+        Self {
+            index,
+            config: None,
+            templates: Vec::new(),
+            overrides: BTreeMap::new(),
+            extra: Vec::new(),
+            dedup: true,
+            annotate: true,
+            minify: false,
+            normalize: false,
+            merge: MergeStrategy::Overwrite,
+            existing: String::new(),
+        }
+    }
+
+    /// Project config whose custom templates may be selected
+    pub fn config(mut self, config: &'a LignoreConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Adds a template; sections appear in the order templates are added
+    pub fn template(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.templates.contains(&name) {
+            self.templates.push(name);
+        }
+        self
+    }
+
+    pub fn templates<I, S>(self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        names
+            .into_iter()
+            .fold(self, |builder, name| builder.template(name))
+    }
+
+    /// Uses `content` instead of the cached content of `name`
+    pub fn content_for(mut self, name: impl Into<String>, content: impl Into<String>) -> Self {
+        self.overrides.insert(name.into(), content.into());
+        self
+    }
+
+    /// Patterns written in a final section after every template
+    pub fn extra_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extra.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Leave out patterns an earlier section already has (default: on)
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Write the file header and a header line per section (default: on)
+    pub fn annotate(mut self, annotate: bool) -> Self {
+        self.annotate = annotate;
+        self
+    }
+
+    /// Drop template comments and blank lines (default: off)
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.minify = minify;
+        self
+    }
+
+    /// Write patterns in their canonical form, so equivalent patterns from
+    /// different templates are deduplicated (default: off)
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Combine the output with `existing` file content using `strategy`
+    pub fn merge(mut self, strategy: MergeStrategy, existing: impl Into<String>) -> Self {
+        self.merge = strategy;
+        self.existing = existing.into();
+        self
+    }
+
+    pub fn build(&self) -> Result<Generated> {
+        let default_config = LignoreConfig::default();
+        let config = self.config.unwrap_or(&default_config);
+
+        let mut output_lines = Vec::new();
+        if self.annotate {
+            output_lines.push(GENERATED_HEADER.to_string());
+            output_lines.push(format!("# Templates: {}", self.templates.join(", ")));
+            if !self.minify {
+                output_lines.push(String::new());
+            }
+        }
+
+        let mut report = GenerateReport::default();
+        let mut seen_patterns = BTreeSet::new();
+        // Sections written from the config and the builder, after every
+        // template
+        let mut own_sections = Vec::new();
+        let conditional = conditional_patterns(config)?;
+        if !conditional.is_empty() {
+            own_sections.push((CONDITIONAL_SECTION.to_string(), conditional));
+        }
+        if !self.extra.is_empty() {
+            own_sections.push((EXTRA_SECTION.to_string(), self.extra.join("\n")));
+        }
+        let sections = self.templates.iter().map(|name| (name, None)).chain(
+            own_sections
+                .iter()
+                .map(|(name, content)| (name, Some(content))),
+        );
+        for (idx, (name, own)) in sections.enumerate() {
+            if idx > 0 && !self.minify {
+                output_lines.push(String::new());
+            }
+            if self.annotate {
+                output_lines.push(section_header_line(name));
+            }
+
+            let content = if let Some(content) = own {
+                content.clone()
+            } else if let Some(content) = self.overrides.get(name) {
+                content.clone()
+            } else {
+                if self.index.get(name).is_none() && !config.custom.contains_key(name) {
+                    report.missing.push(name.clone());
+                }
+                load_template_content(name, self.index, config)?
+            };
+            let mut section = SectionReport {
+                name: name.clone(),
+                ..Default::default()
+            };
+            let body_start = output_lines.len();
+            self.process_template_lines(
+                &content,
+                &mut output_lines,
+                &mut seen_patterns,
+                &mut section,
+            );
+            trim_blank_lines(&mut output_lines, body_start);
+            report.sections.push(section);
+        }
+
+        // Newline-terminated, like the templates themselves
+        let mut generated = output_lines.join("\n");
+        if !generated.is_empty() && !generated.ends_with('\n') {
+            generated.push('\n');
+        }
+        let content = match self.merge {
+            MergeStrategy::Overwrite => generated,
+            MergeStrategy::ManagedBlock => {
+                managed::replace_or_append(&self.existing, &managed::PROJECT, &generated)
+            }
+        };
+        Ok(Generated { content, report })
+    }
+
+    fn process_template_lines(
+        &self,
+        content: &str,
+        output_lines: &mut Vec<String>,
+        seen_patterns: &mut BTreeSet<String>,
+        section: &mut SectionReport,
+    ) {
+        let lines: Vec<&str> = content.lines().collect();
+
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+
+            if trimmed.starts_with('#') {
+                if !self.minify
+                    && (!self.dedup
+                        || should_include_comment(&lines, i, seen_patterns, self.normalize))
+                {
+                    output_lines.push(line.to_string());
+                }
+            } else if trimmed.is_empty() {
+                if !self.minify {
+                    output_lines.push(line.to_string());
+                }
+            } else {
+                let pattern = if self.normalize {
+                    normalize_pattern(trimmed)
+                } else {
+                    trimmed.to_string()
+                };
+                if !seen_patterns.insert(pattern.clone()) && self.dedup {
+                    section.duplicates.push(trimmed.to_string());
+                    continue;
+                }
+                output_lines.push(if self.normalize {
+                    pattern
+                } else {
+                    line.to_string()
+                });
+                section.patterns += 1;
+            }
+        }
+    }
+}
+
+/// The `conditional` patterns of `config` whose condition holds, each
+/// group under a comment naming its condition
+fn conditional_patterns(config: &LignoreConfig) -> Result<String> {
+    let mut lines = Vec::new();
+    for group in &config.conditional {
+        if !Condition::parse(&group.when)?.holds(&config.dir) {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("# When {}", group.when.trim()));
+        lines.extend(group.patterns.iter().cloned());
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Drops blank lines at both ends of the section body starting at `start`,
+/// so exactly one blank line separates sections however templates end
+fn trim_blank_lines(lines: &mut Vec<String>, start: usize) {
+    while lines.len() > start && lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    let leading = lines[start..]
+        .iter()
+        .take_while(|line| line.trim().is_empty())
+        .count();
+    lines.drain(start..start + leading);
+}
 
-    let mut seen_patterns = BTreeSet::new();
+/// Splits a generated file back into the content of each template section
+pub fn read_sections(content: &str) -> BTreeMap<String, String> {
+    let mut sections = BTreeMap::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in content.lines() {
+        if let Some(name) = section_header(line) {
+            if let Some((name, lines)) = current.take() {
+                sections.insert(name, lines.join("\n").trim_end().to_string());
+            }
+            current = Some((name.to_string(), Vec::new()));
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    if let Some((name, lines)) = current {
+        sections.insert(name, lines.join("\n").trim_end().to_string());
+    }
+    sections
+}
+
+/// The `# ===== name =====` line that starts the section of template `name`
+pub fn section_header_line(name: &str) -> String {
+    format!("# ===== {} =====", name)
+}
+
+/// The template name of a `# ===== name =====` section header
+fn section_header(line: &str) -> Option<&str> {
+    line.strip_prefix("# ===== ")
+        .and_then(|rest| rest.strip_suffix(" ====="))
+}
+
+/// Lines of section `name`, from its header to its last non-blank line
+/// before the next section header. Nothing marks where the last section
+/// ends, so it stops before the first line that is not in `template`, the
+/// text the section was generated from, or at its first blank line when
+/// that is unknown; lines added after it stay outside the section.
+fn section_range(lines: &[&str], name: &str, template: Option<&str>) -> Option<(usize, usize)> {
+    let start = lines
+        .iter()
+        .position(|line| section_header(line) == Some(name))?;
+    let body = &lines[start + 1..];
+    let len = match body.iter().position(|line| section_header(line).is_some()) {
+        Some(offset) => offset,
+        None => last_section_len(body, template),
+    };
+    let mut end = start + 1 + len;
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    Some((start, end))
+}
+
+/// How many lines of `body`, the lines after the last section header,
+/// belong to that section
+fn last_section_len(body: &[&str], template: Option<&str>) -> usize {
+    // Compared in canonical form, as `normalize` may have rewritten them
+    let known: Option<BTreeSet<String>> = template.map(|template| {
+        template
+            .lines()
+            .map(|line| normalize_pattern(line.trim()))
+            .collect()
+    });
+    body.iter()
+        .position(|line| {
+            let trimmed = line.trim();
+            match &known {
+                Some(known) => !trimmed.is_empty() && !known.contains(&normalize_pattern(trimmed)),
+                None => trimmed.is_empty(),
+            }
+        })
+        .unwrap_or(body.len())
+}
+
+/// Removes section `name` from `content` together with the blank lines that
+/// separated it, returning `None` when the file has no such section. Lines
+/// outside the section, including ones after the last section that are not
+/// in `template`, are kept as they are.
+pub fn remove_section(content: &str, name: &str, template: Option<&str>) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let (mut start, mut end) = section_range(&lines, name, template)?;
+    while end < lines.len() && lines[end].trim().is_empty() {
+        end += 1;
+    }
+    if end == lines.len() {
+        while start > 0 && lines[start - 1].trim().is_empty() {
+            start -= 1;
+        }
+    }
+    let mut result = lines[..start].to_vec();
+    result.extend(&lines[end..]);
+    let mut content = result.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    Some(content)
+}
+
+/// Puts `body` under the header of section `name` in `content`: in place of
+/// that section's body when the file has one, otherwise appended after a
+/// blank line. Lines outside the section, including ones after the last
+/// section that are not in `template`, are kept as they are.
+pub fn put_section(content: &str, name: &str, body: &str, template: &str) -> String {
+    let header = section_header_line(name);
+    let lines: Vec<&str> = content.lines().collect();
+    let mut section = vec![header.as_str()];
+    section.extend(body.trim_end().lines());
 
-    for (idx, key) in selected.iter().enumerate() {
-        if idx > 0 {
-            output_lines.push(String::new());
+    let result = match section_range(&lines, name, Some(template)) {
+        Some((start, end)) => {
+            let mut result = lines[..start].to_vec();
+            result.extend(section);
+            result.extend(&lines[end..]);
+            result
         }
-        output_lines.push(format!("# ===== {} =====", key));
+        None => {
+            let mut result = lines;
+            while result.last().is_some_and(|line| line.trim().is_empty()) {
+                result.pop();
+            }
+            if !result.is_empty() {
+                result.push("");
+            }
+            result.extend(section);
+            result
+        }
+    };
+    let mut content = result.join("\n");
+    content.push('\n');
+    content
+}
 
-        let content = load_template_content(key, index, config)?;
-        process_template_lines(&content, &mut output_lines, &mut seen_patterns);
+/// Lists the sections of a generated file on its `# Templates:` line, in
+/// file order; files without lightignore's header are returned unchanged
+pub fn refresh_templates_line(content: &str) -> String {
+    if !content.starts_with(GENERATED_HEADER) {
+        return content.to_string();
+    }
+    let names: Vec<&str> = content.lines().filter_map(section_header).collect();
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    if let Some(line) = lines
+        .iter_mut()
+        .take(2)
+        .find(|line| line.starts_with("# Templates: "))
+    {
+        *line = format!("# Templates: {}", names.join(", "));
     }
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
 
-    Ok(output_lines.join("\n"))
+/// Adds to `existing` the patterns of `generated` it does not contain yet,
+/// at the end of the section they come from when the file already has it,
+/// otherwise under a new header for that section, and returns the new
+/// content with the number of patterns added. Nothing in `existing` is
+/// changed or removed.
+pub fn append_missing(existing: &str, generated: &str) -> (String, usize) {
+    // Compared in canonical form, so an existing `**/foo` counts as `foo`
+    let present: BTreeSet<String> = existing
+        .lines()
+        .map(|line| normalize_pattern(line.trim()))
+        .collect();
+    let existing_lines: Vec<&str> = existing.lines().collect();
+    let bodies = read_sections(generated);
+    // Patterns for sections already in the file, by the line they go before
+    let mut inserted: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    let mut added: Vec<String> = Vec::new();
+    let mut section: Option<&str> = None;
+    let mut header: Option<&str> = None;
+    let mut count = 0;
+    for line in generated.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("# ===== ") {
+            section = section_header(line);
+            header = Some(line);
+        } else if !trimmed.is_empty()
+            && !trimmed.starts_with('#')
+            && !present.contains(&normalize_pattern(trimmed))
+        {
+            count += 1;
+            if let Some((_, end)) = section.and_then(|name| {
+                section_range(&existing_lines, name, bodies.get(name).map(String::as_str))
+            }) {
+                inserted.entry(end).or_default().push(line);
+                continue;
+            }
+            if let Some(header) = header.take() {
+                if !added.is_empty() {
+                    added.push(String::new());
+                }
+                added.push(header.to_string());
+            }
+            added.push(line.to_string());
+        }
+    }
+    if count == 0 {
+        return (existing.to_string(), 0);
+    }
+
+    let mut lines: Vec<&str> = Vec::new();
+    for (at, line) in existing_lines.iter().enumerate() {
+        if let Some(patterns) = inserted.get(&at) {
+            lines.extend(patterns);
+        }
+        lines.push(line);
+    }
+    if let Some(patterns) = inserted.get(&existing_lines.len()) {
+        lines.extend(patterns);
+    }
+    let mut content = lines.join("\n");
+    if !content.is_empty() {
+        content.push('\n');
+        if !added.is_empty() && !content.ends_with("\n\n") {
+            content.push('\n');
+        }
+    }
+    if !added.is_empty() {
+        content.push_str(&added.join("\n"));
+        content.push('\n');
+    }
+    (content, count)
 }
 
-fn load_template_content(
+/// Refuses a template the safe-mode scan quarantined, unless the user
+/// trusts it
+pub fn ensure_not_quarantined(name: &str, index: &TemplateIndex) -> Result<()> {
+    match index.get(name) {
+        Some(entry) if entry.is_quarantined() => anyhow::bail!(
+            "Template {} is quarantined because {}.\nReview it with `lignore show {}` and add it to `trusted` in the global config to use it anyway.",
+            name,
+            entry.notes.quarantine.join("; "),
+            name
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Reads one official or custom template
+pub fn load_template_content(
     key: &str,
     index: &TemplateIndex,
     config: &LignoreConfig,
 ) -> Result<String> {
-    if let Some(path) = index.get(key) {
+    if let Some(entry) = index.get(key) {
+        ensure_not_quarantined(key, index)?;
         // Load from cache (official template)
-        fs::read_to_string(path).with_context(|| format!("reading template {}", key))
+        entry.read_verified(key)
     } else if let Some(custom_lines) = config.custom.get(key) {
         // Load from custom template (array of lines)
-        Ok(custom_lines.join("\n"))
+        substitute(&custom_lines.join("\n"), &config.variables)
+            .with_context(|| format!("filling in custom template {}", key))
     } else {
         // Template not found, return empty content
         Ok(String::new())
     }
 }
 
-fn process_template_lines(
-    content: &str,
-    output_lines: &mut Vec<String>,
-    seen_patterns: &mut BTreeSet<String>,
-) {
-    let lines: Vec<&str> = content.lines().collect();
-    let mut i = 0;
-
-    while i < lines.len() {
-        let line = lines[i];
-        let trimmed = line.trim();
-
-        if trimmed.starts_with('#') {
-            if should_include_comment(&lines, i, seen_patterns) {
-                output_lines.push(line.to_string());
-            }
-        } else if trimmed.is_empty() {
-            output_lines.push(line.to_string());
-        } else {
-            if seen_patterns.insert(trimmed.to_string()) {
-                output_lines.push(line.to_string());
-            }
-        }
-        i += 1;
-    }
-}
-
 fn should_include_comment(
     lines: &[&str],
     comment_idx: usize,
     seen_patterns: &BTreeSet<String>,
+    normalize: bool,
 ) -> bool {
     // Look ahead to see if the next non-comment line is a pattern we've seen
     let mut j = comment_idx + 1;
@@ -91,6 +612,9 @@ fn should_include_comment(
             continue;
         }
         if !next_line.is_empty() {
+            if normalize {
+                return !seen_patterns.contains(&normalize_pattern(next_line));
+            }
             return !seen_patterns.contains(next_line);
         }
         break;
@@ -100,12 +624,91 @@ fn should_include_comment(
 }
 
 /// Ensures output directory exists
-pub fn ensure_output_directory(output: &std::path::PathBuf) -> Result<()> {
-    if let Some(parent) = output.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("creating output directory {}", parent.display()))?;
-        }
+pub fn ensure_output_directory(output: &std::path::Path) -> Result<()> {
+    if let Some(parent) = output.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("creating output directory {}", parent.display()))?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str =
+        "# ===== Rust =====\ntarget/\n\n# ===== Node =====\nnode_modules/\n\n# Logs\n*.log\n";
+
+    #[test]
+    fn removing_a_middle_section_keeps_the_others() {
+        let content = remove_section(FILE, "Rust", None).unwrap();
+        assert_eq!(
+            content,
+            "# ===== Node =====\nnode_modules/\n\n# Logs\n*.log\n"
+        );
+    }
+
+    #[test]
+    fn removing_the_last_section_keeps_lines_added_after_it() {
+        let content = format!("{}\n# Local\nsecrets.env\n", FILE);
+        let template = "node_modules/\n\n# Logs\n*.log\n";
+        let content = remove_section(&content, "Node", Some(template)).unwrap();
+        assert_eq!(
+            content,
+            "# ===== Rust =====\ntarget/\n\n# Local\nsecrets.env\n"
+        );
+    }
+
+    #[test]
+    fn last_section_ends_at_its_first_blank_line_without_the_template() {
+        let content = format!("{}\nsecrets.env\n", FILE);
+        let content = remove_section(&content, "Node", None).unwrap();
+        assert_eq!(
+            content,
+            "# ===== Rust =====\ntarget/\n\n# Logs\n*.log\n\nsecrets.env\n"
+        );
+    }
+
+    #[test]
+    fn replacing_the_last_section_keeps_lines_added_after_it() {
+        let content = format!("{}\n# Local\nsecrets.env\n", FILE);
+        let template = "node_modules/\n.npm/\n\n# Logs\n*.log\n";
+        let content = put_section(&content, "Node", "node_modules/\n.npm/", template);
+        assert_eq!(
+            content,
+            "# ===== Rust =====\ntarget/\n\n# ===== Node =====\nnode_modules/\n.npm/\n\n# Local\nsecrets.env\n"
+        );
+    }
+
+    #[test]
+    fn appended_patterns_stay_inside_the_last_section() {
+        let existing = "# ===== Node =====\nnode_modules/\n\nsecrets.env\n";
+        let generated = "# ===== Node =====\nnode_modules/\ndist/\n";
+        let (content, added) = append_missing(existing, generated);
+        assert_eq!(added, 1);
+        assert_eq!(
+            content,
+            "# ===== Node =====\nnode_modules/\ndist/\n\nsecrets.env\n"
+        );
+    }
+
+    #[test]
+    fn appended_patterns_of_a_new_section_get_its_header() {
+        let existing = "# ===== Node =====\nnode_modules/\n";
+        let generated = "# ===== Node =====\nnode_modules/\n\n# ===== Rust =====\ntarget/\n";
+        let (content, added) = append_missing(existing, generated);
+        assert_eq!(added, 1);
+        assert_eq!(
+            content,
+            "# ===== Node =====\nnode_modules/\n\n# ===== Rust =====\ntarget/\n"
+        );
+    }
+
+    #[test]
+    fn putting_a_new_section_appends_it() {
+        let content = put_section("secrets.env\n", "Rust", "target/", "target/\n");
+        assert_eq!(content, "secrets.env\n\n# ===== Rust =====\ntarget/\n");
+    }
+}
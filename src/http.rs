@@ -0,0 +1,184 @@
+use anyhow::{Context, Result};
+use reqwest::header::{AUTHORIZATION, ETAG, HeaderMap, LAST_MODIFIED};
+use reqwest::{Client, RequestBuilder, Response, Url};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::rate_limit::MAX_CONCURRENCY;
+
+const USER_AGENT: &str = "lightignore/0.1";
+
+/// How long an idle pooled connection is kept around for reuse
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Tries for a request the server keeps answering with a 5xx status
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry, doubled for each one after it
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Builds the shared HTTP client.
+///
+/// HTTP/2 is negotiated via ALPN so all template downloads from a host share
+/// a single multiplexed connection; `http1` forces HTTP/1.1 for proxies and
+/// mirrors that mishandle HTTP/2, in which case the pool keeps enough idle
+/// connections per host to serve every concurrent download.
+pub fn build_client(http1: bool) -> Result<Client> {
+    let mut builder = Client::builder()
+        .user_agent(USER_AGENT)
+        .pool_max_idle_per_host(MAX_CONCURRENCY)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(TCP_KEEPALIVE);
+
+    builder = if http1 {
+        builder.http1_only()
+    } else {
+        builder
+            .http2_adaptive_window(true)
+            .http2_keep_alive_interval(TCP_KEEPALIVE)
+            .http2_keep_alive_while_idle(true)
+    };
+
+    builder.build().context("building HTTP client")
+}
+
+static DEBUG_HTTP: AtomicBool = AtomicBool::new(false);
+
+// Query parameters that may carry credentials, such as pre-signed S3 URLs
+const SECRET_PARAMS: &[&str] = &[
+    "token",
+    "access_token",
+    "sig",
+    "signature",
+    "x-amz-signature",
+    "x-amz-credential",
+    "x-amz-security-token",
+];
+
+const RATE_LIMIT_HEADERS: &[&str] = &[
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-used",
+    "x-ratelimit-reset",
+    "x-ratelimit-resource",
+    "retry-after",
+];
+
+/// Turns on request tracing for `--debug-http`
+pub fn enable_debug_http() {
+    DEBUG_HTTP.store(true, Ordering::Relaxed);
+}
+
+/// Sends a request, tracing it to stderr when `--debug-http` is set. A
+/// server error is retried after a short, growing delay, since GitHub and
+/// mirrors answer the odd request with a 502 or 503 under load.
+pub async fn send(mut request: RequestBuilder) -> Result<Response> {
+    let mut attempt = 1;
+    loop {
+        // Requests with a streaming body cannot be sent again
+        let retry = request.try_clone().filter(|_| attempt < MAX_ATTEMPTS);
+        let response = send_once(request).await?;
+        match retry {
+            Some(next) if response.status().is_server_error() => {
+                let delay = RETRY_DELAY * 2u32.pow(attempt - 1);
+                if DEBUG_HTTP.load(Ordering::Relaxed) {
+                    eprintln!("[http]     retrying in {:.2?}", delay);
+                }
+                tokio::time::sleep(delay).await;
+                request = next;
+                attempt += 1;
+            }
+            _ => return Ok(response),
+        }
+    }
+}
+
+async fn send_once(request: RequestBuilder) -> Result<Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    if !DEBUG_HTTP.load(Ordering::Relaxed) {
+        return Ok(client.execute(request).await?);
+    }
+
+    let method = request.method().clone();
+    let url = redact_url(request.url());
+    let auth = if request.headers().contains_key(AUTHORIZATION) {
+        " (authorization: [redacted])"
+    } else {
+        ""
+    };
+    eprintln!("[http] --> {} {}{}", method, url, auth);
+
+    let started = Instant::now();
+    match client.execute(request).await {
+        Ok(response) => {
+            eprintln!(
+                "[http] <-- {} {} {} {:?} in {:.2?}",
+                response.status(),
+                method,
+                url,
+                response.version(),
+                started.elapsed()
+            );
+            let headers = response.headers();
+            eprintln!(
+                "[http]     cache: {}",
+                cache_validation(response.status().as_u16(), headers)
+            );
+            for name in RATE_LIMIT_HEADERS {
+                if let Some(value) = headers.get(*name).and_then(|v| v.to_str().ok()) {
+                    eprintln!("[http]     {}: {}", name, value);
+                }
+            }
+            Ok(response)
+        }
+        Err(e) => {
+            eprintln!(
+                "[http] <-- failed {} {} in {:.2?}: {}",
+                method,
+                url,
+                started.elapsed(),
+                e
+            );
+            Err(e.into())
+        }
+    }
+}
+
+/// Describes the validators a response offers for conditional requests
+fn cache_validation(status: u16, headers: &HeaderMap) -> String {
+    if status == 304 {
+        return "not modified".to_string();
+    }
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    match (header(ETAG), header(LAST_MODIFIED)) {
+        (Some(etag), _) => format!("etag {}", etag),
+        (None, Some(modified)) => format!("last-modified {}", modified),
+        (None, None) => "no validators".to_string(),
+    }
+}
+
+/// Strips credentials from a URL before it is logged
+fn redact_url(url: &Url) -> String {
+    let mut url = url.clone();
+    if !url.username().is_empty() || url.password().is_some() {
+        let _ = url.set_username("redacted");
+        let _ = url.set_password(None);
+    }
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| {
+                if SECRET_PARAMS.contains(&key.to_ascii_lowercase().as_str()) {
+                    (key.into_owned(), "[redacted]".to_string())
+                } else {
+                    (key.into_owned(), value.into_owned())
+                }
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url.to_string()
+}
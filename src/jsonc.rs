@@ -0,0 +1,470 @@
+//! JSON with comments, for lignore.json and config.json: `//` and `/* */`
+//! comments and trailing commas are accepted. When lightignore rewrites a
+//! file, comments follow the key or array entry they were written above or
+//! beside; comments on entries that were removed are dropped.
+
+use std::collections::BTreeMap;
+
+/// Where a comment sits: the key path of the entry it belongs to, with
+/// array entries identified by their string value
+type Path = Vec<String>;
+
+/// Marks comments written before the closing bracket of a container
+const CLOSING: &str = "\u{0}end";
+
+/// Marks comments written after the document
+const TRAILER: &str = "\u{0}trailer";
+
+/// Turns JSONC into plain JSON by blanking out comments and dropping
+/// trailing commas; line numbers and string contents are kept
+pub fn strip(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    // Position in `out` of a comma that may turn out to be trailing
+    let mut pending_comma: Option<usize> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                pending_comma = None;
+                out.push(c);
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    out.push(c);
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ',' => {
+                pending_comma = Some(out.len());
+                out.push(c);
+            }
+            ']' | '}' => {
+                if let Some(position) = pending_comma.take() {
+                    out.replace_range(position..position + 1, " ");
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => out.push(c),
+            c => {
+                pending_comma = None;
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Comments of a JSONC document, keyed by the entry they belong to
+#[derive(Debug, Default)]
+pub struct Comments {
+    /// Comments on their own lines above an entry
+    leading: BTreeMap<Path, Vec<String>>,
+    /// A comment after an entry on the same line
+    trailing: BTreeMap<Path, String>,
+}
+
+impl Comments {
+    pub fn collect(text: &str) -> Self {
+        let mut comments = Self::default();
+        let mut pending: Vec<String> = Vec::new();
+        // The last entry seen, and the line it started on
+        let mut last: Option<(Path, usize)> = None;
+        scan(text, |event| match event {
+            Event::Comment {
+                text,
+                line,
+                own_line,
+            } => match &last {
+                Some((path, last_line)) if !own_line && *last_line == line => {
+                    comments.trailing.insert(path.clone(), text.to_string());
+                }
+                _ => pending.push(text.to_string()),
+            },
+            Event::Entry { path, line } => {
+                if !pending.is_empty() {
+                    comments
+                        .leading
+                        .entry(path.clone())
+                        .or_default()
+                        .append(&mut pending);
+                }
+                last = Some((path, line));
+            }
+            Event::Close { mut path, .. } => {
+                if !pending.is_empty() {
+                    path.push(CLOSING.to_string());
+                    comments
+                        .leading
+                        .entry(path)
+                        .or_default()
+                        .append(&mut pending);
+                }
+            }
+        });
+        // Comments after the document stay at its end
+        if !pending.is_empty() {
+            comments
+                .leading
+                .entry(vec![TRAILER.to_string()])
+                .or_default()
+                .append(&mut pending);
+        }
+        comments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.trailing.is_empty()
+    }
+
+    /// Adds the comments to `json`, pretty-printed JSON with one entry per
+    /// line, next to the entries they belong to
+    pub fn apply(mut self, json: &str) -> String {
+        let mut before: BTreeMap<usize, Vec<(String, bool)>> = BTreeMap::new();
+        let mut after: BTreeMap<usize, String> = BTreeMap::new();
+        scan(json, |event| match event {
+            Event::Entry { path, line } => {
+                if let Some(comments) = self.leading.remove(&path) {
+                    let entry = before.entry(line).or_default();
+                    entry.extend(comments.into_iter().map(|comment| (comment, false)));
+                }
+                if let Some(comment) = self.trailing.remove(&path) {
+                    after.insert(line, comment);
+                }
+            }
+            Event::Close { mut path, line } => {
+                path.push(CLOSING.to_string());
+                if let Some(comments) = self.leading.remove(&path) {
+                    let entry = before.entry(line).or_default();
+                    entry.extend(comments.into_iter().map(|comment| (comment, true)));
+                }
+            }
+            Event::Comment { .. } => {}
+        });
+
+        let mut out = String::with_capacity(json.len());
+        for (number, line) in json.lines().enumerate() {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            for (comment, inside) in before.remove(&number).unwrap_or_default() {
+                out.push_str(indent);
+                if inside {
+                    out.push_str("  ");
+                }
+                out.push_str(&comment);
+                out.push('\n');
+            }
+            out.push_str(line);
+            if let Some(comment) = after.remove(&number) {
+                out.push(' ');
+                out.push_str(&comment);
+            }
+            out.push('\n');
+        }
+        // Comments that followed the document
+        if let Some(comments) = self.leading.remove(&vec![TRAILER.to_string()]) {
+            for comment in comments {
+                out.push_str(&comment);
+                out.push('\n');
+            }
+        }
+        if !json.ends_with('\n') {
+            out.pop();
+        }
+        out
+    }
+}
+
+enum Event<'a> {
+    /// A comment, with whether nothing but whitespace precedes it on its line
+    Comment {
+        text: &'a str,
+        line: usize,
+        own_line: bool,
+    },
+    /// A value starts: the document, an object member or an array element
+    Entry { path: Path, line: usize },
+    /// The container at `path` closes
+    Close { path: Path, line: usize },
+}
+
+enum Frame {
+    Object {
+        /// Key of the member being read; `None` until its key is seen
+        key: Option<String>,
+    },
+    Array {
+        index: usize,
+    },
+}
+
+/// Walks a JSONC document and reports its comments and the start of every
+/// entry. Malformed input is scanned as far as it makes sense.
+fn scan<'a>(text: &'a str, mut on_event: impl FnMut(Event<'a>)) {
+    let bytes = text.as_bytes();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut path: Path = Vec::new();
+    let mut line = 0;
+    let mut line_start = 0;
+    // Whether the next value starts an entry that has not been reported
+    let mut expect_value = true;
+    let mut i = 0;
+
+    let path_of = |path: &Path, segment: Option<String>| {
+        let mut path = path.clone();
+        path.extend(segment);
+        path
+    };
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b'\n' => {
+                line += 1;
+                line_start = i + 1;
+                i += 1;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'/') || bytes.get(i + 1) == Some(&b'*') => {
+                let start = i;
+                let own_line = text[line_start..start].trim().is_empty();
+                let comment_line = line;
+                if bytes[i + 1] == b'/' {
+                    while i < bytes.len() && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                } else {
+                    i += 2;
+                    while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                        if bytes[i] == b'\n' {
+                            line += 1;
+                            line_start = i + 1;
+                        }
+                        i += 1;
+                    }
+                    i = (i + 2).min(bytes.len());
+                }
+                on_event(Event::Comment {
+                    text: text[start..i].trim_end(),
+                    line: comment_line,
+                    own_line,
+                });
+            }
+            b'"' => {
+                let (value, end) = read_string(text, i);
+                match stack.last_mut() {
+                    // An object key starts a member
+                    Some(Frame::Object { key }) if key.is_none() => {
+                        *key = Some(value.clone());
+                        on_event(Event::Entry {
+                            path: path_of(&path, Some(value)),
+                            line,
+                        });
+                        expect_value = false;
+                    }
+                    Some(Frame::Array { .. }) if expect_value => {
+                        on_event(Event::Entry {
+                            path: path_of(&path, Some(format!("\"{}\"", value))),
+                            line,
+                        });
+                        expect_value = false;
+                    }
+                    None if expect_value => {
+                        on_event(Event::Entry {
+                            path: path.clone(),
+                            line,
+                        });
+                        expect_value = false;
+                    }
+                    _ => {}
+                }
+                i = end;
+            }
+            b'{' | b'[' => {
+                let segment = match stack.last() {
+                    Some(Frame::Object { key }) => key.clone(),
+                    Some(Frame::Array { index }) => Some(format!("#{}", index)),
+                    None => None,
+                };
+                if expect_value && !matches!(stack.last(), Some(Frame::Object { .. })) {
+                    on_event(Event::Entry {
+                        path: path_of(&path, segment.clone()),
+                        line,
+                    });
+                }
+                if let Some(segment) = segment {
+                    path.push(segment);
+                }
+                stack.push(if c == b'{' {
+                    Frame::Object { key: None }
+                } else {
+                    Frame::Array { index: 0 }
+                });
+                expect_value = true;
+                i += 1;
+            }
+            b'}' | b']' => {
+                stack.pop();
+                on_event(Event::Close {
+                    path: path.clone(),
+                    line,
+                });
+                if !stack.is_empty() {
+                    path.pop();
+                }
+                expect_value = false;
+                i += 1;
+            }
+            b',' => {
+                match stack.last_mut() {
+                    Some(Frame::Object { key }) => *key = None,
+                    Some(Frame::Array { index }) => *index += 1,
+                    None => {}
+                }
+                expect_value = true;
+                i += 1;
+            }
+            b':' => {
+                expect_value = true;
+                i += 1;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                // Numbers, booleans and null
+                if expect_value {
+                    match stack.last() {
+                        Some(Frame::Array { index }) => on_event(Event::Entry {
+                            path: path_of(&path, Some(format!("#{}", index))),
+                            line,
+                        }),
+                        None => on_event(Event::Entry {
+                            path: path.clone(),
+                            line,
+                        }),
+                        Some(Frame::Object { .. }) => {}
+                    }
+                    expect_value = false;
+                }
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Reads the string literal starting at `start`, returning its raw content
+/// and the index after its closing quote
+fn read_string(text: &str, start: usize) -> (String, usize) {
+    let bytes = text.as_bytes();
+    let mut i = start + 1;
+    let mut escaped = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if !escaped => escaped = true,
+            b'"' if !escaped => break,
+            _ => escaped = false,
+        }
+        i += 1;
+    }
+    let end = i.min(bytes.len());
+    (text[start + 1..end].to_string(), (end + 1).min(bytes.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strips `text`, parses it and writes it back the way lignore.json is
+    /// saved, with its comments
+    fn round_trip(text: &str) -> String {
+        let value: serde_json::Value = serde_json::from_str(&strip(text)).unwrap();
+        let json = serde_json::to_string_pretty(&value).unwrap();
+        Comments::collect(text).apply(&json)
+    }
+
+    #[test]
+    fn strip_blanks_comments_and_keeps_lines() {
+        let text = "{\n  // line\n  \"a\": 1, /* block */\n  /* two\n     lines */\n  \"b\": 2\n}";
+        let stripped = strip(text);
+        assert_eq!(stripped.lines().count(), text.lines().count());
+        let value: serde_json::Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value, serde_json::json!({ "a": 1, "b": 2 }));
+    }
+
+    #[test]
+    fn strip_keeps_comment_markers_inside_strings() {
+        let text = r#"{"url": "https://example.com/*x*/", "quote": "say \"//\""}"#;
+        let value: serde_json::Value = serde_json::from_str(&strip(text)).unwrap();
+        assert_eq!(value["url"], "https://example.com/*x*/");
+        assert_eq!(value["quote"], "say \"//\"");
+    }
+
+    #[test]
+    fn strip_drops_trailing_commas() {
+        let text = "{\n  \"templates\": [\"Rust\", \"Node\", // last\n  ],\n}";
+        let value: serde_json::Value = serde_json::from_str(&strip(text)).unwrap();
+        assert_eq!(value, serde_json::json!({ "templates": ["Rust", "Node"] }));
+    }
+
+    #[test]
+    fn line_comments_survive_a_round_trip() {
+        let text = "{\n  // Picked for the backend\n  \"templates\": [\n    \"Rust\", // compiler output\n    \"Node\"\n  ]\n}";
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn block_comments_survive_a_round_trip() {
+        let text = "/* Project config */\n{\n  /* Shared with CI */\n  \"pin\": \"abc123\"\n}";
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn comments_before_a_closing_bracket_and_after_the_document_are_kept() {
+        let text = "{\n  \"templates\": [\n    \"Rust\"\n    // more later\n  ]\n}\n// end";
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_not_comments() {
+        let text = "{\n  \"api_url\": \"http://localhost//api\",\n  \"note\": \"/* kept */\"\n}";
+        assert!(Comments::collect(text).is_empty());
+        assert_eq!(round_trip(text), text);
+    }
+
+    #[test]
+    fn trailing_commas_are_dropped_on_a_round_trip() {
+        let text = "{\n  \"templates\": [\n    \"Rust\", // only one\n  ],\n}";
+        assert_eq!(
+            round_trip(text),
+            "{\n  \"templates\": [\n    \"Rust\" // only one\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn comments_on_removed_entries_are_dropped() {
+        let text = "{\n  // old\n  \"pin\": \"abc\",\n  \"templates\": []\n}";
+        let comments = Comments::collect(text);
+        let json = "{\n  \"templates\": []\n}";
+        assert_eq!(comments.apply(json), json);
+    }
+}
@@ -0,0 +1,45 @@
+//! The ignore files lightignore can generate. They share the same templates
+//! and gitignore-style patterns, and differ in where they are written.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnoreKind {
+    /// .gitignore, read by git
+    #[default]
+    Gitignore,
+    /// .dockerignore, read when building a Docker image
+    Dockerignore,
+    /// .npmignore, read when packing an npm package
+    Npmignore,
+}
+
+impl IgnoreKind {
+    /// Output file written when neither `-o` nor lignore.json names one
+    pub fn file_name(self) -> &'static str {
+        match self {
+            IgnoreKind::Gitignore => ".gitignore",
+            IgnoreKind::Dockerignore => ".dockerignore",
+            IgnoreKind::Npmignore => ".npmignore",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IgnoreKind::Gitignore => "gitignore",
+            IgnoreKind::Dockerignore => "dockerignore",
+            IgnoreKind::Npmignore => "npmignore",
+        }
+    }
+}
+
+impl fmt::Display for IgnoreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,76 @@
+//! Library behind the `lignore` command line tool.
+//!
+//! It exposes [`GenerateBuilder`] so scaffolding tools can generate
+//! .gitignore content from the template cache without going through the
+//! CLI. The modules the binary is built from are public only so it can
+//! use them, and may change in any release.
+
+#[doc(hidden)]
+pub mod app;
+mod archive;
+mod atomic;
+mod bench_data;
+#[doc(hidden)]
+pub mod build_info;
+mod bundle;
+mod clipboard;
+mod companions;
+#[doc(hidden)]
+pub mod completion;
+mod conditions;
+#[doc(hidden)]
+pub mod config;
+mod detect;
+mod excludes;
+#[doc(hidden)]
+pub mod exit;
+mod gitignore;
+#[doc(hidden)]
+pub mod http;
+mod jsonc;
+#[doc(hidden)]
+pub mod kind;
+#[doc(hidden)]
+pub mod limits;
+mod lock;
+mod managed;
+mod matcher;
+mod metadata;
+mod mirror;
+mod normalize;
+#[doc(hidden)]
+pub mod paths;
+#[doc(hidden)]
+pub mod policy;
+mod popularity;
+#[doc(hidden)]
+pub mod preset;
+#[doc(hidden)]
+pub mod progress;
+mod project_guard;
+mod rate_limit;
+mod recipe;
+mod safety;
+#[doc(hidden)]
+pub mod self_updater;
+mod session;
+#[doc(hidden)]
+pub mod settings;
+#[doc(hidden)]
+pub mod sources;
+mod tags;
+#[doc(hidden)]
+pub mod template;
+mod tracked;
+#[doc(hidden)]
+pub mod ui;
+mod usage;
+#[doc(hidden)]
+pub mod validation;
+mod variables;
+mod workspace;
+
+pub use config::LignoreConfig;
+pub use gitignore::{GenerateBuilder, GenerateReport, Generated, MergeStrategy, SectionReport};
+pub use paths::default_cache_dir;
+pub use template::TemplateIndex;
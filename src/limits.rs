@@ -0,0 +1,97 @@
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+// Defaults, used unless the global config overrides them
+pub const DEFAULT_MAX_DOWNLOAD_SIZE: u64 = 10 * 1024 * 1024; // 10MB
+pub const DEFAULT_MAX_CUSTOM_TEMPLATE_SIZE: usize = 100 * 1024; // 100KB
+pub const DEFAULT_MAX_CUSTOM_TEMPLATE_LINES: usize = 10000;
+pub const DEFAULT_WARN_OUTPUT_LINES: usize = 5000;
+pub const DEFAULT_WARN_OUTPUT_SIZE: u64 = 256 * 1024; // 256KB
+
+// Upper bounds for configured limits, so a typo cannot disable the checks
+const CAP_DOWNLOAD_SIZE: u64 = 100 * 1024 * 1024; // 100MB
+const CAP_CUSTOM_TEMPLATE_SIZE: usize = 10 * 1024 * 1024; // 10MB
+const CAP_CUSTOM_TEMPLATE_LINES: usize = 1_000_000;
+const CAP_WARN_OUTPUT_LINES: usize = 1_000_000;
+const CAP_WARN_OUTPUT_SIZE: u64 = 100 * 1024 * 1024; // 100MB
+
+/// Size limits applied to downloaded and custom templates
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Limits {
+    /// Largest template accepted from a source, in bytes
+    pub max_download_size: u64,
+    /// Largest custom template in lignore.json, in bytes
+    pub max_custom_template_size: usize,
+    /// Most lines a custom template in lignore.json may have
+    pub max_custom_template_lines: usize,
+    /// Generated files with more lines than this get a warning, since huge
+    /// ignore files slow down `git status`
+    pub warn_output_lines: usize,
+    /// Generated files larger than this, in bytes, get the same warning
+    pub warn_output_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_download_size: DEFAULT_MAX_DOWNLOAD_SIZE,
+            max_custom_template_size: DEFAULT_MAX_CUSTOM_TEMPLATE_SIZE,
+            max_custom_template_lines: DEFAULT_MAX_CUSTOM_TEMPLATE_LINES,
+            warn_output_lines: DEFAULT_WARN_OUTPUT_LINES,
+            warn_output_size: DEFAULT_WARN_OUTPUT_SIZE,
+        }
+    }
+}
+
+impl Limits {
+    pub fn validate(&self) -> Result<()> {
+        check(
+            "max_download_size",
+            self.max_download_size,
+            CAP_DOWNLOAD_SIZE,
+        )?;
+        check(
+            "max_custom_template_size",
+            self.max_custom_template_size as u64,
+            CAP_CUSTOM_TEMPLATE_SIZE as u64,
+        )?;
+        check(
+            "max_custom_template_lines",
+            self.max_custom_template_lines as u64,
+            CAP_CUSTOM_TEMPLATE_LINES as u64,
+        )?;
+        check(
+            "warn_output_lines",
+            self.warn_output_lines as u64,
+            CAP_WARN_OUTPUT_LINES as u64,
+        )?;
+        check(
+            "warn_output_size",
+            self.warn_output_size,
+            CAP_WARN_OUTPUT_SIZE,
+        )?;
+        Ok(())
+    }
+}
+
+fn check(name: &str, value: u64, cap: u64) -> Result<()> {
+    if value == 0 {
+        anyhow::bail!("Limit '{}' must be greater than 0", name);
+    }
+    if value > cap {
+        anyhow::bail!("Limit '{}' is {} but cannot exceed {}", name, value, cap);
+    }
+    Ok(())
+}
+
+static LIMITS: OnceCell<Limits> = OnceCell::new();
+
+pub fn configure_limits(limits: Limits) {
+    let _ = LIMITS.set(limits);
+}
+
+pub fn get_limits() -> &'static Limits {
+    LIMITS.get_or_init(Limits::default)
+}
@@ -0,0 +1,70 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::atomic::write_atomic;
+
+pub const LOCK_FILE: &str = "lignore.lock";
+pub const LOCK_VERSION: u32 = 1;
+
+/// Records which template content a project's output was generated from,
+/// so later runs can tell when a selected template changed upstream
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ProjectLock {
+    pub version: u32,
+    pub templates: BTreeMap<String, LockedTemplate>,
+}
+
+impl Default for ProjectLock {
+    fn default() -> Self {
+        Self {
+            version: LOCK_VERSION,
+            templates: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct LockedTemplate {
+    /// SHA-256 of the template content used
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source: String,
+}
+
+impl ProjectLock {
+    /// Reads the lockfile, or `None` when the project has none yet
+    pub fn read(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let lock: ProjectLock =
+            serde_json::from_slice(&data).with_context(|| format!("parsing {}", path.display()))?;
+        if lock.version > LOCK_VERSION {
+            anyhow::bail!(
+                "{} version {} is newer than supported ({})",
+                path.display(),
+                lock.version,
+                LOCK_VERSION
+            );
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        write_atomic(path, data)
+    }
+}
+
+/// Hex-encoded SHA-256 of template content
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -1,52 +1,333 @@
-mod app;
 mod cli;
-mod config;
-mod gitignore;
-mod self_updater;
-mod template;
-mod ui;
-mod validation;
-
-use anyhow::Result;
+
+use anyhow::{Context, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::process::ExitCode;
 use tokio::runtime::Runtime;
 
-use app::App;
-use cli::{Cli, Commands};
-use ui::{configure_theme, print_success};
+use cli::{
+    BundleCommands, CacheCommands, Cli, Commands, PresetCommands, SourceCommands, ThemeCommands,
+    WorkspaceCommands,
+};
+use lightignore::app::{App, AppOptions, PickerOutcome, WriteOptions};
+use lightignore::sources::UpdateReport;
+use lightignore::template::TemplateIndex;
+use lightignore::ui::{configure_theme, print_success};
+use lightignore::{
+    build_info, completion, config, exit, http, limits, paths, preset, self_updater, settings,
+    validation,
+};
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(exit::code_for(&e))
+        }
+    }
+}
+
+/// Runs the requested command and returns the exit status to report
+fn run() -> Result<u8> {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // clap exits with 2 on usage errors, which is reserved for drift
+        Err(e) if e.use_stderr() => {
+            let _ = e.print();
+            return Ok(exit::ERROR);
+        }
+        // --help
+        Err(e) => {
+            let _ = e.print();
+            return Ok(exit::SUCCESS);
+        }
+    };
+    if cli.version {
+        let settings = settings::load_settings().ok();
+        print!("{}", build_info::describe(cli.verbose, settings.as_ref()));
+        return Ok(exit::SUCCESS);
+    }
+    // --cache-dir wins over a project cache configured in lignore.json,
+    // which wins over the user-level default
+    let config_path = config::find_config_path(cli.config.as_deref());
+    let project = config::load_or_default_config(&config_path)?;
+    let configured = cli.cache_dir.or_else(|| {
+        project
+            .cache_dir
+            .as_deref()
+            .map(|dir| config::relative_to_config(&config_path, dir))
+    });
+    let cache_dir = match configured {
+        Some(dir) => validation::resolve_cache_dir(&dir)
+            .with_context(|| format!("invalid cache directory {}", dir.display()))?,
+        None => {
+            let dir = paths::default_cache_dir();
+            paths::migrate_legacy_cache(&dir).context("moving the cache to its new location")?;
+            dir
+        }
+    };
+
+    if cli.debug_http {
+        http::enable_debug_http();
+    }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let cache_dir = cli
-        .cache_dir
-        .unwrap_or_else(|| dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".lightignore")));
+    let mut settings = settings::load_settings()?;
+    configure_theme(&settings.theme);
+    if let Some(commit) = &project.pin {
+        settings
+            .pin_commit(commit)
+            .with_context(|| format!("reading the pin in {}", config_path.display()))?;
+    }
+    if let Some(url) = &cli.api_url {
+        settings
+            .override_api_url(url)
+            .context("invalid --api-url")?;
+    }
+    limits::configure_limits(settings.limits.clone());
 
-    // Configure theme early using environment/terminal hints
-    let detected = ui::theme::detect_theme_kind_from_env();
-    configure_theme(detected);
+    // Completion scripts run this on every keystroke, so it returns before
+    // building the HTTP client and runtime other commands need
+    if let Some(Commands::CompletionData { prefix }) = &cli.command {
+        let words =
+            completion::candidates(&cache_dir, &settings.hidden, project.custom.keys(), prefix);
+        let mut out = io::stdout().lock();
+        for word in words {
+            match writeln!(out, "{}", word) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => break,
+                result => result?,
+            }
+        }
+        return Ok(exit::SUCCESS);
+    }
 
-    let app = App::new(cache_dir)?;
+    let default_output = project.output_for(cli.kind, &config_path);
+    let self_update = settings.self_update.clone();
+    let app = App::new(
+        cache_dir,
+        settings,
+        AppOptions {
+            http1: cli.http1,
+            verbose: cli.verbose,
+            offline: cli.offline,
+            config: cli.config,
+            strict: cli.strict,
+            progress: cli.progress,
+            assume_yes: cli.yes,
+        },
+    )?;
     let rt = Runtime::new()?;
 
-    match cli.command.unwrap_or(Commands::Generate { output: None }) {
-        Commands::Update => {
-            rt.block_on(app.update_cache())?;
-            print_success("Cache updated")?;
+    let command = match cli.command {
+        Some(command) => command,
+        None if app.status_by_default(&default_output) => Commands::Status { output: None },
+        None => Commands::Generate {
+            output: None,
+            from: None,
+            stdin_templates: false,
+            recipe: None,
+            copy: false,
+            refresh_changed: false,
+            check_tracked: false,
+            on_empty: None,
+            no_save_config: false,
+            no_personal: false,
+            append: false,
+            only_custom: false,
+            kinds: Vec::new(),
+        },
+    };
+    match command {
+        Commands::Update {
+            from_archive,
+            bench_mode,
+        } => {
+            let report = match (from_archive, bench_mode) {
+                (Some(archive), _) => {
+                    app.update_from_archive(&archive)?;
+                    UpdateReport::default()
+                }
+                (None, Some(count)) => {
+                    app.update_synthetic(count)?;
+                    UpdateReport::default()
+                }
+                (None, None) => rt.block_on(app.update_cache())?.1,
+            };
+            report.render(&mut app.out(), cli.progress)?;
+            if !report.failures.is_empty() {
+                return Ok(exit::PARTIAL_UPDATE);
+            }
+            print_success(&mut app.out(), "Cache updated")?;
         }
-        Commands::List => {
+        Commands::List {
+            interactive: true, ..
+        } => {
             let index = app.read_index_or_update(&rt)?;
-            app.list_templates(&index)?;
+            app.browse_templates(&index)?;
         }
-        Commands::Generate { output } => {
+        Commands::List {
+            long, json, sort, ..
+        } => {
             let index = app.read_index_or_update(&rt)?;
-            let output_path = output.unwrap_or_else(|| PathBuf::from(".gitignore"));
-            app.generate_interactive(&index, output_path)?;
+            app.list_templates(&index, long, json, sort)?;
+        }
+        Commands::Show { name, with_header } => {
+            let index = app.read_verified_index(&rt)?;
+            app.show_template(&index, &name, with_header)?;
+        }
+        Commands::Which { pattern } => {
+            let index = app.read_index_or_update(&rt)?;
+            app.which(&index, &pattern)?;
+        }
+        Commands::Generate {
+            output,
+            from,
+            stdin_templates,
+            recipe,
+            copy,
+            refresh_changed,
+            check_tracked,
+            on_empty,
+            no_save_config,
+            no_personal,
+            append,
+            only_custom,
+            kinds,
+        } => {
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            let write = WriteOptions {
+                copy,
+                refresh_changed,
+                check_tracked,
+                skip_config: no_save_config,
+                no_personal,
+                append,
+            };
+            let preset = match from {
+                Some(from) => Some(preset::parse_from(&from)?),
+                None if stdin_templates => {
+                    let input = io::read_to_string(io::stdin()).context("reading stdin")?;
+                    Some(preset::parse_lines(&input)?)
+                }
+                None => recipe.map(|name| app.recipe(&name)).transpose()?,
+            };
+            match preset {
+                _ if only_custom => app.generate_custom_only(output_path, write)?,
+                _ if !kinds.is_empty() => {
+                    let index = app.read_verified_index(&rt)?;
+                    app.generate_kinds(&index, &kinds, write)?
+                }
+                // Custom templates need nothing from the cache, so a project
+                // using only those never has to download it
+                Some(preset) if app.is_custom_only(&preset)? => {
+                    app.generate_from(&TemplateIndex::new(), preset, output_path, write)?
+                }
+                Some(preset) => {
+                    let index = app.read_verified_index(&rt)?;
+                    app.generate_from(&index, preset, output_path, write)?
+                }
+                None => {
+                    let index = app.read_verified_index(&rt)?;
+                    let outcome =
+                        app.generate_interactive(&index, output_path, cli.kind, write, on_empty)?;
+                    if outcome == PickerOutcome::Cancelled {
+                        writeln!(app.out(), "Selection cancelled; nothing was changed.")?;
+                        return Ok(exit::CANCELLED);
+                    }
+                }
+            }
+        }
+        Commands::Init { output } => {
+            let index = app.read_verified_index(&rt)?;
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            app.init(&index, output_path)?;
+        }
+        Commands::Add {
+            templates,
+            output,
+            force,
+        } => {
+            let index = app.read_verified_index(&rt)?;
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            app.add_templates(&index, &templates, output_path, force)?;
+        }
+        Commands::Remove { templates, output } => {
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            app.remove_templates(&templates, &output_path)?;
+        }
+        Commands::Clean { output, config } => {
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            app.clean(&output_path, config)?;
+        }
+        Commands::Status { output } => {
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            if app.status(&output_path)? {
+                return Ok(exit::DRIFT);
+            }
+        }
+        Commands::Diff { output } => {
+            let index = app.read_verified_index(&rt)?;
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            if app.diff(&index, &output_path)? {
+                return Ok(exit::DRIFT);
+            }
+        }
+        Commands::Lint { output, fix } => {
+            let output_path = output.unwrap_or_else(|| default_output.clone());
+            if app.lint(&output_path, fix)? {
+                return Ok(exit::LINT_ISSUES);
+            }
+        }
+        Commands::Report { format, output } => {
+            let index = app.read_verified_index(&rt)?;
+            app.policy_report(&index, format, &default_output, output.as_deref())?;
+        }
+        Commands::Suggest => {
+            let index = app.read_index_or_update(&rt)?;
+            app.suggest(&index)?;
+        }
+        Commands::CompletionData { .. } => unreachable!("handled before the app is built"),
+        Commands::Stats { usage: _ } => app.usage_report()?,
+        Commands::Workspace { command } => match command {
+            WorkspaceCommands::Check => {
+                let index = app.read_verified_index(&rt)?;
+                app.check_workspace(&index)?;
+            }
+        },
+        Commands::Mirror { dir, verify: true } => app.verify_mirror(&dir)?,
+        Commands::Mirror { dir, verify: false } => {
+            let index = app.read_verified_index(&rt)?;
+            app.mirror(&index, &dir)?;
         }
+        Commands::Cache { command } => match command {
+            CacheCommands::Repair { dry_run } => app.repair_cache(&rt, dry_run)?,
+        },
+        Commands::Bundle { command } => match command {
+            BundleCommands::Add { location, output } => {
+                let bundle = rt.block_on(app.read_bundle(&location))?;
+                let index = app.read_verified_index(&rt)?;
+                let output_path = output.unwrap_or_else(|| default_output.clone());
+                app.add_bundle(&index, &bundle, output_path)?;
+            }
+        },
+        Commands::Preset { command } => match command {
+            PresetCommands::Export => app.export_preset()?,
+        },
+        Commands::Source { command } => match command {
+            SourceCommands::List => app.list_sources()?,
+            SourceCommands::Test { name } => rt.block_on(app.test_sources(name.as_deref()))?,
+        },
+        Commands::Theme { command } => match command {
+            ThemeCommands::Preview {
+                background,
+                palette,
+            } => app.preview_theme(background, palette)?,
+        },
         Commands::SelfUpdate => {
-            self_updater::update()?;
+            self_updater::update(&self_update, cli.yes)?;
         }
     }
 
-    Ok(())
+    Ok(exit::SUCCESS)
 }
@@ -0,0 +1,79 @@
+use std::ops::Range;
+
+/// Comment lines delimiting a block of a file that lightignore owns
+pub struct Markers {
+    pub start: &'static str,
+    pub end: &'static str,
+}
+
+/// Block in the user's global excludes file holding personal templates
+pub const PERSONAL: Markers = Markers {
+    start: "# >>> lightignore personal >>>",
+    end: "# <<< lightignore personal <<<",
+};
+
+/// Block in a project's .gitignore that lightignore manages alongside
+/// hand-written rules
+pub const PROJECT: Markers = Markers {
+    start: "# >>> lightignore >>>",
+    end: "# <<< lightignore <<<",
+};
+
+/// First line of every file lightignore generates in full
+pub const GENERATED_HEADER: &str = "# Generated by Lightignore";
+
+/// Byte range of the block including its markers and trailing newline
+pub fn find(content: &str, markers: &Markers) -> Option<Range<usize>> {
+    let start = content.find(markers.start)?;
+    let end = start + content[start..].find(markers.end)? + markers.end.len();
+    let end = if content[end..].starts_with('\n') {
+        end + 1
+    } else {
+        end
+    };
+    Some(start..end)
+}
+
+/// Text between the markers of the block, if present
+pub fn inner<'a>(content: &'a str, markers: &Markers) -> Option<&'a str> {
+    let range = find(content, markers)?;
+    let block = &content[range];
+    let body = block.strip_prefix(markers.start)?;
+    let end = body.find(markers.end)?;
+    Some(body[..end].trim_matches('\n'))
+}
+
+/// Replaces the block with `generated`, or appends it after a blank line
+pub fn replace_or_append(content: &str, markers: &Markers, generated: &str) -> String {
+    let block = format!(
+        "{}\n{}\n{}\n",
+        markers.start,
+        generated.trim_end(),
+        markers.end
+    );
+    match find(content, markers) {
+        Some(range) => format!(
+            "{}{}{}",
+            &content[..range.start],
+            block,
+            &content[range.end..]
+        ),
+        None if content.is_empty() => block,
+        None if content.ends_with('\n') => format!("{}\n{}", content, block),
+        None => format!("{}\n\n{}", content, block),
+    }
+}
+
+/// Removes the block and the blank line that separated it, returning
+/// `None` when there is no block
+pub fn remove(content: &str, markers: &Markers) -> Option<String> {
+    let range = find(content, markers)?;
+    let before = &content[..range.start];
+    let before = before.strip_suffix('\n').unwrap_or(before);
+    let after = &content[range.end..];
+    let mut result = format!("{}{}", before, after);
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push('\n');
+    }
+    Some(result)
+}
@@ -0,0 +1,289 @@
+//! Gitignore pattern matching, so rules from several ignore files can be
+//! compared without asking git
+
+/// One pattern line of an ignore file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// The line as written, for messages
+    pub line: String,
+    /// `!pattern`, re-including what earlier rules ignored
+    pub negated: bool,
+    /// `pattern/`, matching directories only
+    pub dir_only: bool,
+    /// Contains a `/` before its end, so it matches from the directory of
+    /// the ignore file instead of at any depth
+    pub anchored: bool,
+    glob: String,
+}
+
+impl Rule {
+    /// Parses a line of an ignore file; `None` for blank lines and comments
+    pub fn parse(line: &str) -> Option<Self> {
+        let trimmed = trim_trailing_spaces(line);
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return None;
+        }
+        let (negated, pattern) = match trimmed.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('\\').unwrap_or(trimmed)),
+        };
+        let (dir_only, pattern) = match pattern.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        if pattern.is_empty() {
+            return None;
+        }
+        let anchored = pattern.contains('/');
+        Some(Self {
+            line: trimmed.to_string(),
+            negated,
+            dir_only,
+            anchored,
+            glob: pattern.trim_start_matches('/').to_string(),
+        })
+    }
+
+    /// Whether the rule matches `path`, given relative to the directory of
+    /// its ignore file with `/` separators
+    pub fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let subject = if self.anchored {
+            path
+        } else {
+            path.rsplit('/').next().unwrap_or(path)
+        };
+        wildmatch(self.glob.as_bytes(), subject.as_bytes())
+    }
+
+    /// A concrete path the rule matches, such as `build/x.log` for
+    /// `build/*.log`, used to probe other ignore files
+    pub fn example(&self) -> Option<String> {
+        let mut example = String::new();
+        let mut chars = self.glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                    } else {
+                        example.push('x');
+                    }
+                }
+                '*' | '?' => example.push('x'),
+                '[' => {
+                    let mut class = String::new();
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        class.push(c);
+                    }
+                    let class = class.trim_start_matches(['!', '^']);
+                    example.push(class.chars().next().unwrap_or('x'));
+                }
+                '\\' => example.extend(chars.next()),
+                c => example.push(c),
+            }
+        }
+        let example = example.trim_matches('/').to_string();
+        (!example.is_empty() && self.matches(&example, self.dir_only)).then_some(example)
+    }
+}
+
+/// The rules of one ignore file in the order they are written
+#[derive(Debug, Default, Clone)]
+pub struct Matcher {
+    pub rules: Vec<Rule>,
+}
+
+impl Matcher {
+    pub fn new(content: &str) -> Self {
+        Self {
+            rules: content.lines().filter_map(Rule::parse).collect(),
+        }
+    }
+
+    /// The rule deciding `path`: the last one that matches it
+    pub fn decide(&self, path: &str, is_dir: bool) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+    }
+
+    /// The rule ignoring a directory that contains `path`, if any; git does
+    /// not look inside an ignored directory, so nothing in it can be
+    /// re-included
+    pub fn excluding_parent<'a>(&'a self, path: &'a str) -> Option<(&'a str, &'a Rule)> {
+        path.match_indices('/').find_map(|(at, _)| {
+            let parent = &path[..at];
+            self.decide(parent, true)
+                .filter(|rule| !rule.negated)
+                .map(|rule| (parent, rule))
+        })
+    }
+}
+
+/// Drops trailing spaces unless escaped with a backslash
+fn trim_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while end > 0 && line.as_bytes()[end - 1] == b' ' {
+        if end > 1 && line.as_bytes()[end - 2] == b'\\' {
+            break;
+        }
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Matches `text` against a gitignore glob: `*` and `?` stay within one
+/// path segment, `**` spans directories, and `[...]` is a character class
+fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                None => true,
+                // `**/` matches zero or more whole directories
+                Some(b'/') => (0..=text.len())
+                    .filter(|&at| at == 0 || text[at - 1] == b'/')
+                    .any(|at| wildmatch(&rest[1..], &text[at..])),
+                Some(_) => (0..=text.len()).any(|at| wildmatch(rest, &text[at..])),
+            }
+        }
+        Some(b'*') => {
+            let rest = &pattern[1..];
+            for at in 0..=text.len() {
+                if wildmatch(rest, &text[at..]) {
+                    return true;
+                }
+                if at < text.len() && text[at] == b'/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some(b'?') => match text.first() {
+            Some(&c) if c != b'/' => wildmatch(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        Some(b'[') => {
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            match match_class(&pattern[1..], c) {
+                Some((true, rest)) if c != b'/' => wildmatch(rest, &text[1..]),
+                Some(_) => false,
+                // No closing bracket: a literal `[`
+                None => c == b'[' && wildmatch(&pattern[1..], &text[1..]),
+            }
+        }
+        Some(b'\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && wildmatch(&pattern[2..], &text[1..])
+        }
+        Some(&p) => text.first() == Some(&p) && wildmatch(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `c` against the class starting after `[`, returning whether it
+/// matched and the pattern after the closing `]`
+fn match_class(pattern: &[u8], c: u8) -> Option<(bool, &[u8])> {
+    let (negated, mut at) = match pattern.first() {
+        Some(b'!' | b'^') => (true, 1),
+        _ => (false, 0),
+    };
+    let mut matched = false;
+    let mut first = true;
+    while at < pattern.len() {
+        let start = pattern[at];
+        if start == b']' && !first {
+            return Some((matched != negated, &pattern[at + 1..]));
+        }
+        first = false;
+        if pattern.get(at + 1) == Some(&b'-') && pattern.get(at + 2).is_some_and(|&e| e != b']') {
+            matched |= (start..=pattern[at + 2]).contains(&c);
+            at += 3;
+        } else {
+            matched |= start == c;
+            at += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(line: &str) -> Rule {
+        Rule::parse(line).unwrap()
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        assert!(rule("*.log").matches("a/b/debug.log", false));
+        assert!(rule("target/").matches("crates/x/target", true));
+        assert!(!rule("target/").matches("crates/x/target", false));
+    }
+
+    #[test]
+    fn anchored_patterns_match_from_the_file_directory() {
+        assert!(rule("/build").matches("build", true));
+        assert!(!rule("/build").matches("src/build", true));
+        assert!(rule("docs/*.md").matches("docs/a.md", false));
+        assert!(!rule("docs/*.md").matches("docs/sub/a.md", false));
+    }
+
+    #[test]
+    fn double_stars_span_directories() {
+        assert!(rule("**/cache").matches("cache", true));
+        assert!(rule("**/cache").matches("a/b/cache", true));
+        assert!(rule("a/**/b").matches("a/b", false));
+        assert!(rule("a/**/b").matches("a/x/y/b", false));
+        assert!(rule("logs/**").matches("logs/x/y.txt", false));
+    }
+
+    #[test]
+    fn classes_and_escapes() {
+        assert!(rule("*.py[cod]").matches("x.pyc", false));
+        assert!(!rule("*.py[!cod]").matches("x.pyc", false));
+        assert!(rule("[a-c].txt").matches("b.txt", false));
+        assert!(rule("\\!important").matches("!important", false));
+        assert!(rule("\\#notes").matches("#notes", false));
+    }
+
+    #[test]
+    fn last_matching_rule_decides() {
+        let matcher = Matcher::new("*.log\n!keep.log\n");
+        assert!(matcher.decide("keep.log", false).unwrap().negated);
+        assert!(!matcher.decide("other.log", false).unwrap().negated);
+        assert!(matcher.decide("main.rs", false).is_none());
+    }
+
+    #[test]
+    fn ignored_parent_directories_are_found() {
+        let matcher = Matcher::new("build/\n");
+        let (dir, rule) = matcher.excluding_parent("app/build/keep.txt").unwrap();
+        assert_eq!(dir, "app/build");
+        assert_eq!(rule.line, "build/");
+        assert!(matcher.excluding_parent("app/src/main.rs").is_none());
+    }
+
+    #[test]
+    fn examples_match_their_rule() {
+        assert_eq!(
+            rule("build/*.log").example().as_deref(),
+            Some("build/x.log")
+        );
+        assert_eq!(
+            rule("**/node_modules/").example().as_deref(),
+            Some("node_modules")
+        );
+        assert_eq!(rule("!/dist").example().as_deref(), Some("dist"));
+    }
+}
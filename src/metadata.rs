@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::safety;
+
+/// Human-readable notes about a template, worked out during an update
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateNotes {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Why the template should no longer be used; empty when it is current
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub deprecated: String,
+    /// Template the deprecation note points to instead, such as `Foo` for
+    /// `use Foo instead`; empty when the note names none
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub replacement: String,
+    /// What held the template back in quarantine; empty when the safe-mode
+    /// scan found nothing or the user trusts the template
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quarantine: Vec<String>,
+}
+
+// Longest description kept from a template's header comment
+const MAX_DESCRIPTION_LEN: usize = 120;
+
+/// Describes a template from its header comment, falling back to the folder
+/// conventions of the github/gitignore README: languages and frameworks at
+/// the top level, editors, tools and operating systems in `Global/`, and
+/// specialized templates in `community/`. Content the safe-mode scan flags
+/// puts the template in quarantine.
+pub fn describe(key: &str, content: &str) -> TemplateNotes {
+    let (comments, standalone) = leading_comments(content);
+    let deprecated = comments
+        .iter()
+        .find(|line| line.to_lowercase().contains("deprecated"))
+        .cloned()
+        .unwrap_or_default();
+    let description = comments
+        .iter()
+        .filter(|_| standalone)
+        .find(|line| is_prose(line))
+        .map(|line| truncate(line))
+        .unwrap_or_else(|| folder_description(key));
+    TemplateNotes {
+        description,
+        replacement: replacement_in(&deprecated),
+        deprecated,
+        quarantine: safety::scan(content),
+    }
+}
+
+/// Comment lines at the top of the template, and whether they stand as a
+/// block of their own. Comments directly followed by a pattern label that
+/// pattern, like `# Logs` above `*.log`, rather than describing the template.
+fn leading_comments(content: &str) -> (Vec<String>, bool) {
+    let lines = content.lines().map(str::trim).skip_while(|l| l.is_empty());
+    let mut comments = Vec::new();
+    for line in lines {
+        match line.strip_prefix('#') {
+            Some(comment) => comments.push(comment.trim().to_string()),
+            None => return (comments, line.is_empty()),
+        }
+    }
+    (comments, true)
+}
+
+// Phrases of a deprecation note that precede the template to use instead
+const REPLACEMENT_MARKERS: [&str; 5] = [
+    "use ",
+    "replaced by ",
+    "merged into ",
+    "moved to ",
+    "superseded by ",
+];
+
+/// The template a deprecation note suggests instead, as in `use Foo
+/// instead` or `merged into Global/Foo.gitignore`
+fn replacement_in(note: &str) -> String {
+    // ASCII lowercasing keeps byte offsets valid for `note`
+    let lower = note.to_ascii_lowercase();
+    REPLACEMENT_MARKERS
+        .iter()
+        .find_map(|marker| {
+            let start = lower.find(marker)? + marker.len();
+            let word = note[start..]
+                .split_whitespace()
+                .find(|word| !matches!(word.to_ascii_lowercase().as_str(), "the" | "a" | "an"))?;
+            let word = word.trim_matches(|c: char| "`'\".,;:()".contains(c));
+            let word = word.strip_suffix(".gitignore").unwrap_or(word);
+            let name = word.rsplit('/').next().unwrap_or(word);
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .unwrap_or_default()
+}
+
+fn is_prose(line: &str) -> bool {
+    let lower = line.to_lowercase();
+    line.split_whitespace().count() >= 3
+        && !lower.starts_with("http")
+        && !lower.starts_with("website:")
+        && !lower.starts_with("see ")
+}
+
+fn truncate(line: &str) -> String {
+    if line.chars().count() <= MAX_DESCRIPTION_LEN {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_DESCRIPTION_LEN - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn folder_description(key: &str) -> String {
+    match key.rsplit_once('/') {
+        None => "Language, framework or project template".to_string(),
+        Some(("Global", _)) => "Editor, tool or operating system template".to_string(),
+        Some((dir, _)) => match dir.strip_prefix("community/") {
+            Some(group) => format!("Community template for {}", group.replace('/', " ")),
+            None if dir == "community" => "Community template".to_string(),
+            None => format!("Template from {}", dir),
+        },
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::limits::get_limits;
+use crate::lock::content_hash;
+use crate::sources::RemoteTemplate;
+use crate::template::TemplateIndex;
+use crate::validation::validate_template_key;
+
+pub const MANIFEST_FILE: &str = "index.json";
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// SHA-256 of the manifest and every template, in the format of
+/// `sha256sum`, so a copied mirror can be checked with `sha256sum -c` too
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// Describes a static template set: template name to file path relative
+/// to the manifest
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct MirrorManifest {
+    pub version: u32,
+    pub templates: BTreeMap<String, String>,
+}
+
+/// Exports every cached template into `dir` with a manifest, producing a
+/// layout that can be served as-is or consumed through a `local` source
+pub fn export_mirror(index: &TemplateIndex, dir: &Path) -> Result<MirrorManifest> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("creating mirror directory {}", dir.display()))?;
+
+    let mut manifest = MirrorManifest {
+        version: MANIFEST_VERSION,
+        templates: BTreeMap::new(),
+    };
+    let mut checksums = Checksums::default();
+    for (name, entry) in &index.templates {
+        validate_template_key(name)?;
+        let file_name = format!("{}.gitignore", name.replace('/', "_"));
+        let content = fs::read_to_string(&entry.path)
+            .with_context(|| format!("reading template {}", name))?;
+        fs::write(dir.join(&file_name), &content)
+            .with_context(|| format!("copying template {} into mirror", name))?;
+        checksums.insert(&file_name, &content);
+        manifest.templates.insert(name.clone(), file_name);
+    }
+
+    let data = serde_json::to_string_pretty(&manifest)?;
+    fs::write(dir.join(MANIFEST_FILE), &data).context("writing mirror manifest")?;
+    checksums.insert(MANIFEST_FILE, &data);
+    fs::write(dir.join(CHECKSUMS_FILE), checksums.render()).context("writing mirror checksums")?;
+    Ok(manifest)
+}
+
+/// Published SHA-256 checksums by path relative to the mirror
+#[derive(Debug, Default)]
+pub struct Checksums {
+    files: BTreeMap<String, String>,
+}
+
+impl Checksums {
+    /// Parses `sha256sum` output: a hex digest, a space, a space or `*`,
+    /// then the path
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut files = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed = line.split_once(' ').and_then(|(digest, rest)| {
+                let path = rest.strip_prefix([' ', '*'])?;
+                let valid = digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit());
+                (valid && !path.is_empty()).then_some((path, digest))
+            });
+            let Some((path, digest)) = parsed else {
+                anyhow::bail!(
+                    "{} line {}: expected a SHA-256 digest and a path",
+                    CHECKSUMS_FILE,
+                    number + 1
+                );
+            };
+            files.insert(path.to_string(), digest.to_lowercase());
+        }
+        Ok(Self { files })
+    }
+
+    pub fn render(&self) -> String {
+        self.files
+            .iter()
+            .map(|(path, digest)| format!("{}  {}\n", digest, path))
+            .collect()
+    }
+
+    fn insert(&mut self, path: &str, content: &str) {
+        self.files.insert(path.to_string(), content_hash(content));
+    }
+
+    /// The published digest of `path`
+    pub fn get(&self, path: &str) -> Option<&str> {
+        self.files.get(path).map(String::as_str)
+    }
+
+    /// Checks `content` read from `path` against its published digest;
+    /// a file the checksums do not cover fails too
+    pub fn verify(&self, path: &str, content: &str) -> Result<()> {
+        let expected = self
+            .get(path)
+            .with_context(|| format!("{} has no checksum for {}", CHECKSUMS_FILE, path))?;
+        check_digest(path, expected, content)
+    }
+}
+
+/// Checks content against a digest from [`Checksums`]
+pub fn check_digest(what: &str, expected: &str, content: &str) -> Result<()> {
+    let actual = content_hash(content);
+    if actual != expected {
+        anyhow::bail!(
+            "{} does not match its published checksum (expected SHA-256 {}, got {})",
+            what,
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Checks a mirror against its checksums file before it is trusted: the
+/// manifest and every template it lists must be present and unchanged.
+/// Returns how many files were checked.
+pub fn verify_mirror(dir: &Path) -> Result<usize> {
+    let read = |path: &str| {
+        fs::read_to_string(dir.join(path))
+            .with_context(|| format!("reading {}", dir.join(path).display()))
+    };
+    let checksums = Checksums::parse(&read(CHECKSUMS_FILE)?)?;
+    let data = read(MANIFEST_FILE)?;
+    checksums.verify(MANIFEST_FILE, &data)?;
+    let manifest = parse_manifest(data.as_bytes())?;
+
+    let mut problems = Vec::new();
+    for path in manifest.templates.values() {
+        if let Err(e) = read(path).and_then(|content| checksums.verify(path, &content)) {
+            problems.push(format!("{:#}", e));
+        }
+    }
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "{} of {} templates failed verification:\n  {}",
+            problems.len(),
+            manifest.templates.len(),
+            problems.join("\n  ")
+        );
+    }
+    Ok(manifest.templates.len() + 1)
+}
+
+/// Parses and checks a manifest so it cannot point outside its directory
+pub fn parse_manifest(data: &[u8]) -> Result<MirrorManifest> {
+    let manifest: MirrorManifest =
+        serde_json::from_slice(data).context("parsing mirror manifest")?;
+    if manifest.version > MANIFEST_VERSION {
+        anyhow::bail!(
+            "Mirror manifest version {} is newer than supported ({})",
+            manifest.version,
+            MANIFEST_VERSION
+        );
+    }
+    for (name, path) in &manifest.templates {
+        validate_template_key(name)?;
+        validate_relative_path(path)
+            .with_context(|| format!("invalid path for template {}", name))?;
+    }
+    Ok(manifest)
+}
+
+/// Lists templates in a local directory, using its manifest when present
+/// and otherwise every `.gitignore` file found beneath it
+pub fn list_local_templates(root: &Path) -> Result<Vec<RemoteTemplate>> {
+    if !root.is_dir() {
+        anyhow::bail!("Template directory not found: {}", root.display());
+    }
+
+    let manifest_path = root.join(MANIFEST_FILE);
+    let files: Vec<(String, PathBuf, Option<String>)> = if manifest_path.exists() {
+        let data = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("reading {}", manifest_path.display()))?;
+        let checksums_path = root.join(CHECKSUMS_FILE);
+        let checksums = if checksums_path.exists() {
+            let text = fs::read_to_string(&checksums_path)
+                .with_context(|| format!("reading {}", checksums_path.display()))?;
+            let checksums = Checksums::parse(&text)?;
+            checksums.verify(MANIFEST_FILE, &data)?;
+            Some(checksums)
+        } else {
+            None
+        };
+        parse_manifest(data.as_bytes())?
+            .templates
+            .into_iter()
+            .map(|(name, path)| {
+                let sha256 = published_digest(checksums.as_ref(), &path)?;
+                Ok((name, root.join(path), sha256))
+            })
+            .collect::<Result<_>>()?
+    } else {
+        let mut found = Vec::new();
+        collect_gitignore_files(root, root, &mut found)?;
+        found
+            .into_iter()
+            .map(|(key, path)| (key, path, None))
+            .collect()
+    };
+
+    Ok(files
+        .into_iter()
+        .map(|(key, path, sha256)| RemoteTemplate {
+            name: key.rsplit('/').next().unwrap_or(&key).to_string(),
+            key,
+            location: path.to_string_lossy().to_string(),
+            sha256,
+        })
+        .collect())
+}
+
+/// The digest `checksums` publish for a template file; once a mirror
+/// publishes checksums, a template without one is not trusted
+pub fn published_digest(checksums: Option<&Checksums>, path: &str) -> Result<Option<String>> {
+    let Some(checksums) = checksums else {
+        return Ok(None);
+    };
+    checksums
+        .get(path)
+        .map(|digest| Some(digest.to_string()))
+        .with_context(|| format!("{} has no checksum for {}", CHECKSUMS_FILE, path))
+}
+
+/// Checks downloaded content against the checksum its source published
+pub fn verify_template(template: &RemoteTemplate, content: &str) -> Result<()> {
+    match &template.sha256 {
+        Some(expected) => check_digest(&format!("template {}", template.key), expected, content),
+        None => Ok(()),
+    }
+}
+
+/// Reads one template listed by [`list_local_templates`]
+pub fn read_local_template(template: &RemoteTemplate) -> Result<String> {
+    let path = Path::new(&template.location);
+    let size = fs::metadata(path)
+        .with_context(|| format!("reading template {}", path.display()))?
+        .len();
+    if size > get_limits().max_download_size {
+        anyhow::bail!(
+            "{} bytes exceeds size limit (max: {} bytes)",
+            size,
+            get_limits().max_download_size
+        );
+    }
+    let content =
+        fs::read_to_string(path).with_context(|| format!("reading template {}", path.display()))?;
+    verify_template(template, &content)?;
+    Ok(content)
+}
+
+fn collect_gitignore_files(
+    root: &Path,
+    dir: &Path,
+    found: &mut Vec<(String, PathBuf)>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_gitignore_files(root, &path, found)?;
+        } else if file_type.is_file() {
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !file_name.ends_with(".gitignore") || file_name == ".gitignore" {
+                continue;
+            }
+            let relative = path
+                .strip_prefix(root)?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let key = relative.trim_end_matches(".gitignore").to_string();
+            if validate_template_key(&key).is_ok() {
+                found.push((key, path));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_relative_path(path: &str) -> Result<()> {
+    let path = Path::new(path);
+    if path.as_os_str().is_empty() {
+        anyhow::bail!("path is empty");
+    }
+    if !path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        anyhow::bail!("path must be relative and cannot contain ..");
+    }
+    Ok(())
+}
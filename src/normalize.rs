@@ -0,0 +1,118 @@
+//! Canonical forms of gitignore patterns, so patterns that match the same
+//! paths compare equal when deduplicating, and `lignore lint` for files
+//! written by hand
+
+use std::collections::HashMap;
+
+/// Rewrites a pattern (a trimmed line that is not a comment) to its
+/// canonical form:
+///
+/// - `**/**/` collapses to `**/`
+/// - a leading `**/` is dropped when no other slash follows before the end,
+///   since a pattern without a slash matches at any depth anyway (`**/foo`
+///   is `foo`, `**/build/` is `build/`)
+/// - a leading `/` is dropped when another slash follows before the end,
+///   since that slash already anchors the pattern (`/foo/bar` is `foo/bar`)
+///
+/// `foo/` and `/foo/` stay apart: the first also matches nested directories.
+pub fn normalize_pattern(pattern: &str) -> String {
+    if let Some(rest) = pattern.strip_prefix('!') {
+        return format!("!{}", normalize_pattern(rest));
+    }
+
+    let mut pattern = pattern.to_string();
+    while pattern.contains("**/**/") {
+        pattern = pattern.replace("**/**/", "**/");
+    }
+    if let Some(rest) = pattern.strip_prefix("**/")
+        && !rest.is_empty()
+        && !has_inner_slash(rest)
+    {
+        pattern = rest.to_string();
+    }
+    if let Some(rest) = pattern.strip_prefix('/')
+        && has_inner_slash(rest)
+    {
+        pattern = rest.to_string();
+    }
+    pattern
+}
+
+/// Whether a slash appears anywhere but at the end
+fn has_inner_slash(pattern: &str) -> bool {
+    pattern.trim_end_matches('/').contains('/')
+}
+
+/// A line `lint` would change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintIssue {
+    /// The pattern has a shorter canonical form
+    Rewrite {
+        line: usize,
+        pattern: String,
+        canonical: String,
+    },
+    /// The pattern matches the same paths as an earlier line
+    Duplicate {
+        line: usize,
+        pattern: String,
+        first: usize,
+    },
+}
+
+/// Result of linting an ignore file
+#[derive(Debug, Default)]
+pub struct Lint {
+    /// Issues in file order; line numbers count from 1
+    pub issues: Vec<LintIssue>,
+    /// The content with patterns rewritten and duplicates removed
+    pub fixed: String,
+}
+
+/// Finds patterns in `content` that are not in canonical form or repeat an
+/// earlier one. A negation can undo an earlier pattern, so repeats after a
+/// `!` line are left alone.
+pub fn lint(content: &str) -> Lint {
+    let mut lint = Lint::default();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut lines = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let number = idx + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let canonical = normalize_pattern(trimmed);
+        if canonical.starts_with('!') {
+            seen.clear();
+        } else if let Some(&first) = seen.get(&canonical) {
+            lint.issues.push(LintIssue::Duplicate {
+                line: number,
+                pattern: trimmed.to_string(),
+                first,
+            });
+            continue;
+        } else {
+            seen.insert(canonical.clone(), number);
+        }
+
+        if canonical != trimmed {
+            lint.issues.push(LintIssue::Rewrite {
+                line: number,
+                pattern: trimmed.to_string(),
+                canonical: canonical.clone(),
+            });
+            lines.push(canonical);
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    lint.fixed = lines.join("\n");
+    if content.ends_with('\n') {
+        lint.fixed.push('\n');
+    }
+    lint
+}
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::sources::state::STATE_FILE;
+use crate::template::TemplateIndex;
+
+const APP_DIR: &str = "lightignore";
+
+/// Default template cache: `$XDG_CACHE_HOME/lightignore`, otherwise the
+/// platform cache directory
+pub fn default_cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME")
+        .or_else(dirs::cache_dir)
+        .map(|dir| dir.join(APP_DIR))
+        .unwrap_or_else(|| PathBuf::from(".lightignore"))
+}
+
+/// Directory holding user settings: `$XDG_CONFIG_HOME/lightignore`,
+/// otherwise the platform config directory
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME")
+        .or_else(dirs::config_dir)
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// Where earlier versions wrote the cache: straight into the generic cache root
+fn legacy_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir()
+}
+
+/// The XDG spec says relative paths in these variables are invalid and
+/// must be ignored
+fn xdg_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+}
+
+/// Moves a cache written by an earlier version from the generic cache root
+/// into `cache_dir`, once, when `cache_dir` has no index yet
+pub fn migrate_legacy_cache(cache_dir: &Path) -> Result<()> {
+    let Some(legacy) = legacy_cache_dir() else {
+        return Ok(());
+    };
+    if legacy == cache_dir
+        || cache_dir.join("index.json").exists()
+        || !legacy.join("index.json").exists()
+    {
+        return Ok(());
+    }
+
+    let mut index = TemplateIndex::read(&legacy)?;
+    fs::create_dir_all(cache_dir)
+        .with_context(|| format!("creating cache directory {}", cache_dir.display()))?;
+
+    let mut emptied = Vec::new();
+    for (name, entry) in index.templates.iter_mut() {
+        let old = PathBuf::from(&entry.path);
+        // Only move what lives in the legacy root; other programs share it
+        let Ok(relative) = old.strip_prefix(&legacy) else {
+            continue;
+        };
+        let new = cache_dir.join(relative);
+        if let Some(parent) = new.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating directory {}", parent.display()))?;
+        }
+        if old.exists() {
+            fs::rename(&old, &new).with_context(|| format!("moving template {}", name))?;
+        }
+        if let Some(parent) = old.parent()
+            && parent != legacy
+        {
+            emptied.push(parent.to_path_buf());
+        }
+        entry.path = new.to_string_lossy().to_string();
+    }
+    index.write(cache_dir)?;
+
+    let state = legacy.join(STATE_FILE);
+    if state.exists() {
+        fs::rename(&state, cache_dir.join(STATE_FILE))
+            .with_context(|| format!("moving {}", state.display()))?;
+    }
+    fs::remove_file(legacy.join("index.json")).context("removing the old cache index")?;
+    emptied.sort();
+    emptied.dedup();
+    for dir in emptied {
+        // Leaves directories that still hold other files
+        let _ = fs::remove_dir(dir);
+    }
+
+    println!(
+        "Moved the template cache from {} to {}",
+        legacy.display(),
+        cache_dir.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! A human-readable description of a project's ignore policy, for
+//! CONTRIBUTING files and project docs
+
+use clap::ValueEnum;
+
+/// Most patterns quoted for each template
+const NOTABLE_PATTERNS: usize = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+}
+
+/// One selected template as the report describes it
+#[derive(Debug, Clone)]
+pub struct PolicyTemplate {
+    pub name: String,
+    /// Where the template comes from, such as a source name or lignore.json
+    pub origin: String,
+    pub description: String,
+    pub deprecated: Option<String>,
+    pub content: String,
+}
+
+/// Renders the report for `templates`, which are written to `output`
+pub fn render_markdown(output: &str, templates: &[PolicyTemplate]) -> String {
+    let mut out = String::new();
+    out.push_str("# Ignore policy\n\n");
+    out.push_str(&format!(
+        "`{}` is generated by [lightignore]({}) from the templates selected in \
+         `lignore.json`. Run `lignore generate` to change the selection instead of \
+         editing the file by hand.\n\n",
+        output,
+        env!("CARGO_PKG_REPOSITORY")
+    ));
+
+    if templates.is_empty() {
+        out.push_str("No templates are selected.\n");
+        return out;
+    }
+
+    out.push_str("| Template | From | Purpose |\n|---|---|---|\n");
+    for template in templates {
+        out.push_str(&format!(
+            "| {} | {} | {} |\n",
+            escape_cell(&template.name),
+            escape_cell(&template.origin),
+            escape_cell(&template.description)
+        ));
+    }
+
+    for template in templates {
+        out.push_str(&format!("\n## {}\n\n", template.name));
+        if !template.description.is_empty() {
+            out.push_str(&format!("{}\n\n", template.description));
+        }
+        if let Some(note) = &template.deprecated {
+            out.push_str(&format!("> **Deprecated:** {}\n\n", note));
+        }
+        let (notable, total) = notable_patterns(&template.content);
+        if total == 0 {
+            out.push_str("This template has no patterns.\n");
+            continue;
+        }
+        out.push_str(&format!("Notable patterns ({} in total):\n\n", total));
+        for pattern in notable {
+            out.push_str(&format!("- `{}`\n", pattern));
+        }
+    }
+    out
+}
+
+/// Picks the patterns that say the most about a template: negations and
+/// directories first, then the rest in file order. Returns them with the
+/// template's total number of patterns.
+fn notable_patterns(content: &str) -> (Vec<&str>, usize) {
+    let patterns: Vec<&str> = content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let rank = |pattern: &str| {
+        if pattern.starts_with('!') {
+            0
+        } else if pattern.ends_with('/') {
+            1
+        } else {
+            2
+        }
+    };
+    let mut notable = patterns.clone();
+    // Stable, so each rank keeps file order
+    notable.sort_by_key(|pattern| rank(pattern));
+    notable.truncate(NOTABLE_PATTERNS);
+    (notable, patterns.len())
+}
+
+fn escape_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
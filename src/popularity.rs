@@ -0,0 +1,88 @@
+use std::cmp::Ordering;
+
+use crate::template::TemplateIndex;
+
+/// Templates most projects reach for, most popular first. Anything not
+/// listed falls back to where it lives in the upstream repository.
+const POPULAR: &[&str] = &[
+    "Node",
+    "Python",
+    "Rust",
+    "Go",
+    "Java",
+    "macOS",
+    "Windows",
+    "Linux",
+    "VisualStudioCode",
+    "JetBrains",
+    "C++",
+    "C",
+    "CMake",
+    "VisualStudio",
+    "Unity",
+    "Android",
+    "Swift",
+    "Xcode",
+    "Dart",
+    "Ruby",
+    "Rails",
+    "Gradle",
+    "Maven",
+    "Composer",
+    "Laravel",
+    "Symfony",
+    "Vim",
+    "Emacs",
+    "Terraform",
+    "TeX",
+    "Haskell",
+    "Elixir",
+    "Scala",
+    "Julia",
+    "R",
+    "Lua",
+    "Perl",
+    "Qt",
+    "UnrealEngine",
+    "Godot",
+    "Jekyll",
+    "Hugo",
+];
+
+/// Sort key for one template: bundled rank, then repository tier
+/// (top-level before `Global/` before `community/`), then name
+fn sort_key(name: &str, index: &TemplateIndex) -> (usize, u8, String) {
+    let base = name.rsplit('/').next().unwrap_or(name);
+    let rank = POPULAR
+        .iter()
+        .position(|popular| popular.eq_ignore_ascii_case(base))
+        .unwrap_or(usize::MAX);
+
+    let tier = match index.get(name) {
+        // Custom templates are project specific, so keep them on top
+        None => 0,
+        Some(entry) if entry.key.starts_with("community/") => 3,
+        Some(entry) if entry.key.contains('/') => 2,
+        Some(_) => 1,
+    };
+    let rank = if tier == 0 { 0 } else { rank };
+
+    (rank, tier, name.to_lowercase())
+}
+
+/// Compares two template names by popularity
+pub fn compare(a: &str, b: &str, index: &TemplateIndex) -> Ordering {
+    sort_key(a, index).cmp(&sort_key(b, index))
+}
+
+/// Popularity rank of each name (0 = most popular), aligned with `names`
+pub fn ranks(names: &[String], index: &TemplateIndex) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..names.len()).collect();
+    order.sort_by_cached_key(|&idx| sort_key(&names[idx], index));
+
+    let mut ranks = vec![0; names.len()];
+    for (rank, idx) in order.into_iter().enumerate() {
+        ranks[idx] = rank;
+    }
+    ranks
+}
@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use crate::config::{LignoreConfig, validate_custom_template};
+use crate::template::TemplateIndex;
+
+/// Prefix of encoded presets; the number is bumped if the payload changes
+pub const TOKEN_PREFIX: &str = "lg1.";
+
+// Decoded presets larger than this are rejected
+const MAX_PRESET_SIZE: u64 = 1024 * 1024;
+
+/// A shareable template selection, including custom templates
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct Preset {
+    #[serde(default, rename = "t")]
+    pub templates: Vec<String>,
+    #[serde(default, rename = "c", skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, Vec<String>>,
+}
+
+impl Preset {
+    /// Custom templates are always selected, so they lead the list just as
+    /// they do in the picker
+    pub fn from_config(config: &LignoreConfig) -> Self {
+        Self {
+            templates: config
+                .custom
+                .keys()
+                .chain(&config.templates)
+                .cloned()
+                .collect(),
+            custom: config.custom.clone(),
+        }
+    }
+}
+
+/// Encodes a preset as a compact URL-safe token
+pub fn encode(preset: &Preset) -> Result<String> {
+    let json = serde_json::to_vec(preset)?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&json)?;
+    let compressed = encoder.finish()?;
+    Ok(format!(
+        "{}{}",
+        TOKEN_PREFIX,
+        URL_SAFE_NO_PAD.encode(compressed)
+    ))
+}
+
+/// Decodes a token written by [`encode`]
+pub fn decode(token: &str) -> Result<Preset> {
+    let payload = token
+        .strip_prefix(TOKEN_PREFIX)
+        .context("not a lightignore preset token")?;
+    let compressed = URL_SAFE_NO_PAD
+        .decode(payload.trim())
+        .context("preset token is not valid base64")?;
+
+    let mut json = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_PRESET_SIZE + 1)
+        .read_to_end(&mut json)
+        .context("decompressing preset token")?;
+    if json.len() as u64 > MAX_PRESET_SIZE {
+        anyhow::bail!("Preset exceeds size limit (max: {} bytes)", MAX_PRESET_SIZE);
+    }
+
+    let preset: Preset = serde_json::from_slice(&json).context("parsing preset token")?;
+    for (name, lines) in &preset.custom {
+        validate_custom_template(name, lines)
+            .with_context(|| format!("validating custom template '{}'", name))?;
+    }
+    Ok(preset)
+}
+
+/// Parses a `--from` argument: either a preset token or a comma-separated
+/// list of template names such as `rust,node,global/macos`
+pub fn parse_from(arg: &str) -> Result<Preset> {
+    if arg.starts_with(TOKEN_PREFIX) {
+        return decode(arg);
+    }
+    let templates: Vec<String> = arg
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect();
+    if templates.is_empty() {
+        anyhow::bail!("No templates given in --from");
+    }
+    Ok(Preset {
+        templates,
+        custom: BTreeMap::new(),
+    })
+}
+
+/// Reads template names one per line, as printed by `lignore list` when
+/// piped, skipping blank lines and `#` comments
+pub fn parse_lines(input: &str) -> Result<Preset> {
+    let templates: Vec<String> = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if templates.is_empty() {
+        anyhow::bail!("No templates given on stdin");
+    }
+    Ok(Preset {
+        templates,
+        custom: BTreeMap::new(),
+    })
+}
+
+/// Maps the preset's template names onto the cached index, ignoring case
+/// and accepting source paths, and reports names that match nothing
+pub fn resolve_templates(preset: &Preset, index: &TemplateIndex) -> Result<Vec<String>> {
+    let mut resolved = Vec::new();
+    let mut missing = Vec::new();
+    for name in &preset.templates {
+        let found = if preset.custom.contains_key(name) {
+            Some(name.as_str())
+        } else {
+            index.resolve(name)
+        };
+        match found {
+            Some(found) if !resolved.iter().any(|r| r == found) => resolved.push(found.to_string()),
+            Some(_) => {}
+            None => missing.push(name.as_str()),
+        }
+    }
+
+    if !missing.is_empty() {
+        let mut error_msg = String::from("The following templates in the preset do not exist:\n");
+        for name in missing {
+            error_msg.push_str(&format!("  - {}\n", name));
+        }
+        error_msg.push_str("\nRun `lignore list` to see available templates.");
+        anyhow::bail!(error_msg);
+    }
+    Ok(resolved)
+}
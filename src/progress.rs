@@ -0,0 +1,67 @@
+//! Progress reporting for template downloads, as text for people or as
+//! newline-delimited JSON events for GUIs and editor extensions wrapping
+//! `lignore`
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+
+/// How `update` reports download progress
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// Counters and warnings meant for a terminal
+    #[default]
+    Human,
+    /// One JSON object per line on stderr
+    Json,
+}
+
+/// A step of an update, serialized with its kind in the `event` field
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent<'a> {
+    /// A source started listing its templates
+    ScanStarted { source: &'a str },
+    /// One template was downloaded; `current` counts templates finished so
+    /// far, including failed ones
+    TemplateDownloaded {
+        source: &'a str,
+        template: &'a str,
+        current: usize,
+        total: usize,
+    },
+    /// One template could not be downloaded and was skipped
+    TemplateFailed {
+        source: &'a str,
+        template: &'a str,
+        error: String,
+        current: usize,
+        total: usize,
+    },
+    /// A source finished; `failed` counts its skipped templates
+    SourceCompleted {
+        source: &'a str,
+        templates: usize,
+        failed: usize,
+    },
+    /// A problem that did not stop the update, such as an incomplete listing
+    Warning { message: &'a str },
+    /// Every source finished and the index was written
+    Completed { templates: usize, failed: usize },
+}
+
+impl ProgressFormat {
+    pub fn is_json(self) -> bool {
+        self == ProgressFormat::Json
+    }
+
+    /// Writes `event` to stderr when JSON events were requested
+    pub fn report(self, event: &ProgressEvent) {
+        if !self.is_json() {
+            return;
+        }
+        if let Ok(line) = serde_json::to_string(event) {
+            let _ = writeln!(io::stderr().lock(), "{}", line);
+        }
+    }
+}
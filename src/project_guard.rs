@@ -0,0 +1,143 @@
+//! Keeps two `lignore` runs in the same project, such as a git hook and a
+//! person at the terminal, from interleaving their config and output writes
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crate::sources::state::now_secs;
+
+/// How long to wait for another run to finish before giving up
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A lock older than this is taken over even when its owner cannot be
+/// checked, since no run holds the lock that long
+const STALE_AFTER_SECS: u64 = 60 * 60;
+
+/// Who holds the lock, recorded in the lock file
+#[derive(Deserialize, Serialize, Debug)]
+struct Holder {
+    pid: u32,
+    /// Unix time the lock was taken
+    since: u64,
+}
+
+/// Held while a command updates the project; removes the lock file on drop
+#[derive(Debug)]
+pub struct ProjectGuard {
+    path: PathBuf,
+}
+
+impl ProjectGuard {
+    /// Locks the project whose config lives at `config_path`, through a
+    /// `<config>.lock` file beside it. Waits briefly for another run, and
+    /// takes over locks left behind by runs that crashed.
+    pub fn acquire(config_path: &Path) -> Result<Self> {
+        let mut name = config_path
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_default();
+        name.push(".lock");
+        let path = config_path.with_file_name(name);
+
+        let mut waited = Duration::ZERO;
+        loop {
+            match Self::create(&path) {
+                Ok(guard) => return Ok(guard),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+                Err(e) => {
+                    return Err(e).with_context(|| format!("creating {}", path.display()));
+                }
+            }
+
+            let holder = read_holder(&path);
+            let stale = match &holder {
+                Some(holder) => is_stale(holder),
+                None => is_abandoned(&path),
+            };
+            if stale {
+                if let Some(holder) = &holder {
+                    eprintln!(
+                        "Warning: Removing a stale lock left by process {} in {}",
+                        holder.pid,
+                        path.display()
+                    );
+                }
+                match fs::remove_file(&path) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => {
+                        return Err(e).with_context(|| format!("removing {}", path.display()));
+                    }
+                    _ => continue,
+                }
+            }
+
+            if waited >= WAIT_TIMEOUT {
+                let pid = holder.map(|holder| holder.pid).unwrap_or_default();
+                anyhow::bail!(
+                    "Another lignore run (process {}) is updating this project.\n\
+                     Try again when it finishes, or remove {} if it is no longer running.",
+                    pid,
+                    path.display()
+                );
+            }
+            thread::sleep(POLL_INTERVAL);
+            waited += POLL_INTERVAL;
+        }
+    }
+
+    fn create(path: &Path) -> std::io::Result<Self> {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+        let guard = Self {
+            path: path.to_path_buf(),
+        };
+        let holder = Holder {
+            pid: std::process::id(),
+            since: now_secs(),
+        };
+        file.write_all(&serde_json::to_vec(&holder)?)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for ProjectGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// The lock's holder; `None` while it is being written, or when the run
+/// that created it was killed before writing it
+fn read_holder(path: &Path) -> Option<Holder> {
+    let data = fs::read(path).ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+/// Whether a lock file without a holder has been left that way for longer
+/// than writing the holder takes
+fn is_abandoned(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > WAIT_TIMEOUT)
+}
+
+fn is_stale(holder: &Holder) -> bool {
+    now_secs().saturating_sub(holder.since) > STALE_AFTER_SECS || !is_running(holder.pid)
+}
+
+/// Whether a process is still alive; assumed so where that cannot be checked
+fn is_running(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    if proc.is_dir() {
+        proc.join(pid.to_string()).exists()
+    } else {
+        true
+    }
+}
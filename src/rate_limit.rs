@@ -0,0 +1,211 @@
+use anyhow::Result;
+use reqwest::header::HeaderMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Upper bound on concurrent requests when the quota is healthy
+pub const MAX_CONCURRENCY: usize = 20;
+
+/// Below this many remaining requests, pacing kicks in
+const LOW_WATERMARK: u32 = 10;
+
+/// Longest we are willing to sleep waiting for a quota reset
+const MAX_RESET_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Quota {
+    remaining: Option<u32>,
+    reset: Option<u64>,
+}
+
+/// Tracks the `X-RateLimit-*` headers seen on responses and throttles
+/// outgoing requests so an update degrades gracefully instead of failing
+/// with a burst of 403s once the quota runs out.
+#[derive(Debug)]
+pub struct RateLimiter {
+    quota: Mutex<Quota>,
+    /// `MAX_CONCURRENCY` slots; a request takes more of them as the quota
+    /// runs low, so fewer fit in flight
+    slots: Semaphore,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self {
+            quota: Mutex::default(),
+            slots: Semaphore::new(MAX_CONCURRENCY),
+        }
+    }
+}
+
+/// Held until a response has been read; releases its slots on drop
+pub struct Permit<'a> {
+    _slots: SemaphorePermit<'a>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the rate limit headers of a response, if present
+    pub fn observe(&self, headers: &HeaderMap) {
+        let parse = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+
+        let remaining = parse("x-ratelimit-remaining");
+        let reset = parse("x-ratelimit-reset");
+        if remaining.is_none() && reset.is_none() {
+            return;
+        }
+
+        let mut quota = self.quota.lock().unwrap();
+        if let Some(remaining) = remaining {
+            quota.remaining = Some(remaining.min(u32::MAX as u64) as u32);
+        }
+        if let Some(reset) = reset {
+            quota.reset = Some(reset);
+        }
+    }
+
+    /// Number of requests allowed in flight given the last observed quota
+    pub fn concurrency(&self) -> usize {
+        let quota = *self.quota.lock().unwrap();
+        match quota.remaining {
+            None => MAX_CONCURRENCY,
+            Some(0) => 1,
+            // Keep half of the remaining quota as headroom for retries
+            Some(remaining) => ((remaining / 2) as usize).clamp(1, MAX_CONCURRENCY),
+        }
+    }
+
+    /// Waits until a request may be sent, sleeping through a quota reset
+    /// when it is close enough, and bails out when it is not.
+    pub async fn acquire(&self) -> Result<Permit<'_>> {
+        if let Some(delay) = self.pacing_delay()? {
+            tokio::time::sleep(delay).await;
+        }
+
+        // Each request takes MAX_CONCURRENCY / concurrency slots, rounded
+        // up, so at most `concurrency` of them are in flight
+        let weight = MAX_CONCURRENCY.div_ceil(self.concurrency()) as u32;
+        let slots = self.slots.acquire_many(weight).await?;
+        Ok(Permit { _slots: slots })
+    }
+
+    fn pacing_delay(&self) -> Result<Option<Duration>> {
+        let quota = *self.quota.lock().unwrap();
+        let Some(remaining) = quota.remaining else {
+            return Ok(None);
+        };
+        if remaining >= LOW_WATERMARK {
+            return Ok(None);
+        }
+
+        let until_reset = quota
+            .reset
+            .map(|reset| Duration::from_secs(reset.saturating_sub(now_secs())))
+            .unwrap_or_default();
+
+        if remaining == 0 {
+            if until_reset > MAX_RESET_WAIT {
+                let secs = until_reset.as_secs();
+                anyhow::bail!(
+                    "GitHub API rate limit exhausted; resets in {}m {}s",
+                    secs / 60,
+                    secs % 60
+                );
+            }
+            return Ok(Some(until_reset));
+        }
+
+        // Spread the remaining quota evenly over the rest of the window
+        Ok(Some((until_reset / remaining).min(MAX_RESET_WAIT)))
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Caps how many requests a single source may spend during one update
+#[derive(Debug)]
+pub struct RequestBudget {
+    limit: Option<u32>,
+    used: AtomicU32,
+    exceeded: AtomicBool,
+}
+
+impl RequestBudget {
+    pub fn new(limit: Option<u32>) -> Self {
+        Self {
+            limit,
+            used: AtomicU32::new(0),
+            exceeded: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether `requests` more requests fit in what is left of the budget
+    pub fn allows(&self, requests: u32) -> bool {
+        match self.limit {
+            Some(limit) => self.used.load(Ordering::SeqCst).saturating_add(requests) <= limit,
+            None => true,
+        }
+    }
+
+    /// Records one request, failing once the budget is spent
+    pub fn spend(&self) -> Result<()> {
+        let used = self.used.fetch_add(1, Ordering::SeqCst);
+        match self.limit {
+            Some(limit) if used >= limit => {
+                self.used.fetch_sub(1, Ordering::SeqCst);
+                self.exceeded.store(true, Ordering::SeqCst);
+                anyhow::bail!("request budget of {} exhausted", limit)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether a request was refused because the budget ran out
+    pub fn was_exceeded(&self) -> bool {
+        self.exceeded.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn quota(remaining: u32) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from(remaining));
+        headers.insert("x-ratelimit-reset", HeaderValue::from(now_secs()));
+        headers
+    }
+
+    #[tokio::test]
+    async fn low_quota_lets_fewer_requests_in_flight() {
+        let limiter = RateLimiter::new();
+        limiter.observe(&quota(4));
+        assert_eq!(limiter.concurrency(), 2);
+
+        let first = limiter.acquire().await.unwrap();
+        let _second = limiter.acquire().await.unwrap();
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.is_err(), "a third request was let through");
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(50), limiter.acquire()).await;
+        assert!(third.is_ok(), "a released permit was not handed on");
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::preset::Preset;
+
+/// Template sets for common kinds of project, so a first run can start from
+/// a known-good selection instead of the full list. The `recipes` setting
+/// adds to these or replaces them by name.
+const BUILT_IN: &[(&str, &[&str])] = &[
+    (
+        "rust-cli",
+        &[
+            "Rust",
+            "macOS",
+            "Windows",
+            "Linux",
+            "VisualStudioCode",
+            "JetBrains",
+        ],
+    ),
+    (
+        "node-web",
+        &[
+            "Node",
+            "macOS",
+            "Windows",
+            "Linux",
+            "VisualStudioCode",
+            "JetBrains",
+        ],
+    ),
+    (
+        "python-ml",
+        &[
+            "Python",
+            "JupyterNotebooks",
+            "macOS",
+            "Windows",
+            "Linux",
+            "VisualStudioCode",
+            "JetBrains",
+        ],
+    ),
+];
+
+/// Names of the built-in recipes and the ones from `configured`, sorted
+pub fn recipe_names(configured: &BTreeMap<String, Vec<String>>) -> Vec<String> {
+    let names: BTreeSet<String> = BUILT_IN
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .chain(configured.keys().cloned())
+        .collect();
+    names.into_iter().collect()
+}
+
+/// The templates of recipe `name`, preferring a configured recipe over the
+/// built-in one of the same name
+pub fn find(name: &str, configured: &BTreeMap<String, Vec<String>>) -> Option<Vec<String>> {
+    if let Some(templates) = configured.get(name) {
+        return Some(templates.clone());
+    }
+    BUILT_IN
+        .iter()
+        .find(|(recipe, _)| *recipe == name)
+        .map(|(_, templates)| templates.iter().map(|t| t.to_string()).collect())
+}
+
+/// Turns recipe `name` into a preset for generation
+pub fn preset(name: &str, configured: &BTreeMap<String, Vec<String>>) -> Result<Preset> {
+    let Some(templates) = find(name, configured) else {
+        anyhow::bail!(
+            "Unknown recipe '{}'. Available recipes: {}",
+            name,
+            recipe_names(configured).join(", ")
+        );
+    };
+    if templates.is_empty() {
+        anyhow::bail!("Recipe '{}' lists no templates", name);
+    }
+    Ok(Preset {
+        templates,
+        custom: BTreeMap::new(),
+    })
+}
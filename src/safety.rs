@@ -0,0 +1,102 @@
+//! Safe-mode checks on downloaded templates. A careless or compromised
+//! source could hide patterns in very long lines or invisible characters,
+//! or ignore paths outside the repository; templates with such content are
+//! quarantined during the update instead of being written into projects.
+
+/// Longest line accepted; patterns in github/gitignore stay far below this
+pub const MAX_LINE_LENGTH: usize = 1000;
+
+/// Findings kept per template, so a binary file does not flood the report
+const MAX_FINDINGS: usize = 5;
+
+/// Top-level directories of a system, which an ignore file anchored at the
+/// repository root has no reason to name
+const SYSTEM_DIRECTORIES: [&str; 9] = [
+    "etc", "proc", "sys", "dev", "boot", "root", "home", "usr", "Users",
+];
+
+/// Describes what makes `content` suspicious, one finding per entry with
+/// its line number; empty when the template looks like an ordinary ignore
+/// file
+pub fn scan(content: &str) -> Vec<String> {
+    let mut findings = Vec::new();
+    for (idx, line) in content.lines().enumerate() {
+        let number = idx + 1;
+        let length = line.chars().count();
+        if length > MAX_LINE_LENGTH {
+            findings.push(format!(
+                "line {} is {} characters long (at most {})",
+                number, length, MAX_LINE_LENGTH
+            ));
+        }
+        // A byte order mark may start the file
+        let line = if idx == 0 {
+            line.trim_start_matches('\u{feff}')
+        } else {
+            line
+        };
+        if let Some(ch) = line.chars().find(|&ch| is_hidden(ch)) {
+            findings.push(format!(
+                "line {} has the non-printable character U+{:04X}",
+                number, ch as u32
+            ));
+        }
+        if let Some(path) = outside_path(line) {
+            findings.push(format!(
+                "line {} names a path outside the repository: {}",
+                number, path
+            ));
+        }
+        if findings.len() >= MAX_FINDINGS {
+            findings.truncate(MAX_FINDINGS);
+            break;
+        }
+    }
+    findings
+}
+
+/// Shows `content` with the characters `scan` flags spelled out as
+/// `<U+XXXX>`, so reviewing a quarantined template cannot send escape
+/// sequences to the terminal
+pub fn reveal(content: &str) -> String {
+    let mut revealed = String::with_capacity(content.len());
+    for ch in content.chars() {
+        if ch != '\n' && is_hidden(ch) {
+            revealed.push_str(&format!("<U+{:04X}>", ch as u32));
+        } else {
+            revealed.push(ch);
+        }
+    }
+    revealed
+}
+
+/// Control characters other than tabs and the carriage return of CRLF
+/// files, and the invisible characters that reorder or hide text
+fn is_hidden(ch: char) -> bool {
+    (ch.is_control() && ch != '\t' && ch != '\r')
+        || matches!(
+            ch,
+            '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}' | '\u{feff}'
+        )
+}
+
+/// The pattern on `line` when it names a home directory, a drive or a
+/// system directory rather than a path inside the repository
+fn outside_path(line: &str) -> Option<&str> {
+    let pattern = line.trim();
+    if pattern.starts_with('#') {
+        return None;
+    }
+    let path = pattern.strip_prefix('!').unwrap_or(pattern);
+    let bytes = path.as_bytes();
+    let drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'/' | b'\\');
+    // `~$*` and `*~` are editor backups, `~/` is the user's home
+    if path.starts_with("~/") || drive {
+        return Some(pattern);
+    }
+    let first = path.strip_prefix('/')?.split('/').next()?;
+    SYSTEM_DIRECTORIES.contains(&first).then_some(pattern)
+}
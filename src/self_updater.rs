@@ -2,36 +2,111 @@ use anyhow::{Context, Result, anyhow};
 use flate2::read::GzDecoder;
 use reqwest::header;
 use self_update::backends::github::ReleaseList;
+use self_update::update::{Release, ReleaseAsset};
 use self_update::{Download, self_replace, version};
+use serde::{Deserialize, Serialize};
 use std::env;
 use std::ffi::OsStr;
 use std::fs::{self, File};
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Read};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use tempfile::Builder;
 use xz2::read::XzDecoder;
 use zip::read::ZipArchive;
 
+use crate::ui::display::confirm;
+use crate::validation::validate_remote_url;
+
 const BIN_NAME: &str = "lignore";
 
-pub fn update() -> Result<()> {
+/// Suffix of binary patches published next to the full release archives
+const PATCH_SUFFIX: &str = ".patch.zst";
+
+/// How long the downloaded binary may take to print its version
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Set to `1`, `true` or `yes` to skip the confirmation prompt
+pub const ASSUME_YES_ENV: &str = "LIGNORE_SELF_UPDATE_ASSUME_YES";
+
+/// Token sent to the release host, for releases in a private repository
+pub const TOKEN_ENV: &str = "LIGNORE_SELF_UPDATE_TOKEN";
+
+pub const DEFAULT_RELEASE_REPO: &str = "kmizuki/lightignore";
+
+/// The `self_update` section of the settings file, for forks and
+/// distributions that publish their own releases
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SelfUpdateSettings {
+    /// Repository the releases are published in, as `owner/name`
+    pub repo: String,
+    /// API root of a GitHub Enterprise server or an artifact server with a
+    /// GitHub-compatible releases API, in place of api.github.com
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+}
+
+impl Default for SelfUpdateSettings {
+    fn default() -> Self {
+        Self {
+            repo: DEFAULT_RELEASE_REPO.to_string(),
+            api_url: None,
+        }
+    }
+}
+
+impl SelfUpdateSettings {
+    pub fn validate(&self) -> Result<()> {
+        self.owner_and_name()?;
+        if let Some(url) = &self.api_url {
+            validate_remote_url(url)?;
+        }
+        Ok(())
+    }
+
+    fn owner_and_name(&self) -> Result<(&str, &str)> {
+        match self.repo.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok((owner, name))
+            }
+            _ => anyhow::bail!(
+                "Invalid release repository '{}': expected owner/name",
+                self.repo
+            ),
+        }
+    }
+}
+
+pub fn update(settings: &SelfUpdateSettings, assume_yes: bool) -> Result<()> {
+    let assume_yes = assume_yes || assume_yes_from_env();
     let current_version = env!("CARGO_PKG_VERSION");
+    let token = env::var(TOKEN_ENV).ok().filter(|token| !token.is_empty());
+    let host = settings.api_url.as_deref().unwrap_or("GitHub");
 
     println!("Current version: {}", current_version);
-    println!("Checking for updates...");
+    println!("Checking for updates in {} on {}...", settings.repo, host);
 
     let target = self_update::get_target();
     println!("Checking target-arch... {}", target);
     println!("Checking current version... v{}", current_version);
 
-    let releases = ReleaseList::configure()
-        .repo_owner("kmizuki")
-        .repo_name("lightignore")
-        .with_target(&target)
+    let (owner, name) = settings.owner_and_name()?;
+    let mut query = ReleaseList::configure();
+    query.repo_owner(owner).repo_name(name).with_target(target);
+    if let Some(url) = &settings.api_url {
+        query.with_url(url.trim_end_matches('/'));
+    }
+    if let Some(token) = &token {
+        query.auth_token(token);
+    }
+    let releases = query
         .build()
-        .context("building GitHub release query")?
+        .context("building release query")?
         .fetch()
-        .context("fetching releases from GitHub")?;
+        .with_context(|| format!("fetching releases from {}", host))?;
 
     if let Some(latest) = releases.first() {
         println!(
@@ -69,7 +144,7 @@ pub fn update() -> Result<()> {
     println!("New release is {}compatible", compatibility_note);
 
     let asset = release
-        .asset_for(&target, None)
+        .asset_for(target, None)
         .ok_or_else(|| anyhow!("No release asset available for target '{}'.", target))?;
 
     let current_exe = env::current_exe().context("locating current executable")?;
@@ -82,7 +157,14 @@ pub fn update() -> Result<()> {
         "\nThe new release will be downloaded/extracted and the existing binary will be replaced."
     );
 
-    if !prompt_yes_no("Do you want to continue? [Y/n] ")? {
+    if assume_yes {
+        println!("Continuing without confirmation.");
+    } else if !io::stdin().is_terminal() {
+        anyhow::bail!(
+            "Cannot ask for confirmation because stdin is not a terminal. Re-run with `lignore self-update --yes` or set {}=1.",
+            ASSUME_YES_ENV
+        );
+    } else if !confirm("Do you want to continue? [Y/n] ")? {
         println!("Update aborted.");
         return Ok(());
     }
@@ -91,26 +173,44 @@ pub fn update() -> Result<()> {
         .prefix("lightignore-update")
         .tempdir()
         .context("creating temporary directory")?;
-    let archive_path = temp_dir.path().join(&asset.name);
+    let bin_name = format!("{}{}", BIN_NAME, env::consts::EXE_SUFFIX);
 
-    println!("Downloading...");
-    let mut archive_file =
-        File::create(&archive_path).context("creating temporary archive file")?;
-    let mut download = Download::from_url(&asset.download_url);
-    let mut headers = header::HeaderMap::new();
-    headers.insert(header::ACCEPT, "application/octet-stream".parse().unwrap());
-    download.set_headers(headers);
-    download.show_progress(true);
-    download
-        .download_to(&mut archive_file)
-        .context("downloading release asset")?;
-    drop(archive_file);
+    let patched = find_patch(&release, target, current_version).and_then(|patch| {
+        apply_patch(
+            patch,
+            &current_exe,
+            temp_dir.path(),
+            &bin_name,
+            token.as_deref(),
+        )
+        .and_then(|path| verify_binary(&path, &release.version).map(|()| path))
+        .inspect_err(|e| {
+            println!(
+                "Could not apply patch ({:#}); downloading the full release instead.",
+                e
+            )
+        })
+        .ok()
+    });
+
+    let new_exe_path = match patched {
+        Some(path) => path,
+        None => {
+            let archive_path = temp_dir.path().join(&asset.name);
+            println!("Downloading...");
+            download_asset(&asset.download_url, &archive_path, token.as_deref())?;
+
+            println!("Extracting archive...");
+            let new_exe_path = unpack_asset(&archive_path, temp_dir.path(), &bin_name)
+                .context("extracting downloaded archive")?;
+            make_executable(&new_exe_path)?;
+            println!("Verifying new binary...");
+            verify_binary(&new_exe_path, &release.version)
+                .context("verifying downloaded binary")?;
+            new_exe_path
+        }
+    };
 
-    println!("Extracting archive...");
-    let bin_name = format!("{}{}", BIN_NAME, env::consts::EXE_SUFFIX);
-    let new_exe_path = unpack_asset(&archive_path, temp_dir.path(), &bin_name)
-        .context("extracting downloaded archive")?;
-    make_executable(&new_exe_path)?;
     println!("Replacing binary file...");
     self_replace::self_replace(&new_exe_path).context("replacing installed binary")?;
 
@@ -121,16 +221,82 @@ pub fn update() -> Result<()> {
     Ok(())
 }
 
-fn prompt_yes_no(prompt: &str) -> Result<bool> {
-    print!("{}", prompt);
-    io::stdout().flush().context("flushing prompt")?;
+/// Finds a patch published for upgrading this exact version and target,
+/// named like `lignore-<target>-from-<version>.patch.zst`
+fn find_patch<'a>(
+    release: &'a Release,
+    target: &str,
+    current_version: &str,
+) -> Option<&'a ReleaseAsset> {
+    let from = format!("-from-{}", current_version.trim_start_matches('v'));
+    let from_tag = format!("-from-v{}", current_version.trim_start_matches('v'));
+    release.assets.iter().find(|asset| {
+        asset.name.contains(target)
+            && asset.name.ends_with(PATCH_SUFFIX)
+            && (asset.name.contains(&format!("{}{}", from, PATCH_SUFFIX))
+                || asset
+                    .name
+                    .contains(&format!("{}{}", from_tag, PATCH_SUFFIX)))
+    })
+}
+
+/// Downloads a zstd `--patch-from` patch and applies it to the running
+/// executable, writing the result into `work_dir`
+fn apply_patch(
+    patch: &ReleaseAsset,
+    current_exe: &Path,
+    work_dir: &Path,
+    bin_name: &str,
+    token: Option<&str>,
+) -> Result<PathBuf> {
+    println!("Downloading patch {}...", patch.name);
+    let patch_path = work_dir.join(&patch.name);
+    download_asset(&patch.download_url, &patch_path, token)?;
+
+    println!("Applying patch...");
+    let current = fs::read(current_exe).context("reading current executable")?;
+    let patch_file = File::open(&patch_path).context("opening downloaded patch")?;
+    let mut decoder =
+        zstd::stream::read::Decoder::with_ref_prefix(io::BufReader::new(patch_file), &current)
+            .context("preparing patch decoder")?;
+    // Patches reference the whole old binary, so allow the largest window
+    decoder
+        .window_log_max(31)
+        .context("configuring patch decoder")?;
+
+    let dest = work_dir.join(bin_name);
+    let mut out = File::create(&dest).context("creating patched binary")?;
+    io::copy(&mut decoder, &mut out).context("patch does not match the current binary")?;
+    drop(out);
+    make_executable(&dest)?;
+    Ok(dest)
+}
 
-    let mut answer = String::new();
-    io::stdin()
-        .read_line(&mut answer)
-        .context("reading confirmation input")?;
-    let normalized = answer.trim().to_lowercase();
-    Ok(normalized.is_empty() || normalized == "y" || normalized == "yes")
+/// Downloads an asset through the releases API, which serves assets of
+/// private repositories only with the token
+fn download_asset(url: &str, dest: &Path, token: Option<&str>) -> Result<()> {
+    let mut file = File::create(dest).context("creating temporary download file")?;
+    let mut download = Download::from_url(url);
+    let mut headers = header::HeaderMap::new();
+    headers.insert(header::ACCEPT, "application/octet-stream".parse().unwrap());
+    if let Some(token) = token {
+        let value = format!("token {}", token)
+            .parse()
+            .with_context(|| format!("{} is not a valid header value", TOKEN_ENV))?;
+        headers.insert(header::AUTHORIZATION, value);
+    }
+    download.set_headers(headers);
+    download.show_progress(true);
+    download
+        .download_to(&mut file)
+        .context("downloading release asset")?;
+    Ok(())
+}
+
+fn assume_yes_from_env() -> bool {
+    env::var(ASSUME_YES_ENV)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
 }
 
 fn unpack_asset(archive_path: &Path, work_dir: &Path, bin_name: &str) -> Result<PathBuf> {
@@ -215,6 +381,60 @@ fn find_binary(root: &Path, bin_name: &str) -> Result<PathBuf> {
     Err(anyhow!("Extracted archive does not contain `{}`", bin_name))
 }
 
+/// Runs the extracted binary with `--version` from its temporary location
+/// and checks it reports the release version, so a corrupted or
+/// wrong-target download never replaces the working install
+fn verify_binary(path: &Path, expected_version: &str) -> Result<()> {
+    let mut child = Command::new(path)
+        .arg("--version")
+        .current_dir(path.parent().unwrap_or(path))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("running the new binary; it may be built for a different platform")?;
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().context("waiting for the new binary")? {
+            break status;
+        }
+        if started.elapsed() > VERIFY_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!(
+                "The new binary did not exit within {}s",
+                VERIFY_TIMEOUT.as_secs()
+            );
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    if !status.success() {
+        anyhow::bail!(
+            "The new binary exited with {} when asked for its version",
+            status
+        );
+    }
+
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout
+            .read_to_string(&mut output)
+            .context("reading the new binary's version")?;
+    }
+    // clap prints "<name> <version>"
+    let reported = output.split_whitespace().last().unwrap_or_default();
+    let expected = expected_version.trim_start_matches('v');
+    if reported.trim_start_matches('v') != expected {
+        anyhow::bail!(
+            "The new binary reports version '{}' but the release is {}",
+            output.trim(),
+            expected
+        );
+    }
+    Ok(())
+}
+
 fn make_executable(path: &Path) -> Result<()> {
     #[cfg(unix)]
     {
@@ -0,0 +1,55 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::lock::content_hash;
+use crate::settings::SortOrder;
+
+/// Directory in the cache holding one picker session per project
+pub const SESSIONS_DIR: &str = "sessions";
+
+/// Where the picker was left in a project, restored when it opens again
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct PickerSession {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub filter: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortOrder>,
+    /// Template under the cursor, kept by name so it survives cache updates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
+impl PickerSession {
+    /// Reads the session saved for `project`, or an empty one
+    pub fn read(cache_dir: &Path, project: &Path) -> Self {
+        fs::read(session_path(cache_dir, project))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write(&self, cache_dir: &Path, project: &Path) -> Result<()> {
+        let path = session_path(cache_dir, project);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating directory {}", parent.display()))?;
+        }
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(&path, data).with_context(|| format!("writing {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Sessions are keyed by a hash of the project directory so any path can be
+/// used as a file name
+fn session_path(cache_dir: &Path, project: &Path) -> PathBuf {
+    let project = project
+        .canonicalize()
+        .unwrap_or_else(|_| project.to_path_buf());
+    let key = content_hash(&project.to_string_lossy());
+    cache_dir
+        .join(SESSIONS_DIR)
+        .join(format!("{}.json", &key[..16]))
+}
@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::jsonc;
+use crate::limits::Limits;
+use crate::paths::config_dir;
+use crate::self_updater::SelfUpdateSettings;
+use crate::ui::theme::ThemeSettings;
+use crate::validation::{file_url_path, validate_remote_url};
+
+pub const SETTINGS_FILE: &str = "config.json";
+
+pub const DEFAULT_SOURCE_NAME: &str = "github";
+pub const DEFAULT_GITHUB_REPO: &str = "github/gitignore";
+
+/// User-level settings shared by every project
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct Settings {
+    pub sources: Vec<SourceConfig>,
+    /// What to do when several sources provide the same template name
+    pub conflict_policy: ConflictPolicy,
+    /// Default ordering for `list` and the template picker
+    pub sort: SortOrder,
+    /// What `lignore` does when run without a subcommand
+    pub default_command: DefaultCommand,
+    /// Size limits for downloaded and custom templates
+    pub limits: Limits,
+    /// Templates or `@tags` that belong in the user's global git excludes
+    /// file rather than each repository's .gitignore
+    pub personal: Vec<String>,
+    /// Whether generating records the selection in lignore.json and
+    /// lignore.lock; an existing lignore.json is read either way
+    pub save_config: bool,
+    /// Whether to keep local usage statistics in the cache directory
+    pub usage_stats: bool,
+    /// Templates, paths or directories left out of listings and the picker
+    pub hidden: Vec<String>,
+    /// Templates, paths or directories the safe-mode scan quarantined that
+    /// were reviewed and may be generated anyway
+    pub trusted: Vec<String>,
+    /// Template lists for `generate --recipe`, added to the built-in
+    /// recipes or replacing the one of the same name
+    pub recipes: BTreeMap<String, Vec<String>>,
+    /// Colors of the picker and messages
+    pub theme: ThemeSettings,
+    /// Where `self-update` looks for releases
+    pub self_update: SelfUpdateSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            sources: vec![SourceConfig::default_github()],
+            conflict_policy: ConflictPolicy::default(),
+            sort: SortOrder::default(),
+            default_command: DefaultCommand::default(),
+            limits: Limits::default(),
+            personal: Vec::new(),
+            save_config: true,
+            usage_stats: true,
+            hidden: Vec::new(),
+            trusted: Vec::new(),
+            recipes: BTreeMap::new(),
+            theme: ThemeSettings::default(),
+            self_update: SelfUpdateSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Makes every GitHub source fetch `commit`, for projects that pin their
+    /// templates in lignore.json
+    pub fn pin_commit(&mut self, commit: &str) -> Result<()> {
+        // The git trees API does not resolve abbreviated SHAs
+        if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!(
+                "Invalid pin '{}': expected a full 40-character commit SHA",
+                commit
+            );
+        }
+        for source in &mut self.sources {
+            if let SourceKind::Github { commit: pinned, .. } = &mut source.kind {
+                *pinned = Some(commit.to_lowercase());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends every GitHub source's requests to the API at `url`, such as a
+    /// fixture server; raw downloads follow unless a source sets `raw_url`
+    pub fn override_api_url(&mut self, url: &str) -> Result<()> {
+        if file_url_path(url).is_none() {
+            validate_remote_url(url)?;
+        }
+        for source in &mut self.sources {
+            if let SourceKind::Github { api_url, .. } = &mut source.kind {
+                *api_url = Some(url.trim_end_matches('/').to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sources ordered from highest to lowest priority; ties keep the
+    /// order they are listed in
+    pub fn sources_by_priority(&self) -> Vec<&SourceConfig> {
+        let mut sources: Vec<&SourceConfig> = self.sources.iter().collect();
+        sources.sort_by_key(|source| std::cmp::Reverse(source.priority));
+        sources
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// The highest-priority source wins; others are hidden
+    #[default]
+    FirstWins,
+    /// Every conflicting template is exposed as `<source>/<name>`
+    NamespaceAll,
+    /// Refuse to build an index with conflicting names
+    Error,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DefaultCommand {
+    /// `status` in a project whose output is up to date with lignore.json,
+    /// otherwise `generate`
+    #[default]
+    Auto,
+    /// Always open the picker
+    Generate,
+    /// Always summarize the project
+    Status,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortOrder {
+    /// Alphabetical, with project templates first in the picker
+    #[default]
+    Name,
+    /// Commonly used templates such as Node, Python and Rust first
+    Popular,
+    /// Templates you generated most recently first
+    Recent,
+}
+
+impl SortOrder {
+    pub fn toggled(self) -> Self {
+        match self {
+            SortOrder::Name => SortOrder::Popular,
+            SortOrder::Popular => SortOrder::Recent,
+            SortOrder::Recent => SortOrder::Name,
+        }
+    }
+}
+
+/// A place templates are downloaded from
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SourceConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SourceKind,
+    /// Maximum number of requests this source may spend per update
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<u32>,
+    /// Higher priority sources win template name conflicts
+    #[serde(default)]
+    pub priority: i32,
+}
+
+impl SourceConfig {
+    /// This source as a `local` one when it points at a `file://` URL: an
+    /// `http` source's URL, or a GitHub source's `api_url`, under which the
+    /// repository is a directory named like `github/gitignore`
+    pub fn as_local(&self) -> Option<SourceConfig> {
+        let path = match &self.kind {
+            SourceKind::Http { url } => file_url_path(url)?,
+            SourceKind::Github {
+                repo,
+                api_url: Some(url),
+                ..
+            } => file_url_path(url)?.join(repo),
+            _ => return None,
+        };
+        Some(SourceConfig {
+            kind: SourceKind::Local { path },
+            ..self.clone()
+        })
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SourceKind {
+    Github {
+        #[serde(default = "default_github_repo")]
+        repo: String,
+        /// Commit to fetch instead of the default branch
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+        /// API root of a GitHub Enterprise server or a fixture server, in
+        /// place of api.github.com
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        api_url: Option<String>,
+        /// Root raw files are downloaded from; derived from `api_url` when
+        /// unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        raw_url: Option<String>,
+    },
+    /// A directory of `.gitignore` files, such as one written by `lignore mirror`
+    Local { path: PathBuf },
+    /// Any git remote, including SSH URLs, cloned with the `git` CLI
+    Git {
+        url: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        branch: Option<String>,
+        /// Directory inside the repository that holds the templates
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subdir: Option<PathBuf>,
+    },
+    /// Static files on an HTTPS server or S3-compatible bucket with a manifest
+    Http { url: String },
+}
+
+impl SourceKind {
+    /// The `type` tag used in the settings file
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SourceKind::Github { .. } => "github",
+            SourceKind::Local { .. } => "local",
+            SourceKind::Git { .. } => "git",
+            SourceKind::Http { .. } => "http",
+        }
+    }
+}
+
+fn default_github_repo() -> String {
+    DEFAULT_GITHUB_REPO.to_string()
+}
+
+impl SourceConfig {
+    pub fn default_github() -> Self {
+        Self {
+            name: DEFAULT_SOURCE_NAME.to_string(),
+            kind: SourceKind::Github {
+                repo: default_github_repo(),
+                commit: None,
+                api_url: None,
+                raw_url: None,
+            },
+            budget: None,
+            priority: 0,
+        }
+    }
+}
+
+/// Location of the user-level settings file
+pub fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(SETTINGS_FILE))
+}
+
+/// Loads user settings, falling back to defaults when no file exists
+pub fn load_settings() -> Result<Settings> {
+    let Some(path) = settings_path() else {
+        return Ok(Settings::default());
+    };
+    if !path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let settings: Settings = serde_json::from_str(&jsonc::strip(&content))
+        .with_context(|| format!("parsing {}", path.display()))?;
+    validate_settings(&settings)?;
+    Ok(settings)
+}
+
+fn validate_settings(settings: &Settings) -> Result<()> {
+    settings.limits.validate().context("invalid limits")?;
+    settings.theme.validate().context("invalid theme")?;
+    settings
+        .self_update
+        .validate()
+        .context("invalid self_update")?;
+
+    if settings.sources.is_empty() {
+        anyhow::bail!("At least one template source must be configured");
+    }
+
+    let mut names = std::collections::BTreeSet::new();
+    for source in &settings.sources {
+        crate::validation::validate_template_key(&source.name)
+            .with_context(|| format!("invalid source name '{}'", source.name))?;
+        if source.name.contains('/') {
+            anyhow::bail!("Source name '{}' cannot contain '/'", source.name);
+        }
+        if !names.insert(source.name.as_str()) {
+            anyhow::bail!("Source '{}' is configured more than once", source.name);
+        }
+        if source.budget == Some(0) {
+            anyhow::bail!("Source '{}' has a request budget of 0", source.name);
+        }
+        if let SourceKind::Github {
+            api_url, raw_url, ..
+        } = &source.kind
+        {
+            let remote = api_url
+                .iter()
+                .filter(|url| file_url_path(url).is_none())
+                .chain(raw_url);
+            for url in remote {
+                validate_remote_url(url)
+                    .with_context(|| format!("invalid URL for source '{}'", source.name))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use anyhow::{Context, Result};
+use once_cell::sync::OnceCell;
+use std::path::PathBuf;
+use std::process::Command;
+use tempfile::TempDir;
+
+use super::{
+    FetchedTemplate, RemoteTemplate, SourceContext, SourceFuture, SourceMetadata, TemplateSource,
+    fetch_local,
+};
+use crate::mirror::{list_local_templates, read_local_template};
+use crate::settings::{SourceConfig, SourceKind};
+
+/// Templates in any git repository, cloned with the `git` CLI so SSH
+/// remotes and the user's existing credentials work without tokens
+pub struct GitSource {
+    name: String,
+    url: String,
+    branch: Option<String>,
+    subdir: Option<PathBuf>,
+    checkout: OnceCell<TempDir>,
+}
+
+impl GitSource {
+    pub fn from_config(config: &SourceConfig) -> Result<Box<dyn TemplateSource>> {
+        let SourceKind::Git {
+            url,
+            branch,
+            subdir,
+        } = &config.kind
+        else {
+            anyhow::bail!("expected a git source");
+        };
+        if url.starts_with('-') {
+            anyhow::bail!("Invalid git URL: {}", url);
+        }
+        if let Some(branch) = branch
+            && branch.starts_with('-')
+        {
+            anyhow::bail!("Invalid git branch: {}", branch);
+        }
+        Ok(Box::new(Self {
+            name: config.name.clone(),
+            url: url.clone(),
+            branch: branch.clone(),
+            subdir: subdir.clone(),
+            checkout: OnceCell::new(),
+        }))
+    }
+
+    /// Shallow-clones the repository once per update
+    fn checkout(&self) -> Result<PathBuf> {
+        let dir = self.checkout.get_or_try_init(|| -> Result<TempDir> {
+            let dir = tempfile::Builder::new()
+                .prefix("lightignore-git")
+                .tempdir()
+                .context("creating temporary directory")?;
+            println!("Cloning {}...", self.url);
+
+            let mut command = Command::new("git");
+            command.arg("clone").arg("--depth").arg("1").arg("--quiet");
+            if let Some(branch) = &self.branch {
+                command.arg("--branch").arg(branch);
+            }
+            let status = command
+                .arg("--")
+                .arg(&self.url)
+                .arg(dir.path())
+                // Fail instead of hanging on an interactive credential prompt
+                .env("GIT_TERMINAL_PROMPT", "0")
+                .status()
+                .context("running git; is it installed and on PATH?")?;
+            if !status.success() {
+                anyhow::bail!("git clone of {} failed ({})", self.url, status);
+            }
+            Ok(dir)
+        })?;
+
+        Ok(match &self.subdir {
+            Some(subdir) => dir.path().join(subdir),
+            None => dir.path().to_path_buf(),
+        })
+    }
+}
+
+impl TemplateSource for GitSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        let location = match &self.branch {
+            Some(branch) => format!("{}#{}", self.url, branch),
+            None => self.url.clone(),
+        };
+        SourceMetadata {
+            kind: "git",
+            location,
+        }
+    }
+
+    fn list<'a>(&'a self, _ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<RemoteTemplate>> {
+        Box::pin(async move {
+            let root = self.checkout()?;
+            let mut templates = list_local_templates(&root)?;
+            // The clone's own .git directory never holds templates
+            templates.retain(|template| !template.key.starts_with(".git/"));
+            Ok(templates)
+        })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        _ctx: &'a SourceContext<'a>,
+        template: &'a RemoteTemplate,
+    ) -> SourceFuture<'a, String> {
+        Box::pin(async move { read_local_template(template) })
+    }
+
+    fn sync<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<FetchedTemplate>> {
+        Box::pin(async move {
+            let templates = self.list(ctx).await?;
+            Ok(fetch_local(self, ctx, templates).await)
+        })
+    }
+}
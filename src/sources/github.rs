@@ -0,0 +1,609 @@
+use anyhow::{Context, Result};
+use crossterm::{
+    QueueableCommand,
+    style::{Print, ResetColor, SetForegroundColor},
+};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::{
+    DownloadEstimate, FetchedTemplate, RemoteTemplate, SourceContext, SourceFuture, SourceMetadata,
+    TemplateSource, fetch_all,
+};
+use crate::app::MAX_ARCHIVE_SIZE;
+use crate::archive::read_tar_gz;
+use crate::exit::RateLimited;
+use crate::http::send;
+use crate::limits::get_limits;
+use crate::progress::ProgressEvent;
+use crate::rate_limit::RequestBudget;
+use crate::settings::{SourceConfig, SourceKind};
+use crate::ui::theme::get_theme;
+use crate::validation::{validate_remote_url, validate_template_key};
+
+pub const GITHUB_API: &str = "https://api.github.com";
+const GITHUB_RAW: &str = "https://raw.githubusercontent.com";
+
+/// Path GitHub Enterprise serves its API under, next to `/raw`
+const ENTERPRISE_API_SUFFIX: &str = "/api/v3";
+
+// The contents API stops listing a directory at this many entries
+const CONTENTS_ENTRY_LIMIT: usize = 1000;
+
+// Used to estimate the cost of a contents walk before the first update
+const ESTIMATED_TEMPLATE_COUNT: usize = 260;
+const ESTIMATED_DIRECTORY_COUNT: u32 = 5;
+const ESTIMATED_TEMPLATE_SIZE: u64 = 1200;
+const ESTIMATED_TARBALL_SIZE: u64 = 250 * 1024;
+
+#[derive(Deserialize, Debug)]
+pub struct RepoContent {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub content_type: String,
+    pub download_url: Option<String>,
+    pub path: String,
+    #[serde(default)]
+    pub sha: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitTree {
+    pub tree: Vec<GitTreeEntry>,
+    /// Set when the tree is too large to return in one response
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GitTreeEntry {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+}
+
+/// Quota reported by the `X-RateLimit-*` headers of a response
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset: u64,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Some(Self {
+            limit: header_number(headers, "x-ratelimit-limit")?,
+            remaining: header_number(headers, "x-ratelimit-remaining")?,
+            reset: header_number(headers, "x-ratelimit-reset")?,
+        })
+    }
+}
+
+fn header_number<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Templates hosted in a GitHub repository, such as github/gitignore
+pub struct GithubSource {
+    name: String,
+    repo: String,
+    /// Pinned commit; the default branch is used when unset
+    commit: Option<String>,
+    /// API root, such as https://api.github.com
+    api_root: String,
+    /// `repos/<repo>` endpoint under the API root
+    api: String,
+    /// Root raw files are downloaded from
+    raw: String,
+    budget: Option<u32>,
+    /// Budget for the contents walk, keeping one request in reserve so a
+    /// walk that runs over can still fall back to the tarball
+    walk_budget: RequestBudget,
+}
+
+impl GithubSource {
+    pub fn from_config(config: &SourceConfig) -> Result<Box<dyn TemplateSource>> {
+        let SourceKind::Github {
+            repo,
+            commit,
+            api_url,
+            raw_url,
+        } = &config.kind
+        else {
+            anyhow::bail!("expected a github source");
+        };
+        let api_root = api_url
+            .as_deref()
+            .unwrap_or(GITHUB_API)
+            .trim_end_matches('/')
+            .to_string();
+        let raw = match (raw_url, api_url) {
+            (Some(raw), _) => raw.trim_end_matches('/').to_string(),
+            (None, None) => GITHUB_RAW.to_string(),
+            // GitHub Enterprise serves raw files from /raw beside /api/v3;
+            // anything else, such as a fixture server, from /raw below it
+            (None, Some(_)) => match api_root.strip_suffix(ENTERPRISE_API_SUFFIX) {
+                Some(host) => format!("{}/raw", host),
+                None => format!("{}/raw", api_root),
+            },
+        };
+        Ok(Box::new(Self {
+            name: config.name.clone(),
+            repo: repo.clone(),
+            commit: commit.clone(),
+            api: format!("{}/repos/{}", api_root, repo),
+            api_root,
+            raw,
+            budget: config.budget,
+            walk_budget: RequestBudget::new(config.budget.map(|limit| limit.saturating_sub(1))),
+        }))
+    }
+
+    /// Git revision every listing and download is made at
+    fn revision(&self) -> &str {
+        self.commit.as_deref().unwrap_or("HEAD")
+    }
+
+    /// Sends one budgeted API request, checks its status and parses its
+    /// JSON body as `what`, returning it with the response headers
+    async fn api_json<T: DeserializeOwned>(
+        &self,
+        ctx: &SourceContext<'_>,
+        url: &str,
+        what: &str,
+    ) -> Result<(HeaderMap, T)> {
+        self.walk_budget.spend()?;
+        // Held until the body is read, so slow bodies count against the limit
+        let _permit = ctx.rate_limiter.acquire().await?;
+        let res = send(ctx.client.get(url))
+            .await
+            .with_context(|| format!("requesting {}", url))?;
+        ctx.rate_limiter.observe(res.headers());
+        if !res.status().is_success() {
+            let message = format!("GitHub API returned status {}", res.status());
+            return Err(status_error(res, message).await);
+        }
+        let headers = res.headers().clone();
+        let body = res
+            .json::<T>()
+            .await
+            .with_context(|| format!("parsing {}", what))?;
+        Ok((headers, body))
+    }
+
+    /// Lists one directory through the contents API, following `Link`
+    /// pagination
+    async fn fetch_repo_tree(
+        &self,
+        ctx: &SourceContext<'_>,
+        path: &str,
+    ) -> Result<Vec<RepoContent>> {
+        let mut url = Some(match &self.commit {
+            Some(commit) => format!("{}/contents/{}?ref={}", self.api, path, commit),
+            None => format!("{}/contents/{}", self.api, path),
+        });
+        let mut contents = Vec::new();
+        while let Some(current) = url {
+            let (headers, mut page) = self
+                .api_json::<Vec<RepoContent>>(ctx, &current, "GitHub contents response")
+                .await?;
+            url = next_page_url(&headers).filter(|next| next.starts_with(&self.api_root));
+            contents.append(&mut page);
+        }
+        Ok(contents)
+    }
+
+    /// Lists a whole tree through the git trees API
+    async fn fetch_git_tree(&self, ctx: &SourceContext<'_>, tree: &str) -> Result<GitTree> {
+        let url = format!("{}/git/trees/{}?recursive=1", self.api, tree);
+        let (_, tree) = self
+            .api_json::<GitTree>(ctx, &url, "GitHub tree response")
+            .await?;
+        Ok(tree)
+    }
+
+    /// Turns git tree entries below `prefix` into templates
+    fn templates_from_tree(&self, prefix: &str, tree: GitTree) -> Vec<RemoteTemplate> {
+        tree.tree
+            .into_iter()
+            .filter(|entry| entry.entry_type == "blob")
+            .filter_map(|entry| {
+                let path = if prefix.is_empty() {
+                    entry.path
+                } else {
+                    format!("{}/{}", prefix, entry.path)
+                };
+                let key = path.strip_suffix(".gitignore")?.to_string();
+                let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+                if name.is_empty() {
+                    return None;
+                }
+                Some(RemoteTemplate {
+                    location: format!(
+                        "{}/{}/{}/{}",
+                        self.raw,
+                        self.repo,
+                        self.revision(),
+                        path.replace(' ', "%20")
+                    ),
+                    key,
+                    name,
+                    sha256: None,
+                })
+            })
+            .collect()
+    }
+
+    // Collect all template information without downloading
+    fn collect_templates_recursive<'a>(
+        &'a self,
+        ctx: &'a SourceContext<'a>,
+        path: &'a str,
+        sha: &'a str,
+    ) -> SourceFuture<'a, Vec<RemoteTemplate>> {
+        Box::pin(async move {
+            let contents = self.fetch_repo_tree(ctx, path).await?;
+            if contents.len() >= CONTENTS_ENTRY_LIMIT {
+                // The contents API silently drops entries past its limit
+                if ctx.verbose {
+                    println!(
+                        "{} has {} or more entries; listing it through the trees API",
+                        if path.is_empty() { "/" } else { path },
+                        CONTENTS_ENTRY_LIMIT
+                    );
+                }
+                let tree = self.fetch_git_tree(ctx, sha).await?;
+                if tree.truncated {
+                    ctx.warn(format!(
+                        "{} in {} is too large to list completely; some templates may be missing",
+                        if path.is_empty() {
+                            "The repository"
+                        } else {
+                            path
+                        },
+                        self.repo
+                    ));
+                }
+                return Ok(self.templates_from_tree(path, tree));
+            }
+
+            let mut templates = Vec::new();
+            for entry in contents {
+                if entry.content_type == "file" && entry.name.ends_with(".gitignore") {
+                    if let Some(download_url) = entry.download_url {
+                        let name = entry.name.trim_end_matches(".gitignore").to_string();
+                        // Use the full path as the cache key to avoid conflicts
+                        let key = if path.is_empty() {
+                            name.clone()
+                        } else {
+                            format!("{}/{}", path, name)
+                        };
+                        templates.push(RemoteTemplate {
+                            key,
+                            name,
+                            location: download_url,
+                            sha256: None,
+                        });
+                    }
+                } else if entry.content_type == "dir" {
+                    let mut sub_templates = self
+                        .collect_templates_recursive(ctx, &entry.path, &entry.sha)
+                        .await?;
+                    templates.append(&mut sub_templates);
+                }
+            }
+
+            Ok(templates)
+        })
+    }
+
+    /// Downloads the whole repository as a single tarball request
+    async fn fetch_tarball(
+        &self,
+        ctx: &SourceContext<'_>,
+        budget: &RequestBudget,
+    ) -> Result<Vec<FetchedTemplate>> {
+        println!("Downloading repository tarball...");
+        let started = Instant::now();
+
+        budget.spend()?;
+        let _permit = ctx.rate_limiter.acquire().await?;
+        let url = match &self.commit {
+            Some(commit) => format!("{}/tarball/{}", self.api, commit),
+            None => format!("{}/tarball", self.api),
+        };
+        let response = send(ctx.client.get(url))
+            .await
+            .context("downloading repository tarball")?;
+        ctx.rate_limiter.observe(response.headers());
+
+        if !response.status().is_success() {
+            let message = format!(
+                "failed to download repository tarball: status {}",
+                response.status()
+            );
+            return Err(status_error(response, message).await);
+        }
+        if let Some(content_length) = response.content_length()
+            && content_length > MAX_ARCHIVE_SIZE
+        {
+            anyhow::bail!(
+                "Repository tarball is too large: {} bytes (max: {} bytes)",
+                content_length,
+                MAX_ARCHIVE_SIZE
+            );
+        }
+
+        let bytes = response.bytes().await?;
+        if bytes.len() as u64 > MAX_ARCHIVE_SIZE {
+            anyhow::bail!(
+                "Repository tarball exceeds size limit: {} bytes (max: {} bytes)",
+                bytes.len(),
+                MAX_ARCHIVE_SIZE
+            );
+        }
+
+        let templates = read_tar_gz(bytes.as_ref())?;
+        println!("Extracted {} templates from tarball", templates.len());
+        for (i, template) in templates.iter().enumerate() {
+            ctx.progress.report(&ProgressEvent::TemplateDownloaded {
+                source: &self.name,
+                template: &template.key,
+                current: i + 1,
+                total: templates.len(),
+            });
+        }
+        if ctx.verbose {
+            println!("Fetched tarball in {:.2?}", started.elapsed());
+        }
+        Ok(templates)
+    }
+}
+
+impl TemplateSource for GithubSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        let location = match &self.commit {
+            Some(commit) => format!("{}@{}", self.repo, commit),
+            None => self.repo.clone(),
+        };
+        SourceMetadata {
+            kind: "github",
+            location,
+        }
+    }
+
+    fn estimate(&self) -> Option<DownloadEstimate> {
+        let requests = ESTIMATED_TEMPLATE_COUNT as u32 + ESTIMATED_DIRECTORY_COUNT;
+        Some(if self.walk_budget.allows(requests) {
+            DownloadEstimate {
+                requests,
+                bytes: ESTIMATED_TEMPLATE_COUNT as u64 * ESTIMATED_TEMPLATE_SIZE,
+            }
+        } else {
+            DownloadEstimate {
+                requests: 1,
+                bytes: ESTIMATED_TARBALL_SIZE,
+            }
+        })
+    }
+
+    fn list<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<RemoteTemplate>> {
+        Box::pin(async move {
+            println!("Scanning {} repository...", self.repo);
+            let scan_started = Instant::now();
+            // One request lists the whole repository unless the tree is
+            // truncated, in which case walk it directory by directory
+            let tree = self.fetch_git_tree(ctx, self.revision()).await?;
+            let templates = if tree.truncated {
+                if ctx.verbose {
+                    println!("Repository tree is truncated; walking directories instead");
+                }
+                self.collect_templates_recursive(ctx, "", self.revision())
+                    .await?
+            } else {
+                self.templates_from_tree("", tree)
+            };
+            if ctx.verbose {
+                println!("Scanned repository in {:.2?}", scan_started.elapsed());
+            }
+            Ok(templates)
+        })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        ctx: &'a SourceContext<'a>,
+        template: &'a RemoteTemplate,
+    ) -> SourceFuture<'a, String> {
+        Box::pin(async move {
+            let key = template.key.as_str();
+            let url = template.location.as_str();
+
+            // Validate key to prevent path traversal
+            validate_template_key(key)?;
+
+            validate_remote_url(url)?;
+
+            self.walk_budget.spend()?;
+            let _permit = ctx.rate_limiter.acquire().await?;
+            let response = send(ctx.client.get(url))
+                .await
+                .with_context(|| format!("downloading template {}", key))?;
+            ctx.rate_limiter.observe(response.headers());
+
+            if !response.status().is_success() {
+                let message = format!(
+                    "failed to download template {}: status {}",
+                    key,
+                    response.status()
+                );
+                return Err(status_error(response, message).await);
+            }
+
+            let max_size = get_limits().max_download_size;
+            if let Some(content_length) = response.content_length()
+                && content_length > max_size
+            {
+                anyhow::bail!(
+                    "Template {} is too large: {} bytes (max: {} bytes)",
+                    key,
+                    content_length,
+                    max_size
+                );
+            }
+
+            let content = response.text().await?;
+
+            // Double-check size after download
+            if content.len() > max_size as usize {
+                anyhow::bail!(
+                    "Template {} exceeds size limit: {} bytes (max: {} bytes)",
+                    key,
+                    content.len(),
+                    max_size
+                );
+            }
+
+            Ok(content)
+        })
+    }
+
+    fn sync<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<FetchedTemplate>> {
+        Box::pin(async move {
+            // A scan costs one tree request (or one per directory when the
+            // tree is truncated) plus one per template
+            let estimate = ctx
+                .previous_count
+                .filter(|count| *count > 0)
+                .unwrap_or(ESTIMATED_TEMPLATE_COUNT) as u32
+                + ESTIMATED_DIRECTORY_COUNT;
+            if !self.walk_budget.allows(estimate) {
+                println!(
+                    "Request budget for {} is below the ~{} requests a full scan needs; using the repository tarball instead.",
+                    self.name, estimate
+                );
+                return self
+                    .fetch_tarball(ctx, &RequestBudget::new(self.budget))
+                    .await;
+            }
+
+            let result = match self.list(ctx).await {
+                Ok(templates) => Ok(fetch_all(self, ctx, templates).await),
+                Err(e) => Err(e),
+            };
+            if self.walk_budget.was_exceeded() {
+                println!(
+                    "Request budget for {} ran out during the scan; using the repository tarball instead.",
+                    self.name
+                );
+                return self.fetch_tarball(ctx, &RequestBudget::new(Some(1))).await;
+            }
+            result
+        })
+    }
+}
+
+/// Extracts the `rel="next"` URL from a `Link` header
+fn next_page_url(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == "rel=\"next\"")
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+    })
+}
+
+/// Builds the error for a failed response. A 403 or 429 is explained from
+/// the response itself, telling an exhausted quota apart from GitHub's
+/// secondary (abuse detection) limit, which applies even with quota left
+async fn status_error(response: reqwest::Response, message: String) -> anyhow::Error {
+    let status = response.status();
+    if status != StatusCode::FORBIDDEN && status != StatusCode::TOO_MANY_REQUESTS {
+        return anyhow::anyhow!(message);
+    }
+
+    let rate_limit = RateLimit::from_headers(response.headers());
+    let retry_after = header_number::<u64>(response.headers(), "retry-after");
+    let body = response.text().await.unwrap_or_default().to_lowercase();
+
+    if let Some(rate_limit) = rate_limit.filter(|rate_limit| rate_limit.remaining == 0) {
+        display_rate_limit_info(&rate_limit);
+        return anyhow::Error::new(RateLimited::Quota).context(message);
+    }
+    let secondary = status == StatusCode::TOO_MANY_REQUESTS
+        || retry_after.is_some()
+        || body.contains("secondary rate limit")
+        || body.contains("abuse");
+    if secondary {
+        display_secondary_limit_info(retry_after);
+        return anyhow::Error::new(RateLimited::Secondary).context(message);
+    }
+    // Other 403s, such as a private repository or missing SSO authorization
+    anyhow::anyhow!(message)
+}
+
+fn display_rate_limit_info(rate_limit: &RateLimit) {
+    let mut stdout = io::stdout();
+    let theme = get_theme();
+    let _ = stdout.queue(SetForegroundColor(theme.header_title));
+    let _ = stdout.queue(Print("\nRate Limit Information:\n"));
+    let _ = stdout.queue(ResetColor);
+
+    let _ = stdout.queue(SetForegroundColor(theme.accent));
+    let _ = stdout.queue(Print(format!("  Limit:     {}\n", rate_limit.limit)));
+    let _ = stdout.queue(Print(format!("  Remaining: {}\n", rate_limit.remaining)));
+
+    // Convert reset timestamp to human-readable format
+    let reset_time = rate_limit.reset;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let wait_time = reset_time.saturating_sub(now);
+
+    let minutes = wait_time / 60;
+    let seconds = wait_time % 60;
+
+    let _ = stdout.queue(Print(format!(
+        "  Reset:     {} (in {}m {}s)\n",
+        reset_time, minutes, seconds
+    )));
+    let _ = stdout.queue(ResetColor);
+    let _ = stdout.flush();
+}
+
+fn display_secondary_limit_info(retry_after: Option<u64>) {
+    let mut stdout = io::stdout();
+    let theme = get_theme();
+    let _ = stdout.queue(SetForegroundColor(theme.header_title));
+    let _ = stdout.queue(Print("\nSecondary Rate Limit:\n"));
+    let _ = stdout.queue(ResetColor);
+
+    let _ = stdout.queue(SetForegroundColor(theme.accent));
+    let _ = stdout.queue(Print(
+        "  GitHub throttled too many requests in a short time; your quota is not used up.\n",
+    ));
+    let wait = match retry_after {
+        Some(secs) => format!("  Retry in:  {}m {}s\n", secs / 60, secs % 60),
+        None => "  Retry in:  a few minutes\n".to_string(),
+    };
+    let _ = stdout.queue(Print(wait));
+    let _ = stdout.queue(ResetColor);
+    let _ = stdout.flush();
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use reqwest::{StatusCode, Url};
+
+use super::{RemoteTemplate, SourceContext, SourceFuture, SourceMetadata, TemplateSource};
+use crate::http::send;
+use crate::limits::get_limits;
+use crate::mirror::{
+    CHECKSUMS_FILE, Checksums, MANIFEST_FILE, parse_manifest, published_digest, verify_template,
+};
+use crate::rate_limit::RequestBudget;
+use crate::settings::{SourceConfig, SourceKind};
+use crate::validation::validate_remote_url;
+
+/// Templates published as static files on an HTTPS server or S3-compatible
+/// bucket, next to an index.json manifest in the `lignore mirror` format.
+/// When the mirror publishes SHA256SUMS, the manifest and every template
+/// are checked against it.
+pub struct HttpSource {
+    name: String,
+    base: Url,
+    budget: RequestBudget,
+}
+
+impl HttpSource {
+    pub fn from_config(config: &SourceConfig) -> Result<Box<dyn TemplateSource>> {
+        let SourceKind::Http { url } = &config.kind else {
+            anyhow::bail!("expected an http source");
+        };
+        validate_remote_url(url)?;
+        // Treat the URL as a directory so relative manifest paths resolve inside it
+        let base = Url::parse(&format!("{}/", url.trim_end_matches('/')))
+            .with_context(|| format!("parsing source URL {}", url))?;
+        Ok(Box::new(Self {
+            name: config.name.clone(),
+            base,
+            budget: RequestBudget::new(config.budget),
+        }))
+    }
+
+    async fn get(&self, ctx: &SourceContext<'_>, url: Url, what: &str) -> Result<String> {
+        match self.get_if_present(ctx, url, what).await? {
+            Some(content) => Ok(content),
+            None => anyhow::bail!(
+                "failed to download {}: status {}",
+                what,
+                StatusCode::NOT_FOUND
+            ),
+        }
+    }
+
+    /// Like `get`, but `None` when the server has no such file. S3 answers
+    /// 403 instead of 404 for missing keys unless listing is allowed.
+    async fn get_if_present(
+        &self,
+        ctx: &SourceContext<'_>,
+        url: Url,
+        what: &str,
+    ) -> Result<Option<String>> {
+        self.budget.spend()?;
+        // Held until the body is read, which is most of a download
+        let _permit = ctx.rate_limiter.acquire().await?;
+        let response = send(ctx.client.get(url))
+            .await
+            .with_context(|| format!("downloading {}", what))?;
+        ctx.rate_limiter.observe(response.headers());
+
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::FORBIDDEN
+        ) {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("failed to download {}: status {}", what, response.status());
+        }
+        let max_size = get_limits().max_download_size;
+        if let Some(content_length) = response.content_length()
+            && content_length > max_size
+        {
+            anyhow::bail!(
+                "{} is too large: {} bytes (max: {} bytes)",
+                what,
+                content_length,
+                max_size
+            );
+        }
+
+        let content = response.text().await?;
+        if content.len() > max_size as usize {
+            anyhow::bail!(
+                "{} exceeds size limit: {} bytes (max: {} bytes)",
+                what,
+                content.len(),
+                max_size
+            );
+        }
+        Ok(Some(content))
+    }
+}
+
+impl TemplateSource for HttpSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: "http",
+            location: self.base.to_string(),
+        }
+    }
+
+    fn list<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<RemoteTemplate>> {
+        Box::pin(async move {
+            println!("Fetching manifest from {}...", self.base);
+            let manifest_url = self.base.join(MANIFEST_FILE)?;
+            let data = self.get(ctx, manifest_url, "source manifest").await?;
+            let checksums_url = self.base.join(CHECKSUMS_FILE)?;
+            let checksums = match self
+                .get_if_present(ctx, checksums_url, "source checksums")
+                .await?
+            {
+                Some(text) => {
+                    let checksums = Checksums::parse(&text)?;
+                    checksums.verify(MANIFEST_FILE, &data)?;
+                    Some(checksums)
+                }
+                None => None,
+            };
+            let manifest = parse_manifest(data.as_bytes())?;
+
+            manifest
+                .templates
+                .into_iter()
+                .map(|(name, path)| {
+                    let url = self
+                        .base
+                        .join(&path)
+                        .with_context(|| format!("resolving URL for template {}", name))?;
+                    Ok(RemoteTemplate {
+                        name: name.rsplit('/').next().unwrap_or(&name).to_string(),
+                        sha256: published_digest(checksums.as_ref(), &path)?,
+                        key: name,
+                        location: url.to_string(),
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        ctx: &'a SourceContext<'a>,
+        template: &'a RemoteTemplate,
+    ) -> SourceFuture<'a, String> {
+        Box::pin(async move {
+            let url = Url::parse(&template.location)?;
+            let content = self
+                .get(ctx, url, &format!("template {}", template.key))
+                .await?;
+            verify_template(template, &content)?;
+            Ok(content)
+        })
+    }
+}
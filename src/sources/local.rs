@@ -0,0 +1,64 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::{
+    DownloadEstimate, FetchedTemplate, RemoteTemplate, SourceContext, SourceFuture, SourceMetadata,
+    TemplateSource, fetch_local,
+};
+use crate::mirror::{list_local_templates, read_local_template};
+use crate::settings::{SourceConfig, SourceKind};
+
+/// A directory of `.gitignore` files, such as one written by `lignore mirror`
+pub struct LocalSource {
+    name: String,
+    path: PathBuf,
+}
+
+impl LocalSource {
+    pub fn from_config(config: &SourceConfig) -> Result<Box<dyn TemplateSource>> {
+        let SourceKind::Local { path } = &config.kind else {
+            anyhow::bail!("expected a local source");
+        };
+        Ok(Box::new(Self {
+            name: config.name.clone(),
+            path: path.clone(),
+        }))
+    }
+}
+
+impl TemplateSource for LocalSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metadata(&self) -> SourceMetadata {
+        SourceMetadata {
+            kind: "local",
+            location: self.path.display().to_string(),
+        }
+    }
+
+    fn estimate(&self) -> Option<DownloadEstimate> {
+        Some(DownloadEstimate::default())
+    }
+
+    fn list<'a>(&'a self, _ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<RemoteTemplate>> {
+        Box::pin(async move { list_local_templates(&self.path) })
+    }
+
+    fn fetch<'a>(
+        &'a self,
+        _ctx: &'a SourceContext<'a>,
+        template: &'a RemoteTemplate,
+    ) -> SourceFuture<'a, String> {
+        Box::pin(async move { read_local_template(template) })
+    }
+
+    fn sync<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<FetchedTemplate>> {
+        Box::pin(async move {
+            println!("Reading templates from {}...", self.path.display());
+            let templates = self.list(ctx).await?;
+            Ok(fetch_local(self, ctx, templates).await)
+        })
+    }
+}
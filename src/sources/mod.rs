@@ -0,0 +1,341 @@
+pub mod git;
+pub mod github;
+pub mod http;
+pub mod local;
+pub mod report;
+pub mod state;
+
+use anyhow::{Context, Result};
+use crossterm::{
+    QueueableCommand,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use crate::progress::{ProgressEvent, ProgressFormat};
+use crate::rate_limit::{MAX_CONCURRENCY, RateLimiter};
+use crate::settings::SourceConfig;
+use crate::ui::display::print_warning;
+
+pub use git::GitSource;
+pub use github::GithubSource;
+pub use http::HttpSource;
+pub use local::LocalSource;
+pub use report::{SourceSummary, TemplateFailure, UpdateReport};
+
+pub type SourceFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+/// Shared resources a source may use while talking to its backend
+pub struct SourceContext<'a> {
+    pub client: &'a Client,
+    pub rate_limiter: &'a RateLimiter,
+    pub verbose: bool,
+    /// Templates this source provided on the previous update, for cost estimates
+    pub previous_count: Option<usize>,
+    /// Failures and warnings collected while the source updates
+    pub report: Mutex<UpdateReport>,
+    pub progress: ProgressFormat,
+}
+
+impl SourceContext<'_> {
+    /// Records a template that was listed but could not be downloaded
+    pub fn record_failure(&self, source: &str, template: &str, error: &anyhow::Error) {
+        self.report.lock().unwrap().failures.push(TemplateFailure {
+            source: source.to_string(),
+            template: template.to_string(),
+            error: format!("{:#}", error),
+        });
+    }
+
+    /// Records a problem to show once the update finishes
+    pub fn warn(&self, message: String) {
+        self.report.lock().unwrap().warnings.push(message);
+    }
+
+    /// Number of templates that failed so far
+    pub fn failures(&self) -> usize {
+        self.report.lock().unwrap().failures.len()
+    }
+
+    pub fn into_report(self) -> UpdateReport {
+        self.report.into_inner().unwrap()
+    }
+}
+
+/// A template a source advertises, before its content is downloaded
+#[derive(Debug, Clone)]
+pub struct RemoteTemplate {
+    /// Path of the template inside the source, without extension
+    pub key: String,
+    pub name: String,
+    /// Backend-specific location such as a download URL or file path
+    pub location: String,
+    /// SHA-256 the source publishes for the content, checked once it is read
+    pub sha256: Option<String>,
+}
+
+/// A template with its content, ready to be written to the cache
+#[derive(Debug)]
+pub struct FetchedTemplate {
+    pub key: String,
+    pub name: String,
+    pub content: String,
+}
+
+/// Human-readable description of a configured source
+#[derive(Debug, Clone)]
+pub struct SourceMetadata {
+    pub kind: &'static str,
+    pub location: String,
+}
+
+/// Rough cost of downloading a source that has never been cached
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DownloadEstimate {
+    pub requests: u32,
+    pub bytes: u64,
+}
+
+/// A backend that templates can be downloaded from
+pub trait TemplateSource {
+    fn name(&self) -> &str;
+
+    fn metadata(&self) -> SourceMetadata;
+
+    /// Estimates a first download; `None` when the source cannot tell
+    /// without listing its templates
+    fn estimate(&self) -> Option<DownloadEstimate> {
+        None
+    }
+
+    /// Lists the templates the source provides without downloading them
+    fn list<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<RemoteTemplate>>;
+
+    /// Downloads the content of one listed template
+    fn fetch<'a>(
+        &'a self,
+        ctx: &'a SourceContext<'a>,
+        template: &'a RemoteTemplate,
+    ) -> SourceFuture<'a, String>;
+
+    /// Downloads every template; backends with a cheaper bulk strategy
+    /// override this
+    fn sync<'a>(&'a self, ctx: &'a SourceContext<'a>) -> SourceFuture<'a, Vec<FetchedTemplate>> {
+        Box::pin(async move {
+            let templates = self.list(ctx).await?;
+            Ok(fetch_all(self, ctx, templates).await)
+        })
+    }
+}
+
+/// Fetches listed templates concurrently, reporting progress and skipping
+/// templates that fail
+pub async fn fetch_all<S: TemplateSource + ?Sized>(
+    source: &S,
+    ctx: &SourceContext<'_>,
+    templates: Vec<RemoteTemplate>,
+) -> Vec<FetchedTemplate> {
+    println!("Found {} templates. Downloading...", templates.len());
+    let download_started = Instant::now();
+
+    let counter = AtomicUsize::new(0);
+    let failed = AtomicUsize::new(0);
+    let total = templates.len();
+
+    let results = stream::iter(&templates)
+        .map(|template| {
+            let counter = &counter;
+            let failed = &failed;
+            async move {
+                let started = Instant::now();
+                let result = source.fetch(ctx, template).await;
+                let current = counter.fetch_add(1, Ordering::SeqCst) + 1;
+
+                if ctx.verbose
+                    && let Ok(content) = &result
+                {
+                    println!(
+                        "\r  {} ({} bytes) in {:.2?}",
+                        template.key,
+                        content.len(),
+                        started.elapsed()
+                    );
+                }
+
+                match &result {
+                    Ok(_) => ctx.progress.report(&ProgressEvent::TemplateDownloaded {
+                        source: source.name(),
+                        template: &template.key,
+                        current,
+                        total,
+                    }),
+                    Err(e) => {
+                        failed.fetch_add(1, Ordering::SeqCst);
+                        ctx.progress.report(&ProgressEvent::TemplateFailed {
+                            source: source.name(),
+                            template: &template.key,
+                            error: format!("{:#}", e),
+                            current,
+                            total,
+                        });
+                        show_failure(ctx, source.name(), &template.key, e);
+                    }
+                }
+
+                // Print progress every 10 templates, on the last one and
+                // after a failure pushed the line down
+                if !ctx.progress.is_json()
+                    && (current.is_multiple_of(10) || current == total || result.is_err())
+                {
+                    let mut line = format!(
+                        "\r{}: downloaded {}/{} templates",
+                        source.name(),
+                        current,
+                        total
+                    );
+                    let failed = failed.load(Ordering::SeqCst);
+                    if failed > 0 {
+                        line.push_str(&format!(", {} failed", failed));
+                    }
+                    print!("{}", line);
+                    let _ = io::stdout().flush();
+                }
+
+                match result {
+                    Ok(content) => Some(FetchedTemplate {
+                        key: template.key.clone(),
+                        name: template.name.clone(),
+                        content,
+                    }),
+                    Err(e) => {
+                        ctx.record_failure(source.name(), &template.key, &e);
+                        None
+                    }
+                }
+            }
+        })
+        // The rate limiter narrows this further when the quota runs low
+        .buffer_unordered(MAX_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+    if !ctx.progress.is_json() {
+        println!(); // New line after progress
+    }
+    if ctx.verbose {
+        println!(
+            "Downloaded {} templates in {:.2?}",
+            total,
+            download_started.elapsed()
+        );
+    }
+
+    results.into_iter().flatten().collect()
+}
+
+/// Shows a failed template on its own line above the progress counter,
+/// as it happens; JSON progress reports it as an event instead
+fn show_failure(ctx: &SourceContext<'_>, source: &str, template: &str, error: &anyhow::Error) {
+    if ctx.progress.is_json() {
+        return;
+    }
+    let mut stdout = io::stdout();
+    let _ = stdout.queue(Print("\r"));
+    let _ = stdout.queue(Clear(ClearType::CurrentLine));
+    let _ = print_warning(
+        &mut stdout,
+        &format!("{}: {} failed: {:#}", source, template, error),
+    );
+}
+
+/// Reads listed templates one by one, for backends where every fetch is a
+/// cheap local file read
+pub async fn fetch_local<S: TemplateSource + ?Sized>(
+    source: &S,
+    ctx: &SourceContext<'_>,
+    templates: Vec<RemoteTemplate>,
+) -> Vec<FetchedTemplate> {
+    let total = templates.len();
+    let mut fetched = Vec::with_capacity(total);
+    for (i, template) in templates.into_iter().enumerate() {
+        match source.fetch(ctx, &template).await {
+            Ok(content) => {
+                ctx.progress.report(&ProgressEvent::TemplateDownloaded {
+                    source: source.name(),
+                    template: &template.key,
+                    current: i + 1,
+                    total,
+                });
+                fetched.push(FetchedTemplate {
+                    key: template.key,
+                    name: template.name,
+                    content,
+                });
+            }
+            Err(e) => {
+                ctx.progress.report(&ProgressEvent::TemplateFailed {
+                    source: source.name(),
+                    template: &template.key,
+                    error: format!("{:#}", e),
+                    current: i + 1,
+                    total,
+                });
+                show_failure(ctx, source.name(), &template.key, &e);
+                ctx.record_failure(source.name(), &template.key, &e);
+            }
+        }
+    }
+    println!(
+        "Found {} templates in source {}",
+        fetched.len(),
+        source.name()
+    );
+    fetched
+}
+
+type SourceFactory = fn(&SourceConfig) -> Result<Box<dyn TemplateSource>>;
+
+/// Maps source `type` names from the settings to their constructors
+pub struct SourceRegistry {
+    factories: BTreeMap<&'static str, SourceFactory>,
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            factories: BTreeMap::new(),
+        };
+        registry.register("github", GithubSource::from_config);
+        registry.register("local", LocalSource::from_config);
+        registry.register("git", GitSource::from_config);
+        registry.register("http", HttpSource::from_config);
+        registry
+    }
+}
+
+impl SourceRegistry {
+    pub fn register(&mut self, kind: &'static str, factory: SourceFactory) {
+        self.factories.insert(kind, factory);
+    }
+
+    pub fn create(&self, config: &SourceConfig) -> Result<Box<dyn TemplateSource>> {
+        // file:// URLs are read with the local backend
+        let local = config.as_local();
+        let config = local.as_ref().unwrap_or(config);
+        let kind = config.kind.type_name();
+        let factory = self
+            .factories
+            .get(kind)
+            .with_context(|| format!("no backend registered for source type '{}'", kind))?;
+        factory(config).with_context(|| format!("configuring source {}", config.name))
+    }
+}
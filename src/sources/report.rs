@@ -0,0 +1,104 @@
+//! Problems an update ran into without stopping, collected while sources
+//! download and shown once the update finishes
+
+use anyhow::Result;
+use serde::Serialize;
+use std::io::Write;
+
+use crate::progress::{ProgressEvent, ProgressFormat};
+use crate::ui::display::print_warning;
+
+/// A template a source listed but could not download
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateFailure {
+    pub source: String,
+    pub template: String,
+    pub error: String,
+}
+
+/// How one source fared during an update
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceSummary {
+    pub source: String,
+    /// Templates installed in the cache
+    pub templates: usize,
+    /// Templates listed but skipped because their download failed
+    pub failed: usize,
+}
+
+/// What went wrong during an update that still produced an index
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateReport {
+    /// Templates skipped because their download failed
+    pub failures: Vec<TemplateFailure>,
+    /// Other problems, such as a listing that may be incomplete
+    pub warnings: Vec<String>,
+    /// Per-source counts, in update order
+    pub sources: Vec<SourceSummary>,
+}
+
+impl UpdateReport {
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty() && self.warnings.is_empty()
+    }
+
+    /// Moves everything `other` recorded into this report
+    pub fn merge(&mut self, other: UpdateReport) {
+        self.failures.extend(other.failures);
+        self.warnings.extend(other.warnings);
+        self.sources.extend(other.sources);
+    }
+
+    /// Prints the report as warnings, after a table of per-source counts
+    /// when several sources were updated. Failed templates were shown as
+    /// they happened, so only their count is repeated. JSON progress gets
+    /// `warning` events, its other events having covered the rest.
+    pub fn render(&self, out: &mut impl Write, progress: ProgressFormat) -> Result<()> {
+        if progress.is_json() {
+            for message in &self.warnings {
+                progress.report(&ProgressEvent::Warning { message });
+            }
+            return Ok(());
+        }
+
+        if self.sources.len() > 1 {
+            self.render_sources(out)?;
+        }
+        for message in &self.warnings {
+            print_warning(out, message)?;
+        }
+        if !self.failures.is_empty() {
+            print_warning(
+                out,
+                &format!(
+                    "{} template(s) could not be downloaded; run `lignore update` again to retry them",
+                    self.failures.len()
+                ),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn render_sources(&self, out: &mut impl Write) -> Result<()> {
+        let width = self
+            .sources
+            .iter()
+            .map(|summary| summary.source.len())
+            .max()
+            .unwrap_or_default()
+            .max("Source".len());
+        writeln!(
+            out,
+            "{:<width$}  {:>9}  {:>6}",
+            "Source", "Templates", "Failed"
+        )?;
+        for summary in &self.sources {
+            writeln!(
+                out,
+                "{:<width$}  {:>9}  {:>6}",
+                summary.source, summary.templates, summary.failed
+            )?;
+        }
+        Ok(())
+    }
+}
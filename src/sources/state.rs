@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const STATE_FILE: &str = "sources.json";
+
+/// Outcome of the last successful update of one source
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct SyncRecord {
+    /// Unix timestamp of the last successful sync
+    pub last_sync: u64,
+    pub templates: usize,
+}
+
+/// Per-source sync history stored next to the template index
+#[derive(Deserialize, Serialize, Debug, Default)]
+pub struct SyncState {
+    pub sources: BTreeMap<String, SyncRecord>,
+}
+
+impl SyncState {
+    pub fn read(cache_dir: &Path) -> Self {
+        fs::read(cache_dir.join(STATE_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write(&self, cache_dir: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        fs::write(cache_dir.join(STATE_FILE), data)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, source: &str, templates: usize) {
+        self.sources.insert(
+            source.to_string(),
+            SyncRecord {
+                last_sync: now_secs(),
+                templates,
+            },
+        );
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+
+/// Tags that group related templates, used as `@tag` on the command line
+/// and in the picker filter
+const TAGS: &[(&str, &[&str])] = &[
+    (
+        "editor",
+        &[
+            "Eclipse",
+            "Emacs",
+            "JetBrains",
+            "Kate",
+            "KDevelop4",
+            "NetBeans",
+            "NotepadPP",
+            "SlickEdit",
+            "SublimeText",
+            "TextMate",
+            "Vim",
+            "VisualStudio",
+            "VisualStudioCode",
+            "Xcode",
+        ],
+    ),
+    ("os", &["Linux", "macOS", "Windows"]),
+    (
+        "language",
+        &[
+            "C", "C++", "Clojure", "D", "Dart", "Elixir", "Elm", "Erlang", "Fortran", "Go",
+            "Haskell", "Java", "Julia", "Kotlin", "Lua", "Nim", "Node", "OCaml", "Perl", "Python",
+            "R", "Ruby", "Rust", "Scala", "Swift", "Zig",
+        ],
+    ),
+    ("vcs", &["Bazaar", "Mercurial", "SVN", "TortoiseGit"]),
+];
+
+/// Names of every known tag
+pub fn tag_names() -> impl Iterator<Item = &'static str> {
+    TAGS.iter().map(|(tag, _)| *tag)
+}
+
+/// Tags a template belongs to, matched on its name without any namespace
+pub fn tags_for(name: &str) -> impl Iterator<Item = &'static str> + '_ {
+    let base = name.rsplit('/').next().unwrap_or(name);
+    TAGS.iter()
+        .filter(move |(_, members)| members.iter().any(|m| m.eq_ignore_ascii_case(base)))
+        .map(|(tag, _)| *tag)
+}
+
+/// Whether `name` is an operating system or editor template, whose files
+/// come from one developer's setup rather than the project
+pub fn is_personal(name: &str) -> bool {
+    tags_for(name).any(|tag| tag == "os" || tag == "editor")
+}
+
+/// Whether `name` carries a tag starting with `prefix`, so a partially
+/// typed `@ed` already narrows the picker to editors
+pub fn has_tag_prefix(name: &str, prefix: &str) -> bool {
+    let prefix = prefix.to_lowercase();
+    tags_for(name).any(|tag| tag.starts_with(&prefix))
+}
+
+/// Replaces `@tag` arguments with the available templates carrying that
+/// tag, keeping other arguments and the order they were given in
+pub fn expand_tags(args: &[String], available: &[String]) -> Result<Vec<String>> {
+    let mut expanded: Vec<String> = Vec::new();
+    for arg in args {
+        let Some(tag) = arg.strip_prefix('@') else {
+            if !expanded.contains(arg) {
+                expanded.push(arg.clone());
+            }
+            continue;
+        };
+
+        let tag = tag.to_lowercase();
+        if !tag_names().any(|known| known == tag) {
+            anyhow::bail!(
+                "Unknown tag '@{}'. Available tags: {}",
+                tag,
+                tag_names()
+                    .map(|t| format!("@{}", t))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        for name in available {
+            if tags_for(name).any(|t| t == tag) && !expanded.contains(name) {
+                expanded.push(name.clone());
+            }
+        }
+    }
+    Ok(expanded)
+}
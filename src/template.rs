@@ -1,38 +1,135 @@
-use anyhow::Result;
-use serde::Deserialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
 use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Deserialize, Debug)]
-pub struct RepoContent {
+use crate::lock::content_hash;
+use crate::metadata::TemplateNotes;
+use crate::settings::ConflictPolicy;
+use crate::tags::tags_for;
+
+/// A cached template and the source it was downloaded from
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TemplateEntry {
+    pub path: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source: String,
+    /// Path of the template inside its source, e.g. `Global/macOS`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub key: String,
+    /// Lower-priority sources that also provide this template name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadows: Vec<String>,
+    /// SHA-256 of the content written to the cache; empty in indexes
+    /// written before hashes were recorded
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+    #[serde(flatten)]
+    pub notes: TemplateNotes,
+    /// Set from the `trusted` setting, so a quarantined template can be
+    /// used; never stored in the index
+    #[serde(skip)]
+    pub trusted: bool,
+}
+
+impl TemplateEntry {
+    /// Whether the safe-mode scan held the template back and the user has
+    /// not trusted it
+    pub fn is_quarantined(&self) -> bool {
+        !self.notes.quarantine.is_empty() && !self.trusted
+    }
+
+    /// Reads the cached content, refusing files that were truncated or
+    /// modified since they were written
+    pub fn read_verified(&self, name: &str) -> Result<String> {
+        let content =
+            fs::read_to_string(&self.path).with_context(|| format!("reading template {}", name))?;
+        if !self.hash.is_empty() && content_hash(&content) != self.hash {
+            anyhow::bail!(
+                "Cached template {} does not match the hash recorded in the index; run `lignore update` to download it again",
+                name
+            );
+        }
+        Ok(content)
+    }
+
+    /// Whether the cached file exists and matches its recorded hash
+    pub fn is_intact(&self) -> bool {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => self.hash.is_empty() || content_hash(&content) == self.hash,
+            Err(_) => false,
+        }
+    }
+}
+
+/// Details of a cached template for `list --long` and `list --json`
+#[derive(Serialize, Debug)]
+pub struct TemplateInfo {
     pub name: String,
-    #[serde(rename = "type")]
-    pub content_type: String,
-    pub download_url: Option<String>,
+    pub key: String,
+    pub source: String,
+    /// Directory of the template in its source, such as `Global`; `None`
+    /// for top-level templates
+    pub category: Option<String>,
+    pub tags: Vec<&'static str>,
+    pub description: String,
+    /// Deprecation note; `None` when the template is current
+    pub deprecated: Option<String>,
+    /// Template to select instead of a deprecated one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
     pub path: String,
+    /// `None` when the cached file is missing
+    pub size: Option<u64>,
+    /// Git blob SHA-1 of the cached content, as in `git hash-object`
+    pub blob_sha: Option<String>,
+    pub shadows: Vec<String>,
+    /// Findings of the safe-mode scan for a quarantined template
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quarantine: Vec<String>,
 }
 
-#[derive(Deserialize, Debug)]
-pub struct RateLimitResponse {
-    pub resources: RateLimitResources,
+/// Index entries written before sources existed were bare paths
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Path(String),
+    Entry(TemplateEntry),
 }
 
-#[derive(Deserialize, Debug)]
-pub struct RateLimitResources {
-    pub core: RateLimit,
+impl From<StoredEntry> for TemplateEntry {
+    fn from(stored: StoredEntry) -> Self {
+        match stored {
+            StoredEntry::Path(path) => TemplateEntry {
+                path,
+                ..Default::default()
+            },
+            StoredEntry::Entry(entry) => entry,
+        }
+    }
 }
 
-#[derive(Deserialize, Debug)]
-pub struct RateLimit {
-    pub limit: u32,
-    pub remaining: u32,
-    pub reset: u64,
+/// A template written to the cache during an update
+#[derive(Debug, Clone)]
+pub struct CachedTemplate {
+    pub name: String,
+    pub key: String,
+    pub path: PathBuf,
+    pub hash: String,
+    pub notes: TemplateNotes,
+}
+
+/// Templates produced by one source during an update
+pub struct SourceTemplates {
+    pub source: String,
+    pub templates: Vec<CachedTemplate>,
 }
 
 #[derive(Debug, Default)]
 pub struct TemplateIndex {
-    pub templates: BTreeMap<String, String>,
+    pub templates: BTreeMap<String, TemplateEntry>,
 }
 
 impl TemplateIndex {
@@ -42,26 +139,261 @@ impl TemplateIndex {
         }
     }
 
-    pub fn insert(&mut self, name: String, path: String) {
-        self.templates.insert(name, path);
+    /// Merges per-source results, given in priority order, into one index
+    pub fn from_sources(sources: Vec<SourceTemplates>, policy: ConflictPolicy) -> Result<Self> {
+        let mut providers: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for source in &sources {
+            for template in &source.templates {
+                let entry = providers.entry(template.name.as_str()).or_default();
+                if !entry.contains(&source.source.as_str()) {
+                    entry.push(source.source.as_str());
+                }
+            }
+        }
+        let conflicts: BTreeMap<&str, Vec<&str>> = providers
+            .into_iter()
+            .filter(|(_, sources)| sources.len() > 1)
+            .collect();
+
+        if policy == ConflictPolicy::Error && !conflicts.is_empty() {
+            let mut error_msg = String::from("Multiple sources provide the same templates:\n");
+            for (name, sources) in &conflicts {
+                error_msg.push_str(&format!("  - {} ({})\n", name, sources.join(", ")));
+            }
+            error_msg.push_str(
+                "\nReorder the sources or set \"conflict_policy\" to \"first-wins\" or \"namespace-all\".",
+            );
+            anyhow::bail!(error_msg);
+        }
+
+        let mut index = TemplateIndex::new();
+        for source in &sources {
+            for template in &source.templates {
+                let name = &template.name;
+                let contested = conflicts.get(name.as_str());
+                let key = match (policy, contested) {
+                    (ConflictPolicy::NamespaceAll, Some(_)) => {
+                        format!("{}/{}", source.source, name)
+                    }
+                    _ => name.clone(),
+                };
+                if index.templates.contains_key(&key) && contested.is_some() {
+                    // A higher-priority source already won this name
+                    continue;
+                }
+                let shadows = match (policy, contested) {
+                    (ConflictPolicy::FirstWins, Some(sources)) => sources
+                        .iter()
+                        .filter(|other| **other != source.source)
+                        .map(|other| other.to_string())
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                index.templates.insert(
+                    key,
+                    TemplateEntry {
+                        path: template.path.to_string_lossy().to_string(),
+                        source: source.source.clone(),
+                        key: template.key.clone(),
+                        shadows,
+                        hash: template.hash.clone(),
+                        notes: template.notes.clone(),
+                        trusted: false,
+                    },
+                );
+            }
+        }
+        Ok(index)
+    }
+
+    /// Splits the index back into per-source template lists
+    pub fn into_sources(self) -> Vec<SourceTemplates> {
+        let mut grouped: BTreeMap<String, Vec<CachedTemplate>> = BTreeMap::new();
+        for (name, entry) in self.templates {
+            let prefix = format!("{}/", entry.source);
+            let name = name.strip_prefix(&prefix).unwrap_or(&name).to_string();
+            let key = if entry.key.is_empty() {
+                name.clone()
+            } else {
+                entry.key
+            };
+            grouped
+                .entry(entry.source)
+                .or_default()
+                .push(CachedTemplate {
+                    name,
+                    key,
+                    path: PathBuf::from(entry.path),
+                    hash: entry.hash,
+                    notes: entry.notes,
+                });
+        }
+        grouped
+            .into_iter()
+            .map(|(source, templates)| SourceTemplates { source, templates })
+            .collect()
     }
 
     pub fn list(&self) -> Vec<String> {
         self.templates.keys().cloned().collect()
     }
 
-    pub fn get(&self, name: &str) -> Option<&String> {
+    pub fn get(&self, name: &str) -> Option<&TemplateEntry> {
         self.templates.get(name)
     }
 
-    pub fn write(&self, cache_dir: &PathBuf) -> Result<()> {
+    /// Template the index offers in place of deprecated `name`, matched
+    /// regardless of case; `None` when the note names no template it has
+    pub fn replacement(&self, name: &str) -> Option<&str> {
+        let wanted = &self.get(name)?.notes.replacement;
+        if wanted.is_empty() {
+            return None;
+        }
+        self.templates
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case(wanted) && *key != name)
+            .map(String::as_str)
+    }
+
+    /// Warning for a selection of `name` when upstream deprecated it,
+    /// suggesting the replacement when there is one
+    pub fn deprecation_notice(&self, name: &str) -> Option<String> {
+        let note = &self.get(name)?.notes.deprecated;
+        if note.is_empty() {
+            return None;
+        }
+        Some(match self.replacement(name) {
+            Some(replacement) => format!(
+                "{} is deprecated ({}); select {} instead",
+                name, note, replacement
+            ),
+            None => format!("{} is deprecated ({})", name, note),
+        })
+    }
+
+    /// Reads the cached file of `name` to describe it
+    pub fn info(&self, name: &str) -> Option<TemplateInfo> {
+        let entry = self.get(name)?;
+        let key = if entry.key.is_empty() {
+            name.to_string()
+        } else {
+            entry.key.clone()
+        };
+        let category = key.rsplit_once('/').map(|(dir, _)| dir.to_string());
+        let content = fs::read(&entry.path).ok();
+        Some(TemplateInfo {
+            name: name.to_string(),
+            key,
+            source: entry.source.clone(),
+            category,
+            tags: tags_for(name).collect(),
+            description: entry.notes.description.clone(),
+            deprecated: (!entry.notes.deprecated.is_empty())
+                .then(|| entry.notes.deprecated.clone()),
+            replacement: self.replacement(name).map(String::from),
+            path: entry.path.clone(),
+            size: content.as_ref().map(|content| content.len() as u64),
+            blob_sha: content.as_deref().map(blob_sha),
+            shadows: entry.shadows.clone(),
+            quarantine: if entry.is_quarantined() {
+                entry.notes.quarantine.clone()
+            } else {
+                Vec::new()
+            },
+        })
+    }
+
+    /// Why each quarantined template was held back, for the update report
+    pub fn quarantine_warnings(&self) -> Vec<String> {
+        self.templates
+            .iter()
+            .filter(|(_, entry)| entry.is_quarantined())
+            .map(|(name, entry)| {
+                format!(
+                    "Quarantined {} from source {}: {}",
+                    name,
+                    entry.source,
+                    entry.notes.quarantine.join("; ")
+                )
+            })
+            .collect()
+    }
+
+    /// Releases quarantined templates matching an entry of `trusted`, which
+    /// names templates, paths or directories as `hidden` does
+    pub fn trust(&mut self, trusted: &[String]) {
+        for (name, entry) in &mut self.templates {
+            let key = if entry.key.is_empty() {
+                name
+            } else {
+                &entry.key
+            };
+            entry.trusted = trusted
+                .iter()
+                .any(|pattern| hides(pattern, name) || hides(pattern, key));
+        }
+    }
+
+    /// Drops templates matching an entry of `hidden`: a template name, a
+    /// path in the source such as `Global/Xcode`, or a directory such as
+    /// `community/`, ignoring case
+    pub fn hide(&mut self, hidden: &[String]) {
+        if hidden.is_empty() {
+            return;
+        }
+        self.templates.retain(|name, entry| {
+            let key = if entry.key.is_empty() {
+                name
+            } else {
+                &entry.key
+            };
+            !hidden
+                .iter()
+                .any(|pattern| hides(pattern, name) || hides(pattern, key))
+        });
+    }
+
+    /// Finds a template by name or by its path in the source, ignoring
+    /// case, so `macos` and `global/macos` both resolve to `macOS`
+    pub fn resolve(&self, query: &str) -> Option<&str> {
+        if let Some((name, _)) = self.templates.get_key_value(query) {
+            return Some(name);
+        }
+        self.templates
+            .iter()
+            .find(|(name, entry)| {
+                name.eq_ignore_ascii_case(query) || entry.key.eq_ignore_ascii_case(query)
+            })
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Paths inside the cache are stored relative to it, so a cache can be
+    /// moved or vendored into a project
+    pub fn write(&self, cache_dir: &Path) -> Result<()> {
         let index_path = cache_dir.join("index.json");
-        let data = serde_json::to_vec_pretty(&self.templates)?;
+        let templates: BTreeMap<&String, TemplateEntry> = self
+            .templates
+            .iter()
+            .map(|(name, entry)| {
+                let path = Path::new(&entry.path)
+                    .strip_prefix(cache_dir)
+                    .map(|relative| relative.to_string_lossy().to_string())
+                    .unwrap_or_else(|_| entry.path.clone());
+                (
+                    name,
+                    TemplateEntry {
+                        path,
+                        ..entry.clone()
+                    },
+                )
+            })
+            .collect();
+        let data = serde_json::to_vec_pretty(&templates)?;
         fs::write(index_path, data)?;
         Ok(())
     }
 
-    pub fn read(cache_dir: &PathBuf) -> Result<Self> {
+    pub fn read(cache_dir: &Path) -> Result<Self> {
         let index_path = cache_dir.join("index.json");
         if !index_path.exists() {
             anyhow::bail!(
@@ -70,7 +402,41 @@ impl TemplateIndex {
             );
         }
         let data = fs::read(index_path)?;
-        let templates: BTreeMap<String, String> = serde_json::from_slice(&data)?;
+        let stored: BTreeMap<String, StoredEntry> = serde_json::from_slice(&data)?;
+        let templates = stored
+            .into_iter()
+            .map(|(name, entry)| {
+                let mut entry: TemplateEntry = entry.into();
+                // Older indexes stored full paths, which join() keeps as-is
+                entry.path = cache_dir.join(&entry.path).to_string_lossy().to_string();
+                (name, entry)
+            })
+            .collect();
         Ok(TemplateIndex { templates })
     }
 }
+
+/// Whether `pattern` names `path` or a directory containing it
+fn hides(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    if pattern.is_empty() {
+        return false;
+    }
+    let in_directory = path
+        .get(..pattern.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(pattern))
+        && path[pattern.len()..].starts_with('/');
+    in_directory || path.eq_ignore_ascii_case(pattern)
+}
+
+/// Hex-encoded git blob SHA-1 of `content`
+pub fn blob_sha(content: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Tracked files that the rules in `ignore_file` would ignore. git keeps
+/// tracking such files, so the new rules silently have no effect on them.
+/// Returns `None` when the file is not inside a git work tree.
+pub fn tracked_but_ignored(ignore_file: &Path) -> Result<Option<Vec<PathBuf>>> {
+    let dir = match ignore_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let inside = Command::new("git")
+        .args(["rev-parse", "--is-inside-work-tree"])
+        .current_dir(dir)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !inside {
+        return Ok(None);
+    }
+
+    let rules = ignore_file
+        .canonicalize()
+        .with_context(|| format!("resolving {}", ignore_file.display()))?;
+    let output = Command::new("git")
+        .args(["ls-files", "-z", "--cached", "--ignored", "--exclude-from"])
+        .arg(&rules)
+        .current_dir(dir)
+        .output()
+        .context("running git ls-files")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git ls-files failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    // Paths are relative to `dir`; report them relative to the current
+    // directory so the suggested commands can be pasted as-is
+    let files = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(|path| {
+            if dir == Path::new(".") {
+                PathBuf::from(path)
+            } else {
+                dir.join(path)
+            }
+        })
+        .collect();
+    Ok(Some(files))
+}
+
+/// `git rm --cached` command that stops tracking `path`, quoted for a POSIX shell
+pub fn untrack_command(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let plain = path
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "._-/+@".contains(c));
+    if plain {
+        format!("git rm --cached -- {}", path)
+    } else {
+        format!("git rm --cached -- '{}'", path.replace('\'', r"'\''"))
+    }
+}
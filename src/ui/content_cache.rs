@@ -0,0 +1,69 @@
+//! Template contents the picker has already read, kept in memory so moving
+//! back and forth over the same templates does not read them from disk again
+
+use anyhow::Result;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+/// Most templates kept at once
+const MAX_ENTRIES: usize = 64;
+
+/// Most bytes of content kept at once; a larger template is read every
+/// time instead of evicting everything else
+const MAX_BYTES: usize = 4 * 1024 * 1024;
+
+/// A least-recently-used cache of template contents, filled lazily
+#[derive(Debug, Default)]
+pub struct ContentCache {
+    entries: HashMap<String, Rc<str>>,
+    /// Names from least to most recently used
+    order: VecDeque<String>,
+    bytes: usize,
+}
+
+impl ContentCache {
+    /// Returns the content of `name`, reading it with `load` when it is not
+    /// cached. Failed reads are not cached, so they are retried next time.
+    pub fn get_or_load(
+        &mut self,
+        name: &str,
+        load: impl FnOnce(&str) -> Result<String>,
+    ) -> Result<Rc<str>> {
+        if let Some(content) = self.entries.get(name) {
+            let content = Rc::clone(content);
+            self.touch(name);
+            return Ok(content);
+        }
+
+        let content: Rc<str> = load(name)?.into();
+        if content.len() <= MAX_BYTES {
+            while self.entries.len() >= MAX_ENTRIES || self.bytes + content.len() > MAX_BYTES {
+                if !self.evict_oldest() {
+                    break;
+                }
+            }
+            self.bytes += content.len();
+            self.entries.insert(name.to_string(), Rc::clone(&content));
+            self.order.push_back(name.to_string());
+        }
+        Ok(content)
+    }
+
+    /// Marks `name` as the most recently used
+    fn touch(&mut self, name: &str) {
+        if let Some(position) = self.order.iter().position(|entry| entry == name) {
+            let entry = self.order.remove(position).unwrap_or_default();
+            self.order.push_back(entry);
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        let Some(name) = self.order.pop_front() else {
+            return false;
+        };
+        if let Some(content) = self.entries.remove(&name) {
+            self.bytes -= content.len();
+        }
+        true
+    }
+}
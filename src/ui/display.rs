@@ -1,12 +1,31 @@
-use crate::ui::theme::get_theme;
-use anyhow::Result;
+use crate::template::{TemplateIndex, TemplateInfo};
+use crate::ui::theme::{Palette, Theme, ThemeKind, capabilities, get_theme};
+use anyhow::{Context, Result};
 use crossterm::{
     QueueableCommand,
-    style::{Attribute, Print, ResetColor, SetAttribute, SetForegroundColor},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
 };
 use std::cmp::max;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::Path;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Starts writing in `color`, unless colors are off: then output to pipes,
+/// `NO_COLOR` and `CLICOLOR=0` stays free of escape sequences
+fn set_color(out: &mut impl Write, color: Color) -> io::Result<()> {
+    if capabilities().color {
+        out.queue(SetForegroundColor(color))?;
+    }
+    Ok(())
+}
+
+/// Counterpart of [`set_color`]
+fn reset_color(out: &mut impl Write) -> io::Result<()> {
+    if capabilities().color {
+        out.queue(ResetColor)?;
+    }
+    Ok(())
+}
 
 pub struct ColumnLayout {
     pub columns: usize,
@@ -18,9 +37,9 @@ pub fn calculate_column_layout(items: &[String]) -> Result<ColumnLayout> {
     use crossterm::terminal;
 
     let term_width = terminal::size().map(|(w, _)| w as usize).unwrap_or(80);
-    let column_width = items.iter().map(|item| item.len()).max().unwrap_or(0) + 2;
+    let column_width = items.iter().map(|item| item.width()).max().unwrap_or(0) + 2;
     let columns = max(1, term_width / column_width.max(1));
-    let rows = (items.len() + columns - 1) / columns;
+    let rows = items.len().div_ceil(columns);
 
     Ok(ColumnLayout {
         columns,
@@ -29,9 +48,38 @@ pub fn calculate_column_layout(items: &[String]) -> Result<ColumnLayout> {
     })
 }
 
-pub fn print_columnar_list(items: &[String], layout: &ColumnLayout) -> Result<()> {
-    let mut stdout = io::stdout();
+/// Pads `text` with spaces, or cuts it short with an ellipsis, so it fills
+/// exactly `width` terminal cells. Wide characters such as CJK and emoji
+/// count as two cells and are never split.
+pub fn fit_width(text: &str, width: usize) -> String {
+    let text_width = text.width();
+    if text_width <= width {
+        return format!("{}{}", text, " ".repeat(width - text_width));
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut fitted = String::new();
+    let mut used = 0;
+    for ch in text.chars() {
+        let ch_width = ch.width().unwrap_or(0);
+        if used + ch_width > width - 1 {
+            break;
+        }
+        fitted.push(ch);
+        used += ch_width;
+    }
+    fitted.push('…');
+    fitted.push_str(&" ".repeat(width - 1 - used));
+    fitted
+}
 
+pub fn print_columnar_list(
+    out: &mut impl Write,
+    items: &[String],
+    layout: &ColumnLayout,
+) -> Result<()> {
     for row in 0..layout.rows {
         for col in 0..layout.columns {
             let idx = row * layout.columns + col;
@@ -41,17 +89,16 @@ pub fn print_columnar_list(items: &[String], layout: &ColumnLayout) -> Result<()
 
             // Alternate subtle contrast for readability in light and dark themes
             let theme = get_theme();
-            let color = if idx % 2 == 0 {
+            let color = if idx.is_multiple_of(2) {
                 theme.list_alt1
             } else {
                 theme.list_alt2
             };
 
-            let item_text = format!("{:<width$}", items[idx], width = layout.column_width);
-            if let Err(err) = stdout
-                .queue(SetForegroundColor(color))
-                .and_then(|s| s.queue(Print(item_text)))
-                .and_then(|s| s.queue(ResetColor))
+            let item_text = fit_width(&items[idx], layout.column_width);
+            if let Err(err) = set_color(out, color)
+                .and_then(|_| write!(out, "{}", item_text))
+                .and_then(|_| reset_color(out))
             {
                 if err.kind() == io::ErrorKind::BrokenPipe {
                     return Ok(());
@@ -60,7 +107,7 @@ pub fn print_columnar_list(items: &[String], layout: &ColumnLayout) -> Result<()
             }
         }
 
-        if let Err(err) = writeln!(stdout) {
+        if let Err(err) = writeln!(out) {
             if err.kind() == io::ErrorKind::BrokenPipe {
                 return Ok(());
             }
@@ -71,20 +118,246 @@ pub fn print_columnar_list(items: &[String], layout: &ColumnLayout) -> Result<()
     Ok(())
 }
 
-pub fn print_success(message: &str) -> Result<()> {
-    let mut stdout = io::stdout();
+/// Prints one template per line with the source that won its name, its
+/// category, size, blob SHA, cached path and description
+pub fn print_long_list(
+    out: &mut impl Write,
+    index: &TemplateIndex,
+    names: &[String],
+) -> Result<()> {
     let theme = get_theme();
-    stdout.queue(SetForegroundColor(theme.success))?;
-    stdout.queue(SetAttribute(Attribute::Bold))?;
-    stdout.queue(Print("✓ "))?;
-    stdout.queue(SetAttribute(Attribute::Reset))?;
-    stdout.queue(SetForegroundColor(theme.success))?;
-    stdout.queue(Print(message))?;
-    stdout.queue(ResetColor)?;
-    writeln!(stdout)?;
+    let infos: Vec<TemplateInfo> = names.iter().filter_map(|name| index.info(name)).collect();
+    let width =
+        |column: fn(&TemplateInfo) -> usize| infos.iter().map(column).max().unwrap_or(0) + 2;
+    let name_width = width(|info| info.name.len());
+    let source_width = width(|info| info.source.len().max(1));
+    let category_width = width(|info| info.category.as_ref().map_or(1, String::len));
+
+    for info in &infos {
+        let or_dash = |value: &str| {
+            if value.is_empty() {
+                "-".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        let size = info
+            .size
+            .map(|size| size.to_string())
+            .unwrap_or_else(|| "missing".to_string());
+        let sha = info.blob_sha.as_deref().map(|sha| &sha[..7]).unwrap_or("-");
+        let mut line = format!(
+            "{:<name_width$}{:<source_width$}{:<category_width$}{:>8}  {}  {}  ",
+            info.name,
+            or_dash(&info.source),
+            or_dash(info.category.as_deref().unwrap_or_default()),
+            size,
+            sha,
+            info.path,
+            name_width = name_width,
+            source_width = source_width,
+            category_width = category_width
+        );
+        if !info.shadows.is_empty() {
+            line.push_str(&format!("(overrides {})  ", info.shadows.join(", ")));
+        }
+        line.push_str(&info.description);
+        if let Some(note) = &info.deprecated {
+            line.push_str(&format!(" [deprecated: {}]", note));
+        }
+        if !info.quarantine.is_empty() {
+            line.push_str(" [quarantined]");
+        }
+
+        let result = set_color(out, theme.list_alt1)
+            .and_then(|_| write!(out, "{}", line.trim_end()))
+            .and_then(|_| reset_color(out))
+            .and_then(|_| writeln!(out));
+        if let Err(err) = result {
+            if err.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(err.into());
+        }
+    }
+
     Ok(())
 }
 
-pub fn print_success_message(output: &PathBuf) -> Result<()> {
-    print_success(&format!("Generated {}", output.display()))
+/// Prints the details of each template as a JSON array
+pub fn print_json_list(
+    out: &mut impl Write,
+    index: &TemplateIndex,
+    names: &[String],
+) -> Result<()> {
+    let infos: Vec<TemplateInfo> = names.iter().filter_map(|name| index.info(name)).collect();
+    let result = serde_json::to_writer_pretty(&mut *out, &infos)
+        .map_err(io::Error::from)
+        .and_then(|_| writeln!(out));
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => Ok(other?),
+    }
+}
+
+pub fn print_success(out: &mut impl Write, message: &str) -> Result<()> {
+    print_status(out, get_theme().success, "✓", message)
+}
+
+pub fn print_warning(out: &mut impl Write, message: &str) -> Result<()> {
+    print_status(out, get_theme().warning, "!", message)
+}
+
+/// A message in `color` after a bold `mark`
+fn print_status(out: &mut impl Write, color: Color, mark: &str, message: &str) -> Result<()> {
+    if !capabilities().color {
+        writeln!(out, "{} {}", mark, message)?;
+        return Ok(());
+    }
+    out.queue(SetForegroundColor(color))?;
+    out.queue(SetAttribute(Attribute::Bold))?;
+    out.queue(Print(mark))?;
+    out.queue(SetAttribute(Attribute::Reset))?;
+    out.queue(SetForegroundColor(color))?;
+    out.queue(Print(format!(" {}", message)))?;
+    out.queue(ResetColor)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Prints a sample of every element `theme` colors: the picker's header,
+/// items and footer, a `list` row, and the messages commands print
+pub fn print_theme_preview(
+    out: &mut impl Write,
+    theme: &Theme,
+    kind: ThemeKind,
+    palette: Palette,
+) -> Result<()> {
+    let kind = match kind {
+        ThemeKind::Light => "light",
+        ThemeKind::Dark => "dark",
+    };
+    let palette = match palette {
+        Palette::Auto | Palette::Truecolor => "truecolor",
+        Palette::Ansi16 => "16-color",
+        Palette::Ansi8 => "8-color",
+    };
+    let mut buffer = Vec::new();
+    writeln!(buffer, "Theme: {} background, {} palette", kind, palette)?;
+    writeln!(buffer)?;
+
+    buffer.queue(SetForegroundColor(theme.header_title))?;
+    buffer.queue(SetAttribute(Attribute::Bold))?;
+    buffer.queue(Print("Select templates  "))?;
+    buffer.queue(SetAttribute(Attribute::Reset))?;
+    buffer.queue(SetForegroundColor(theme.header_hint))?;
+    buffer.queue(Print("Space=toggle  Enter=confirm  Esc=cancel\n"))?;
+    buffer.queue(Print("Filter: showing all templates\n"))?;
+    let items = [
+        ("Rust", true),
+        ("Node", false),
+        ("Python", false),
+        ("Go", true),
+    ];
+    for (idx, (name, selected)) in items.into_iter().enumerate() {
+        if idx == 0 {
+            buffer.queue(SetAttribute(Attribute::Reverse))?;
+        }
+        let (checkbox, checkbox_color, name_color) = if selected {
+            ("[x]", theme.checkbox_selected, theme.item_selected_text)
+        } else {
+            ("[ ]", theme.checkbox_unselected, theme.item_unselected_text)
+        };
+        buffer.queue(SetForegroundColor(checkbox_color))?;
+        buffer.queue(Print(checkbox))?;
+        buffer.queue(SetAttribute(Attribute::Reset))?;
+        buffer.queue(SetForegroundColor(name_color))?;
+        buffer.queue(Print(format!(" {}", fit_width(name, 10))))?;
+    }
+    buffer.queue(Print("\n"))?;
+    buffer.queue(SetForegroundColor(theme.header_hint))?;
+    buffer.queue(Print("Goes well with Rust: Linux · Ctrl+X hides\n"))?;
+    buffer.queue(SetForegroundColor(theme.footer))?;
+    buffer.queue(Print(
+        "Selected 2/4 · Showing 4/4 · Page 1/1 · Sort: name\n",
+    ))?;
+    buffer.queue(ResetColor)?;
+    writeln!(buffer)?;
+
+    for (idx, name) in ["Rust", "Node", "Python", "Go"].into_iter().enumerate() {
+        let color = if idx.is_multiple_of(2) {
+            theme.list_alt1
+        } else {
+            theme.list_alt2
+        };
+        buffer.queue(SetForegroundColor(color))?;
+        buffer.queue(Print(fit_width(name, 10)))?;
+    }
+    buffer.queue(ResetColor)?;
+    writeln!(buffer)?;
+    writeln!(buffer)?;
+
+    buffer.queue(SetForegroundColor(theme.header_title))?;
+    buffer.queue(Print("Rate Limit Information:\n"))?;
+    buffer.queue(SetForegroundColor(theme.accent))?;
+    buffer.queue(Print("  Remaining: 42\n"))?;
+    for (color, mark, message) in [
+        (theme.success, "✓ ", "Generated .gitignore"),
+        (theme.warning, "! ", "Template 'Foo' not found"),
+    ] {
+        buffer.queue(SetForegroundColor(color))?;
+        buffer.queue(SetAttribute(Attribute::Bold))?;
+        buffer.queue(Print(mark))?;
+        buffer.queue(SetAttribute(Attribute::Reset))?;
+        buffer.queue(SetForegroundColor(color))?;
+        buffer.queue(Print(message))?;
+        buffer.queue(ResetColor)?;
+        writeln!(buffer)?;
+    }
+
+    match out.write_all(&buffer) {
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => Ok(other?),
+    }
+}
+
+/// Asks a yes/no question on stdin; an empty answer means yes
+pub fn confirm(prompt: &str) -> Result<bool> {
+    let normalized = ask(prompt)?;
+    Ok(normalized.is_empty() || normalized == "y" || normalized == "yes")
+}
+
+/// Prints `prompt` and reads one answer from stdin, trimmed and lowercased
+pub fn ask(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    io::stdout().flush().context("flushing prompt")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("reading confirmation input")?;
+    Ok(answer.trim().to_lowercase())
+}
+
+/// Formats a duration in seconds as a short relative age like "3h ago"
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", secs / 60),
+        3600..86400 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+/// Formats a byte count as a short size like "250 KB"
+pub fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{} KB", bytes / 1024),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+pub fn print_success_message(out: &mut impl Write, output: &Path) -> Result<()> {
+    print_success(out, &format!("Generated {}", output.display()))
 }
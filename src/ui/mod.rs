@@ -1,7 +1,12 @@
+pub mod content_cache;
 pub mod display;
+pub mod output;
 pub mod selection;
 pub mod theme;
 
-pub use display::{calculate_column_layout, print_columnar_list, print_success};
-pub use selection::select_templates;
+pub use display::{
+    calculate_column_layout, print_columnar_list, print_json_list, print_long_list, print_success,
+};
+pub use output::Output;
+pub use selection::{PickerOptions, PickerTab, select_tabs, select_templates};
 pub use theme::configure_theme;
@@ -0,0 +1,82 @@
+//! Where [`App`](crate::app::App) prints its messages: the terminal, or a
+//! buffer that tests and library callers read back
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+enum Sink {
+    Stdout,
+    Buffer(Vec<u8>),
+    Writer(Box<dyn Write + Send>),
+}
+
+/// A shared handle to the output; clones write to the same place
+#[derive(Clone)]
+pub struct Output {
+    sink: Arc<Mutex<Sink>>,
+}
+
+impl Output {
+    pub fn stdout() -> Self {
+        Self::with_sink(Sink::Stdout)
+    }
+
+    /// Collects everything written, for [`Output::take_buffer`]
+    pub fn buffer() -> Self {
+        Self::with_sink(Sink::Buffer(Vec::new()))
+    }
+
+    pub fn writer(writer: impl Write + Send + 'static) -> Self {
+        Self::with_sink(Sink::Writer(Box::new(writer)))
+    }
+
+    fn with_sink(sink: Sink) -> Self {
+        Self {
+            sink: Arc::new(Mutex::new(sink)),
+        }
+    }
+
+    /// Returns what a buffer collected so far and empties it; `None` for
+    /// other outputs. Colors are kept as escape sequences.
+    pub fn take_buffer(&self) -> Option<String> {
+        match &mut *self.sink.lock().unwrap() {
+            Sink::Buffer(buffer) => Some(String::from_utf8_lossy(&std::mem::take(buffer)).into()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::stdout()
+    }
+}
+
+impl std::fmt::Debug for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match &*self.sink.lock().unwrap() {
+            Sink::Stdout => "stdout",
+            Sink::Buffer(_) => "buffer",
+            Sink::Writer(_) => "writer",
+        };
+        f.debug_tuple("Output").field(&kind).finish()
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut *self.sink.lock().unwrap() {
+            Sink::Stdout => io::stdout().write(buf),
+            Sink::Buffer(buffer) => buffer.write(buf),
+            Sink::Writer(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut *self.sink.lock().unwrap() {
+            Sink::Stdout => io::stdout().flush(),
+            Sink::Buffer(_) => Ok(()),
+            Sink::Writer(writer) => writer.flush(),
+        }
+    }
+}
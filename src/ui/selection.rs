@@ -1,3 +1,10 @@
+use crate::clipboard::copy_text;
+use crate::companions::suggest;
+use crate::session::PickerSession;
+use crate::settings::SortOrder;
+use crate::tags::has_tag_prefix;
+use crate::ui::content_cache::ContentCache;
+use crate::ui::display::fit_width;
 use crate::ui::theme::get_theme;
 use anyhow::Result;
 use crossterm::{
@@ -11,79 +18,371 @@ use crossterm::{
 use std::cmp::{max, min};
 use std::collections::BTreeSet;
 use std::io::{Stdout, Write, stdout};
+use std::rc::Rc;
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
+
+/// How long to wait for further events before drawing the next frame
+const COALESCE_WINDOW: Duration = Duration::from_millis(4);
+
+/// How the picker orders and presents its options
+pub struct PickerOptions<'a> {
+    /// Popularity rank of each option (0 = most popular), aligned with the
+    /// options; empty when unknown
+    pub popularity: Vec<usize>,
+    /// Recency rank of each option (0 = used most recently), aligned with
+    /// the options; empty when usage statistics are off
+    pub recent: Vec<usize>,
+    /// Description of each option shown in the footer, aligned with the
+    /// options; empty when unknown
+    pub descriptions: Vec<String>,
+    pub sort: SortOrder,
+    /// Reads a template's content, used to copy it to the clipboard; the
+    /// picker caches what it returns
+    pub load_content: &'a dyn Fn(&str) -> Result<String>,
+    /// Open the picker only to look around: Enter and Space preview the
+    /// highlighted template instead of selecting or confirming
+    pub browse: bool,
+}
+
+/// A file the picker edits a selection for, such as .gitignore or the
+/// global excludes file
+pub struct PickerTab {
+    pub label: String,
+    /// Templates checked when the picker opens
+    pub selection: Vec<String>,
+}
 
+/// Runs the picker, starting from `session` and leaving the final filter,
+/// sort and cursor in it
 pub fn select_templates(
     options: &[String],
     previous_selection: &[String],
+    picker: &PickerOptions<'_>,
+    session: &mut PickerSession,
 ) -> Result<Option<Vec<String>>> {
+    let tab = PickerTab {
+        label: String::new(),
+        selection: previous_selection.to_vec(),
+    };
+    let picked = select_tabs(options, vec![tab], picker, session)?;
+    Ok(picked.map(|mut selections| selections.remove(0)))
+}
+
+/// Runs the picker over several files at once: Tab switches between them,
+/// each keeping its own selection. Returns the selections in the order of
+/// `tabs`, or `None` when cancelled.
+pub fn select_tabs(
+    options: &[String],
+    tabs: Vec<PickerTab>,
+    picker: &PickerOptions<'_>,
+    session: &mut PickerSession,
+) -> Result<Option<Vec<Vec<String>>>> {
     if options.is_empty() {
-        return Ok(Some(Vec::new()));
+        return Ok(Some(vec![Vec::new(); tabs.len()]));
+    }
+
+    // The picker draws each frame into buffers of escape sequences, which
+    // consoles older than Windows 10 cannot interpret
+    #[cfg(windows)]
+    if !crossterm::ansi_support::supports_ansi() {
+        anyhow::bail!(
+            "This console does not support ANSI escape sequences, which the picker needs.\n\
+             Use Windows Terminal or a Windows 10+ console, or pass templates with `lignore generate --from`."
+        );
     }
 
     let mut guard = TerminalGuard::enter()?;
     let mut state = SelectionState::new(options.to_vec());
-
-    for (idx, item) in options.iter().enumerate() {
-        if previous_selection.contains(item) {
-            state.select_item(idx);
+    if picker.popularity.len() == options.len() {
+        state.set_popularity(picker.popularity.clone());
+    }
+    if picker.recent.len() == options.len() {
+        state.set_recent(picker.recent.clone());
+    }
+    if picker.descriptions.len() == options.len() {
+        state.set_descriptions(picker.descriptions.clone());
+    }
+    state.set_sort(picker.sort);
+    state.set_browse(picker.browse);
+    state.restore(session);
+
+    state.set_tabs(tabs.iter().map(|tab| tab.label.clone()).collect());
+    for (number, tab) in tabs.iter().enumerate().rev() {
+        state.switch_tab(number);
+        for (idx, item) in options.iter().enumerate() {
+            if tab.selection.contains(item) {
+                state.select_item(idx);
+            }
         }
     }
 
-    let result = loop {
+    let result = 'picker: loop {
         state.render(guard.stdout_mut())?;
         guard.stdout_mut().flush()?;
 
-        match event::read()? {
-            Event::Key(key) if key.kind != KeyEventKind::Release => {
-                if state.handle_search_key(&key) {
-                    continue;
+        let mut event = event::read()?;
+        loop {
+            match handle_event(&mut state, event, picker) {
+                Step::Continue => {}
+                Step::Cancel => {
+                    *session = state.session();
+                    break 'picker None;
+                }
+                Step::Confirm => {
+                    *session = state.session();
+                    break 'picker Some(state.finish_tabs());
                 }
+            }
+            // Apply keys that arrive in a burst, like a paste or key repeat
+            // over SSH, before drawing the next frame
+            if !event::poll(COALESCE_WINDOW)? {
+                break;
+            }
+            event = event::read()?;
+        }
+    };
 
-                match key.code {
-                    KeyCode::Esc | KeyCode::Char('q') => {
-                        break Ok(None);
-                    }
-                    KeyCode::Enter => break Ok(Some(state.finish())),
-                    KeyCode::Char(' ') | KeyCode::Char('　') => {
-                        state.toggle_current();
-                    }
-                    KeyCode::Up | KeyCode::Char('k') => state.move_up(),
-                    KeyCode::Down | KeyCode::Char('j') => state.move_down(),
-                    KeyCode::Left | KeyCode::Char('h') => state.move_left(),
-                    KeyCode::Right | KeyCode::Char('l') => state.move_right(),
-                    KeyCode::PageUp => state.page_up(),
-                    KeyCode::PageDown => state.page_down(),
-                    KeyCode::Home => state.move_home(),
-                    KeyCode::End => state.move_end(),
-                    KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        state.select_all()
+    guard.exit()?;
+    Ok(result)
+}
+
+/// Whether holding `key` down repeats it. Windows consoles and terminals
+/// using the kitty keyboard protocol report auto-repeat as its own event
+/// kind: moving and typing repeat, while toggles, Enter and Esc act once
+/// per press so a held key cannot flip a selection back and forth.
+fn repeats(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::PageUp
+        | KeyCode::PageDown
+        | KeyCode::Backspace => true,
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => true,
+        KeyCode::Char(' ') | KeyCode::Char('　') => false,
+        KeyCode::Char(_) => !key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+/// What the picker does after handling an event
+enum Step {
+    Continue,
+    Cancel,
+    Confirm,
+}
+
+fn handle_event(state: &mut SelectionState, event: Event, picker: &PickerOptions<'_>) -> Step {
+    match event {
+        Event::Key(key) if key.kind == KeyEventKind::Repeat && !repeats(&key) => {}
+        Event::Key(key) if key.kind != KeyEventKind::Release => {
+            state.clear_status();
+            if state.preview.is_some() {
+                return handle_preview_key(state, &key);
+            }
+            // A count typed before a motion repeats it, like `5j` in vim
+            let count = state.count.take();
+            let pending_g = std::mem::take(&mut state.pending_g);
+            if state.handle_search_key(&key) {
+                return Step::Continue;
+            }
+
+            match key.code {
+                // Esc drops a half-typed count before it cancels the picker
+                KeyCode::Esc if count.is_some() || pending_g => {}
+                KeyCode::Esc => return Step::Cancel,
+                // Raw mode turns Ctrl+C into a key press instead of a signal
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Step::Cancel;
+                }
+                KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('　') if state.browse => {
+                    state.open_preview(picker.load_content)
+                }
+                KeyCode::Enter => return Step::Confirm,
+                KeyCode::Tab => state.next_tab(),
+                KeyCode::BackTab => state.previous_tab(),
+                KeyCode::Char(' ') | KeyCode::Char('　') => {
+                    state.toggle_current();
+                }
+                KeyCode::Up => state.repeat(count, SelectionState::move_up),
+                KeyCode::Down => state.repeat(count, SelectionState::move_down),
+                KeyCode::Left => state.repeat(count, SelectionState::move_left),
+                KeyCode::Right => state.repeat(count, SelectionState::move_right),
+                // Plain letters always filter, so vim keys need Alt
+                KeyCode::Char(ch)
+                    if key.modifiers.difference(KeyModifiers::SHIFT) == KeyModifiers::ALT =>
+                {
+                    match ch {
+                        '0'..='9' if count.is_some() || ch != '0' => {
+                            let digit = ch.to_digit(10).unwrap_or(0) as usize;
+                            let count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+                            state.count = Some(count);
+                            state.set_status(format!("Count: {}", count));
+                        }
+                        'g' if pending_g => state.jump_to(count.unwrap_or(1)),
+                        'g' => {
+                            state.count = count;
+                            state.pending_g = true;
+                        }
+                        'G' => match count {
+                            Some(count) => state.jump_to(count),
+                            None => state.move_end(),
+                        },
+                        'k' => state.repeat(count, SelectionState::move_up),
+                        'j' => state.repeat(count, SelectionState::move_down),
+                        'h' => state.repeat(count, SelectionState::move_left),
+                        'l' => state.repeat(count, SelectionState::move_right),
+                        _ => {}
                     }
-                    KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        state.clear_all()
+                }
+                KeyCode::PageUp => state.repeat(count, SelectionState::page_up),
+                KeyCode::PageDown => state.repeat(count, SelectionState::page_down),
+                KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    state.repeat(count, SelectionState::page_down)
+                }
+                KeyCode::Home => state.move_home(),
+                KeyCode::End => state.move_end(),
+                KeyCode::Char('a')
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && !state.browse =>
+                {
+                    state.select_all()
+                }
+                KeyCode::Char('u')
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && !state.browse =>
+                {
+                    state.clear_all()
+                }
+                KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    state.toggle_sort()
+                }
+                KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    state.dismiss_hints()
+                }
+                KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(name) = state.current_item().map(str::to_string) {
+                        let copied = state
+                            .contents
+                            .get_or_load(&name, picker.load_content)
+                            .and_then(|content| copy_text(&content));
+                        state.set_status(match copied {
+                            Ok(()) => format!("Copied {} to clipboard", name),
+                            Err(e) => format!("Could not copy {}: {:#}", name, e),
+                        });
                     }
-                    _ => {}
                 }
+                _ => {}
             }
-            Event::Resize(_, _) => state.invalidate_cache(),
-            _ => {}
         }
-    };
+        Event::Resize(width, height) => {
+            state.resize(width, height);
+            state.redraw_all();
+        }
+        _ => {}
+    }
+    Step::Continue
+}
 
-    guard.exit()?;
-    result
+/// Keys while a preview is open: scrolling, copying the template, and Esc,
+/// Enter or Space to go back to the list
+fn handle_preview_key(state: &mut SelectionState, key: &KeyEvent) -> Step {
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Char('c') if control => return Step::Cancel,
+        KeyCode::Char('y') if control => {
+            if let Some(preview) = &state.preview {
+                let name = preview.name.clone();
+                state.set_status(match copy_text(&preview.content) {
+                    Ok(()) => format!("Copied {} to clipboard", name),
+                    Err(e) => format!("Could not copy {}: {:#}", name, e),
+                });
+            }
+        }
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('q') => {
+            state.preview = None
+        }
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_preview(-1),
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_preview(1),
+        KeyCode::PageUp => {
+            let page = state.preview_page() as isize;
+            state.scroll_preview(-page)
+        }
+        KeyCode::PageDown => {
+            let page = state.preview_page() as isize;
+            state.scroll_preview(page)
+        }
+        KeyCode::Home => state.scroll_preview(isize::MIN),
+        KeyCode::End => state.scroll_preview(isize::MAX),
+        _ => {}
+    }
+    Step::Continue
+}
+
+/// A template's content shown in place of the list while browsing
+struct Preview {
+    name: String,
+    content: Rc<str>,
+    /// Lines scrolled past at the top
+    scroll: usize,
 }
 
 pub struct SelectionState {
     items: Vec<String>,
+    /// Lowercased items, aligned with `items`, so filtering does not
+    /// lowercase every item on every keystroke
+    search_keys: Vec<String>,
+    /// The lowercased query `filtered_indices` was computed for, when a
+    /// longer query can narrow it instead of scanning every item again
+    filtered_for: Option<String>,
+    /// Display widths of `items`, measured once instead of on every layout
+    widths: Vec<usize>,
     filtered_indices: Vec<usize>,
+    /// Selection of the file being edited
     selected: BTreeSet<usize>,
+    /// Labels of the files the picker edits; empty or one for a single file
+    tabs: Vec<String>,
+    /// Selections of the other files, aligned with `tabs`; the active
+    /// file's entry is empty while its selection is in `selected`
+    tab_selections: Vec<BTreeSet<usize>>,
+    active_tab: usize,
     cursor: usize,
     viewport_offset: usize,
     cached_layout: Option<Layout>,
+    /// Terminal size from the last resize, or queried on first use
+    screen: Option<(u16, u16)>,
     search_query: String,
     search_active: bool,
+    /// Item indices in display order for the current sort
+    order: Vec<usize>,
+    popularity: Vec<usize>,
+    recent: Vec<usize>,
+    descriptions: Vec<String>,
+    sort: SortOrder,
+    /// One-off message shown in the footer until the next key press
+    status: Option<String>,
+    /// Rows on screen after the last render; empty forces a full redraw
+    drawn: Vec<Row>,
+    /// Count typed with Alt+digits, applied to the next motion
+    count: Option<usize>,
+    /// Whether Alt+g was pressed once, waiting for the second `g`
+    pending_g: bool,
+    /// Set once companion template hints are dismissed with Ctrl+X
+    hints_dismissed: bool,
+    /// Companion hint for the current selection; `None` until the next
+    /// render recomputes it after the selection changed
+    hint: Option<String>,
+    /// Contents read through `PickerOptions::load_content`
+    contents: ContentCache,
+    /// Set for `list --interactive`, where nothing can be selected
+    browse: bool,
+    /// Template shown instead of the list while browsing
+    preview: Option<Preview>,
 }
 
+/// One screen row of queued terminal output
+type Row = Vec<u8>;
+
 #[derive(Clone)]
 struct Layout {
     columns: usize,
@@ -94,40 +393,187 @@ struct Layout {
 impl SelectionState {
     pub fn new(items: Vec<String>) -> Self {
         let mut state = Self {
+            search_keys: items.iter().map(|item| item.to_lowercase()).collect(),
+            filtered_for: None,
+            widths: items.iter().map(|item| item.width()).collect(),
             items,
             filtered_indices: Vec::new(),
             selected: BTreeSet::new(),
+            tabs: Vec::new(),
+            tab_selections: Vec::new(),
+            active_tab: 0,
             cursor: 0,
             viewport_offset: 0,
             cached_layout: None,
+            screen: None,
             search_query: String::new(),
             search_active: false,
+            order: Vec::new(),
+            popularity: Vec::new(),
+            recent: Vec::new(),
+            descriptions: Vec::new(),
+            sort: SortOrder::Name,
+            status: None,
+            drawn: Vec::new(),
+            count: None,
+            pending_g: false,
+            hints_dismissed: false,
+            hint: None,
+            contents: ContentCache::default(),
+            browse: false,
+            preview: None,
         };
+        state.refresh_order();
         state.refresh_filter(true);
         state
     }
 
+    pub fn set_popularity(&mut self, popularity: Vec<usize>) {
+        self.popularity = popularity;
+        self.refresh_order();
+        self.refresh_filter(true);
+    }
+
+    pub fn set_recent(&mut self, recent: Vec<usize>) {
+        self.recent = recent;
+        self.refresh_order();
+        self.refresh_filter(true);
+    }
+
+    pub fn set_descriptions(&mut self, descriptions: Vec<String>) {
+        self.descriptions = descriptions;
+    }
+
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        self.sort = sort;
+        self.refresh_order();
+        self.refresh_filter(true);
+    }
+
+    pub fn set_browse(&mut self, browse: bool) {
+        self.browse = browse;
+    }
+
+    /// Shows the content of the highlighted template in place of the list
+    pub fn open_preview(&mut self, load: &dyn Fn(&str) -> Result<String>) {
+        let Some(name) = self.current_item().map(str::to_string) else {
+            return;
+        };
+        match self.contents.get_or_load(&name, load) {
+            Ok(content) => {
+                self.preview = Some(Preview {
+                    name,
+                    content,
+                    scroll: 0,
+                })
+            }
+            Err(e) => self.set_status(format!("Could not read {}: {:#}", name, e)),
+        }
+    }
+
+    /// Lines of the preview on screen at once
+    fn preview_page(&mut self) -> usize {
+        self.layout().map_or(1, |layout| layout.rows_visible)
+    }
+
+    /// Scrolls the preview by `lines`, stopping where its last line reaches
+    /// the bottom of the screen
+    pub fn scroll_preview(&mut self, lines: isize) {
+        let page = self.preview_page();
+        let Some(preview) = &mut self.preview else {
+            return;
+        };
+        let last = preview.content.lines().count().saturating_sub(page);
+        preview.scroll = preview.scroll.saturating_add_signed(lines).min(last);
+    }
+
+    pub fn toggle_sort(&mut self) {
+        self.set_sort(self.sort.toggled());
+    }
+
+    /// Picks up where a previous run left off; a cursor on a template that
+    /// no longer matches the filter stays at the top
+    pub fn restore(&mut self, session: &PickerSession) {
+        if let Some(sort) = session.sort {
+            self.set_sort(sort);
+        }
+        if !session.filter.is_empty() {
+            self.search_query = session.filter.clone();
+            self.refresh_filter(true);
+        }
+        if let Some(name) = &session.cursor
+            && let Some(position) = self
+                .filtered_indices
+                .iter()
+                .position(|&idx| &self.items[idx] == name)
+        {
+            self.cursor = position;
+        }
+    }
+
+    pub fn session(&self) -> PickerSession {
+        PickerSession {
+            filter: self.search_query.clone(),
+            sort: Some(self.sort),
+            cursor: self.current_item().map(str::to_string),
+        }
+    }
+
+    fn refresh_order(&mut self) {
+        self.filtered_for = None;
+        self.order = (0..self.items.len()).collect();
+        let ranks = match self.sort {
+            SortOrder::Name => return,
+            SortOrder::Popular => &self.popularity,
+            SortOrder::Recent => &self.recent,
+        };
+        if ranks.len() == self.items.len() {
+            self.order.sort_by_key(|&idx| ranks[idx]);
+        }
+    }
+
     pub fn invalidate_cache(&mut self) {
         self.cached_layout = None;
     }
 
+    /// Lays the items out for a terminal of `width` by `height` cells
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.screen = Some((width, height));
+        self.invalidate_cache();
+    }
+
+    /// Makes the next render clear the screen and draw every row
+    pub fn redraw_all(&mut self) {
+        self.drawn.clear();
+    }
+
     fn refresh_filter(&mut self, reset_position: bool) {
         if self.search_query.is_empty() {
-            self.filtered_indices = (0..self.items.len()).collect();
-        } else {
-            let needle = self.search_query.to_lowercase();
+            self.filtered_indices = self.order.clone();
+            self.filtered_for = None;
+        } else if let Some(tag) = self.tag_filter() {
             self.filtered_indices = self
-                .items
+                .order
                 .iter()
-                .enumerate()
-                .filter_map(|(idx, item)| {
-                    if item.to_lowercase().contains(&needle) {
-                        Some(idx)
-                    } else {
-                        None
-                    }
-                })
+                .copied()
+                .filter(|&idx| has_tag_prefix(&self.items[idx], tag))
+                .collect();
+            self.filtered_for = None;
+        } else {
+            let needle = self.search_query.to_lowercase();
+            // Every item matching the longer query matched the shorter one,
+            // in the same order, so only those need checking
+            let candidates = match &self.filtered_for {
+                Some(previous) if needle.starts_with(previous.as_str()) => {
+                    std::mem::take(&mut self.filtered_indices)
+                }
+                _ => self.order.clone(),
+            };
+            self.filtered_indices = candidates
+                .into_iter()
+                .filter(|&idx| self.search_keys[idx].contains(&needle))
                 .collect();
+            self.filtered_for = Some(needle);
         }
 
         if reset_position {
@@ -144,6 +590,11 @@ impl SelectionState {
         self.invalidate_cache();
     }
 
+    /// The tag being filtered on when the query starts with `@`
+    fn tag_filter(&self) -> Option<&str> {
+        self.search_query.strip_prefix('@')
+    }
+
     fn total_count(&self) -> usize {
         self.items.len()
     }
@@ -156,6 +607,19 @@ impl SelectionState {
         self.filtered_indices.get(self.cursor).copied()
     }
 
+    pub fn current_item(&self) -> Option<&str> {
+        self.current_item_index()
+            .map(|idx| self.items[idx].as_str())
+    }
+
+    pub fn set_status(&mut self, message: String) {
+        self.status = Some(message);
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
     fn filter_matches_full_list(&self) -> bool {
         self.visible_count() == self.total_count()
     }
@@ -193,8 +657,10 @@ impl SelectionState {
         !ch.is_control() && (modifiers.is_empty() || modifiers == KeyModifiers::SHIFT)
     }
 
+    /// Space toggles the highlighted template instead of starting a filter;
+    /// every other character is searchable
     fn is_reserved_hotkey(ch: char) -> bool {
-        matches!(ch, 'q' | 'j' | 'k' | 'h' | 'l' | ' ')
+        ch == ' '
     }
 
     pub fn handle_search_key(&mut self, key: &KeyEvent) -> bool {
@@ -257,18 +723,24 @@ impl SelectionState {
             return Ok(layout.clone());
         }
 
-        let (width, height) = terminal::size()?;
+        let (width, height) = match self.screen {
+            Some(screen) => screen,
+            None => *self.screen.insert(terminal::size()?),
+        };
         let max_item_width = self
             .filtered_indices
             .iter()
-            .map(|&idx| self.items[idx].len())
+            .map(|&idx| self.widths[idx])
             .max()
             .unwrap_or(0)
             + 4;
         let term_width = width.saturating_sub(2) as usize;
+        // A name wider than the terminal is cut short rather than wrapped
+        let max_item_width = min(max_item_width, term_width.max(5));
         let mut columns = max(1, term_width / max_item_width.max(1));
         columns = min(columns, self.visible_count().max(1));
-        let rows_visible = max(1, height.saturating_sub(5) as usize);
+        let chrome = if self.has_tabs() { 6 } else { 5 };
+        let rows_visible = max(1, height.saturating_sub(chrome) as usize);
 
         let layout = Layout {
             columns,
@@ -391,6 +863,27 @@ impl SelectionState {
         }
     }
 
+    /// Moves the cursor to the `position`th item, counted from 1, or the
+    /// last item when there are fewer
+    pub fn jump_to(&mut self, position: usize) {
+        let visible = self.visible_count();
+        if visible > 0 {
+            self.cursor = min(position.saturating_sub(1), visible - 1);
+            if let Ok(layout) = self.layout() {
+                self.ensure_visible(&layout);
+            }
+        }
+    }
+
+    /// Runs `motion` `count` times, or once without a count
+    pub fn repeat(&mut self, count: Option<usize>, motion: fn(&mut Self)) {
+        // Moving further than there are items changes nothing
+        let times = min(count.unwrap_or(1), self.visible_count().max(1));
+        for _ in 0..times {
+            motion(self);
+        }
+    }
+
     pub fn move_end(&mut self) {
         let visible = self.visible_count();
         if visible > 0 {
@@ -402,13 +895,35 @@ impl SelectionState {
     }
 
     pub fn toggle_current(&mut self) {
+        if self.tag_filter().is_some() {
+            self.toggle_tag();
+            return;
+        }
         if let Some(idx) = self.current_item_index() {
             if self.selected.contains(&idx) {
                 self.selected.remove(&idx);
             } else {
                 self.selected.insert(idx);
             }
+            self.hint = None;
+        }
+    }
+
+    /// Selects every template matching the tag filter, or deselects them
+    /// when they are all selected already
+    pub fn toggle_tag(&mut self) {
+        let all_selected = self
+            .filtered_indices
+            .iter()
+            .all(|idx| self.selected.contains(idx));
+        for idx in &self.filtered_indices {
+            if all_selected {
+                self.selected.remove(idx);
+            } else {
+                self.selected.insert(*idx);
+            }
         }
+        self.hint = None;
     }
 
     pub fn select_all(&mut self) {
@@ -418,6 +933,7 @@ impl SelectionState {
         for idx in &self.filtered_indices {
             self.selected.insert(*idx);
         }
+        self.hint = None;
     }
 
     pub fn clear_all(&mut self) {
@@ -428,146 +944,399 @@ impl SelectionState {
                 self.selected.remove(idx);
             }
         }
+        self.hint = None;
     }
 
     pub fn select_item(&mut self, idx: usize) {
         if idx < self.items.len() {
             self.selected.insert(idx);
+            self.hint = None;
         }
     }
 
     pub fn finish(self) -> Vec<String> {
-        self.selected
-            .into_iter()
-            .filter_map(|idx| self.items.get(idx).cloned())
+        self.names(&self.selected)
+    }
+
+    /// The selection of every file, in the order of the tabs
+    pub fn finish_tabs(mut self) -> Vec<Vec<String>> {
+        if self.tabs.is_empty() {
+            return vec![self.finish()];
+        }
+        self.tab_selections[self.active_tab] = std::mem::take(&mut self.selected);
+        self.tab_selections
+            .iter()
+            .map(|selected| self.names(selected))
             .collect()
     }
 
-    pub fn render(&mut self, stdout: &mut Stdout) -> Result<()> {
+    fn names(&self, selected: &BTreeSet<usize>) -> Vec<String> {
+        selected
+            .iter()
+            .filter_map(|&idx| self.items.get(idx).cloned())
+            .collect()
+    }
+
+    /// Edits a selection for each of `labels`, starting with the first
+    pub fn set_tabs(&mut self, labels: Vec<String>) {
+        self.tab_selections = vec![BTreeSet::new(); labels.len()];
+        self.tabs = labels;
+        self.active_tab = 0;
+        self.invalidate_cache();
+    }
+
+    /// Puts the current selection aside and edits the `tab`th file's
+    pub fn switch_tab(&mut self, tab: usize) {
+        if tab == self.active_tab || tab >= self.tabs.len() {
+            return;
+        }
+        std::mem::swap(
+            &mut self.selected,
+            &mut self.tab_selections[self.active_tab],
+        );
+        std::mem::swap(&mut self.selected, &mut self.tab_selections[tab]);
+        self.active_tab = tab;
+        self.hint = None;
+    }
+
+    pub fn next_tab(&mut self) {
+        if !self.tabs.is_empty() {
+            self.switch_tab((self.active_tab + 1) % self.tabs.len());
+        }
+    }
+
+    pub fn previous_tab(&mut self) {
+        if !self.tabs.is_empty() {
+            self.switch_tab((self.active_tab + self.tabs.len() - 1) % self.tabs.len());
+        }
+    }
+
+    /// Whether the picker edits more than one file and shows a line of tabs
+    fn has_tabs(&self) -> bool {
+        self.tabs.len() > 1
+    }
+
+    /// Draws the picker, rewriting only the rows that changed since the last
+    /// frame; the first frame and the one after a resize clear the screen.
+    /// Only the cells on screen are formatted, so a frame costs the same
+    /// with thousands of templates as with a few dozen.
+    pub fn render(&mut self, stdout: &mut impl Write) -> Result<()> {
         let layout = self.layout()?;
         self.ensure_visible(&layout);
+        if self.hint.is_none() {
+            self.hint = Some(self.companion_hint());
+        }
+
+        let mut frame = self.render_header()?;
+        if self.preview.is_some() {
+            frame.extend(self.render_preview(&layout)?);
+        } else {
+            frame.extend(self.render_items(&layout)?);
+        }
+        frame.push(self.render_hint()?);
+        frame.push(self.render_footer(&layout)?);
 
-        stdout.queue(Clear(ClearType::All))?;
-        self.render_header(stdout)?;
-        self.render_items(stdout, &layout)?;
-        self.render_footer(stdout, &layout)?;
+        let full = self.drawn.is_empty();
+        if full {
+            stdout.queue(Clear(ClearType::All))?;
+        }
+        for (y, row) in frame.iter().enumerate() {
+            if !full && self.drawn.get(y) == Some(row) {
+                continue;
+            }
+            stdout.queue(MoveTo(0, y as u16))?;
+            stdout.write_all(row)?;
+            stdout.queue(Clear(ClearType::UntilNewLine))?;
+        }
+        for y in frame.len()..self.drawn.len() {
+            stdout.queue(MoveTo(0, y as u16))?;
+            stdout.queue(Clear(ClearType::CurrentLine))?;
+        }
+        self.drawn = frame;
 
         Ok(())
     }
 
-    fn render_header(&self, stdout: &mut Stdout) -> Result<()> {
-        stdout.queue(MoveTo(0, 0))?;
-        stdout.queue(SetAttribute(Attribute::Reset))?;
+    fn render_header(&self) -> Result<Vec<Row>> {
+        let mut title = Vec::new();
+        title.queue(SetAttribute(Attribute::Reset))?;
         let theme = get_theme();
-        stdout.queue(SetForegroundColor(theme.header_title))?;
-        stdout.queue(SetAttribute(Attribute::Bold))?;
-        stdout.queue(Print("Select templates  "))?;
-        stdout.queue(SetAttribute(Attribute::Reset))?;
-        stdout.queue(SetForegroundColor(theme.header_hint))?;
-        stdout.queue(Print(
-            "Space=toggle  Enter=confirm  Esc=cancel  Ctrl+A=all  Ctrl+U=clear",
-        ))?;
-        stdout.queue(ResetColor)?;
-
-        stdout.queue(MoveTo(0, 1))?;
-        stdout.queue(SetAttribute(Attribute::Reset))?;
+        title.queue(SetForegroundColor(theme.header_title))?;
+        title.queue(SetAttribute(Attribute::Bold))?;
+        let (heading, keys) = match &self.preview {
+            Some(preview) => (
+                format!("{}  ", preview.name),
+                "Up/Down/PgUp/PgDn=scroll  Ctrl+Y=copy  Esc=back to the list",
+            ),
+            None if self.browse => (
+                String::from("Browse templates  "),
+                "Enter=preview  Ctrl+Y=copy  Esc=exit  Ctrl+S=sort",
+            ),
+            None => (
+                String::from("Select templates  "),
+                "Space=toggle  Enter=confirm  Esc=cancel  Ctrl+A=all  Ctrl+U=clear  Ctrl+S=sort",
+            ),
+        };
+        title.queue(Print(heading))?;
+        title.queue(SetAttribute(Attribute::Reset))?;
+        title.queue(SetForegroundColor(theme.header_hint))?;
+        title.queue(Print(keys))?;
+        title.queue(ResetColor)?;
+
+        let mut filter = Vec::new();
+        filter.queue(SetAttribute(Attribute::Reset))?;
         let mut filter_text = if self.search_query.is_empty() {
             String::from("Filter: showing all templates")
+        } else if let Some(tag) = self.tag_filter() {
+            format!("Tag: @{}", tag)
         } else {
             format!("Filter: {}", self.search_query)
         };
         if self.search_active {
             filter_text.push_str(" _");
         }
-        stdout.queue(SetForegroundColor(theme.header_hint))?;
-        stdout.queue(Print(filter_text))?;
-        stdout.queue(Print("  (/ to focus, type to filter, Delete clears)"))?;
-        stdout.queue(ResetColor)?;
-        Ok(())
+        if self.tag_filter().is_some() {
+            filter_text.push_str("  Space toggles the whole tag");
+        }
+        filter.queue(SetForegroundColor(theme.header_hint))?;
+        filter.queue(Print(filter_text))?;
+        filter.queue(Print(
+            "  (/ to focus, type to filter, @ for tags, Delete clears)",
+        ))?;
+        filter.queue(ResetColor)?;
+
+        if !self.has_tabs() {
+            return Ok(vec![title, filter]);
+        }
+        let mut tabs = Vec::new();
+        tabs.queue(SetAttribute(Attribute::Reset))?;
+        for (number, label) in self.tabs.iter().enumerate() {
+            let count = if number == self.active_tab {
+                self.selected.len()
+            } else {
+                self.tab_selections[number].len()
+            };
+            if number == self.active_tab {
+                tabs.queue(SetForegroundColor(theme.header_title))?;
+                tabs.queue(SetAttribute(Attribute::Reverse))?;
+            } else {
+                tabs.queue(SetForegroundColor(theme.header_hint))?;
+            }
+            tabs.queue(Print(format!(" {} ({}) ", label, count)))?;
+            tabs.queue(SetAttribute(Attribute::Reset))?;
+            tabs.queue(Print(" "))?;
+        }
+        tabs.queue(SetForegroundColor(theme.header_hint))?;
+        tabs.queue(Print(" Tab/Shift+Tab switch files"))?;
+        tabs.queue(ResetColor)?;
+        Ok(vec![title, tabs, filter])
     }
 
-    fn render_items(&self, stdout: &mut Stdout, layout: &Layout) -> Result<()> {
+    fn render_items(&self, layout: &Layout) -> Result<Vec<Row>> {
+        let mut rows = vec![Vec::new(); layout.rows_visible];
         if self.filtered_indices.is_empty() {
-            stdout.queue(MoveTo(0, 2))?;
             let theme = get_theme();
-            stdout.queue(SetForegroundColor(theme.header_hint))?;
-            stdout.queue(Print("No templates match the current filter."))?;
-            stdout.queue(ResetColor)?;
-            return Ok(());
+            rows[0].queue(SetForegroundColor(theme.header_hint))?;
+            rows[0].queue(Print("No templates match the current filter."))?;
+            rows[0].queue(ResetColor)?;
+            return Ok(rows);
         }
 
-        for row in 0..layout.rows_visible {
+        for (row, out) in rows.iter_mut().enumerate() {
             for col in 0..layout.columns {
                 let idx = self.viewport_offset + row * layout.columns + col;
                 if idx >= self.filtered_indices.len() {
                     break;
                 }
+                // Every item fills exactly one column, so items follow each
+                // other without explicit cursor moves
+                self.render_single_item(out, idx, layout)?;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// The lines of the previewed template that fit on screen, cut to the
+    /// terminal width
+    fn render_preview(&self, layout: &Layout) -> Result<Vec<Row>> {
+        let mut rows = vec![Vec::new(); layout.rows_visible];
+        let Some(preview) = &self.preview else {
+            return Ok(rows);
+        };
+        let width = self.screen.map_or(80, |(width, _)| width as usize);
+        let lines = preview.content.lines().skip(preview.scroll);
+        for (out, line) in rows.iter_mut().zip(lines) {
+            let line = line.replace('\t', "    ");
+            out.queue(Print(fit_width(&line, width.saturating_sub(1)).trim_end()))?;
+        }
+        Ok(rows)
+    }
 
-                let x = (col * layout.column_width) as u16;
-                let y = (row + 2) as u16;
-                stdout.queue(MoveTo(x, y))?;
+    /// Suggests templates that go with the current selection, above the footer
+    fn render_hint(&self) -> Result<Row> {
+        let mut out = Vec::new();
+        let hint = self.hint.as_deref().unwrap_or_default();
+        if self.hints_dismissed || hint.is_empty() {
+            return Ok(out);
+        }
+        let theme = get_theme();
+        out.queue(SetForegroundColor(theme.header_hint))?;
+        out.queue(Print(hint))?;
+        out.queue(ResetColor)?;
+        Ok(out)
+    }
 
-                self.render_single_item(stdout, idx, layout)?;
+    /// The hint line for the current selection, empty without suggestions.
+    /// Looking companions up goes through every item, so `render` only
+    /// calls this after the selection changed.
+    fn companion_hint(&self) -> String {
+        let selected: Vec<String> = self
+            .selected
+            .iter()
+            .map(|&idx| self.items[idx].clone())
+            .collect();
+        let suggestions = suggest(&selected, &self.items);
+        if suggestions.is_empty() {
+            return String::new();
+        }
+
+        let names: Vec<&str> = suggestions.iter().map(|s| s.name.as_str()).collect();
+        let mut reasons: Vec<&str> = Vec::new();
+        for suggestion in &suggestions {
+            if !reasons.contains(&suggestion.because_of.as_str()) {
+                reasons.push(&suggestion.because_of);
             }
         }
-        Ok(())
+        format!(
+            "Goes well with {}: {} · Ctrl+X hides",
+            reasons.join(", "),
+            names.join(", ")
+        )
     }
 
-    fn render_single_item(&self, stdout: &mut Stdout, idx: usize, layout: &Layout) -> Result<()> {
+    pub fn dismiss_hints(&mut self) {
+        self.hints_dismissed = true;
+    }
+
+    fn render_single_item(&self, out: &mut Row, idx: usize, layout: &Layout) -> Result<()> {
         let actual_idx = self.filtered_indices[idx];
         let is_cursor = self.cursor == idx;
         let is_selected = self.selected.contains(&actual_idx);
 
         if is_cursor {
-            stdout.queue(SetAttribute(Attribute::Reverse))?;
+            out.queue(SetAttribute(Attribute::Reverse))?;
         }
-        let checked = if is_selected { "[x]" } else { "[ ]" };
         let theme = get_theme();
+        if self.browse {
+            // Nothing can be selected, so the name alone is highlighted
+            out.queue(SetForegroundColor(theme.item_unselected_text))?;
+            out.queue(Print(fit_width(
+                &self.items[actual_idx],
+                layout.column_width - 4,
+            )))?;
+            out.queue(ResetColor)?;
+            out.queue(SetAttribute(Attribute::Reset))?;
+            out.queue(Print("    "))?;
+            return Ok(());
+        }
+        let checked = if is_selected { "[x]" } else { "[ ]" };
         let checkbox_color = if is_selected {
             theme.checkbox_selected
         } else {
             theme.checkbox_unselected
         };
 
-        stdout.queue(SetForegroundColor(checkbox_color))?;
-        stdout.queue(Print(checked))?;
+        out.queue(SetForegroundColor(checkbox_color))?;
+        out.queue(Print(checked))?;
         if is_cursor {
             // Stop reverse before the trailing space so the space is not highlighted
-            stdout.queue(SetAttribute(Attribute::Reset))?;
+            out.queue(SetAttribute(Attribute::Reset))?;
         }
-        stdout.queue(Print(" "))?;
+        out.queue(Print(" "))?;
 
         let name_color = if is_selected {
             theme.item_selected_text
         } else {
             theme.item_unselected_text
         };
-        stdout.queue(SetForegroundColor(name_color))?;
-        stdout.queue(Print(format!(
-            "{:<width$}",
+        out.queue(SetForegroundColor(name_color))?;
+        out.queue(Print(fit_width(
             &self.items[actual_idx],
-            width = layout.column_width - 4
+            layout.column_width - 4,
         )))?;
 
-        stdout.queue(ResetColor)?;
-        stdout.queue(SetAttribute(Attribute::Reset))?;
+        out.queue(ResetColor)?;
+        out.queue(SetAttribute(Attribute::Reset))?;
         Ok(())
     }
 
-    fn render_footer(&self, stdout: &mut Stdout, layout: &Layout) -> Result<()> {
-        let status = format!(
-            "Selected {}/{} · Showing {}/{} · Use arrows or hjkl to move, PgUp/PgDn to scroll",
-            self.selected.len(),
-            self.items.len(),
-            self.filtered_indices.len(),
-            self.items.len()
-        );
-        stdout.queue(MoveTo(0, (layout.rows_visible + 3) as u16))?;
+    /// The page on screen and the number of pages, both counted from 1
+    fn page_position(&self, layout: &Layout) -> (usize, usize) {
+        let capacity = (layout.columns.max(1) * layout.rows_visible).max(1);
+        let pages = self.visible_count().div_ceil(capacity).max(1);
+        (self.viewport_offset / capacity + 1, pages)
+    }
+
+    fn render_footer(&self, layout: &Layout) -> Result<Row> {
+        let sort = match self.sort {
+            SortOrder::Name => "name",
+            SortOrder::Popular => "popular",
+            SortOrder::Recent => "recent",
+        };
+        let description = self
+            .current_item_index()
+            .and_then(|idx| self.descriptions.get(idx))
+            .filter(|description| !description.is_empty());
+        let hint = self
+            .status
+            .as_ref()
+            .or(description)
+            .map(String::as_str)
+            .unwrap_or(
+                "Type to filter, arrows or Alt+hjkl to move, PgUp/PgDn to scroll, Ctrl+Y to copy",
+            );
+        let (page, pages) = self.page_position(layout);
+        let status = if let Some(preview) = &self.preview {
+            let total = preview.content.lines().count();
+            let last = min(preview.scroll + layout.rows_visible, total);
+            format!(
+                "Lines {}-{} of {} · {}",
+                min(preview.scroll + 1, total),
+                last,
+                total,
+                self.status
+                    .as_deref()
+                    .unwrap_or("Esc goes back to the list")
+            )
+        } else if self.browse {
+            format!(
+                "Showing {}/{} · Page {}/{} · Sort: {} · {}",
+                self.filtered_indices.len(),
+                self.items.len(),
+                page,
+                pages,
+                sort,
+                hint
+            )
+        } else {
+            format!(
+                "Selected {}/{} · Showing {}/{} · Page {}/{} · Sort: {} · {}",
+                self.selected.len(),
+                self.items.len(),
+                self.filtered_indices.len(),
+                self.items.len(),
+                page,
+                pages,
+                sort,
+                hint
+            )
+        };
+        let mut out = Vec::new();
         let theme = get_theme();
-        stdout.queue(SetForegroundColor(theme.footer))?;
-        stdout.queue(Print(status))?;
-        stdout.queue(ResetColor)?;
-        Ok(())
+        out.queue(SetForegroundColor(theme.footer))?;
+        out.queue(Print(status))?;
+        out.queue(ResetColor)?;
+        Ok(out)
     }
 }
 
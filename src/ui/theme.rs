@@ -1,7 +1,14 @@
-use crossterm::style::Color;
+use anyhow::Result;
+use clap::ValueEnum;
+use crossterm::style::{self, Color};
 use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{self, IsTerminal};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum ThemeKind {
     Light,
     Dark,
@@ -10,6 +17,7 @@ pub enum ThemeKind {
 pub struct Theme {
     pub accent: Color,
     pub success: Color,
+    pub warning: Color,
     pub checkbox_selected: Color,
     pub checkbox_unselected: Color,
     pub item_selected_text: Color,
@@ -26,6 +34,7 @@ impl Theme {
         Self {
             accent: Color::Blue,
             success: Color::Green,
+            warning: Color::DarkYellow,
             checkbox_selected: Color::DarkGreen,
             checkbox_unselected: Color::DarkGrey,
             item_selected_text: Color::Black,
@@ -43,6 +52,7 @@ impl Theme {
             // Increase contrast in dark theme: brighter white for text, distinct accents
             accent: Color::White,
             success: Color::Green,
+            warning: Color::Yellow,
             checkbox_selected: Color::Green,
             checkbox_unselected: Color::DarkGrey,
             item_selected_text: Color::White,
@@ -56,6 +66,136 @@ impl Theme {
     }
 }
 
+impl Theme {
+    /// Light palette for terminals limited to the 8 basic colors, such as
+    /// legacy Windows consoles and the Linux console, where grey and bright
+    /// shades are missing or unreadable
+    pub fn basic_light() -> Self {
+        Self {
+            accent: Color::DarkBlue,
+            success: Color::DarkGreen,
+            warning: Color::DarkYellow,
+            checkbox_selected: Color::DarkGreen,
+            checkbox_unselected: Color::Black,
+            item_selected_text: Color::Black,
+            item_unselected_text: Color::Black,
+            footer: Color::DarkBlue,
+            header_title: Color::DarkBlue,
+            header_hint: Color::DarkCyan,
+            list_alt1: Color::Black,
+            list_alt2: Color::DarkBlue,
+        }
+    }
+
+    /// Dark counterpart of [`Theme::basic_light`]
+    pub fn basic_dark() -> Self {
+        Self {
+            accent: Color::Grey,
+            success: Color::DarkGreen,
+            warning: Color::DarkYellow,
+            checkbox_selected: Color::DarkGreen,
+            checkbox_unselected: Color::Grey,
+            item_selected_text: Color::Grey,
+            item_unselected_text: Color::Grey,
+            footer: Color::Grey,
+            header_title: Color::Grey,
+            header_hint: Color::DarkCyan,
+            list_alt1: Color::Grey,
+            list_alt2: Color::DarkCyan,
+        }
+    }
+
+    /// Light palette for terminals that advertise 24-bit color, with
+    /// softer greys than the 16 named colors allow
+    pub fn truecolor_light() -> Self {
+        Self {
+            accent: rgb(0x1e66f5),
+            success: rgb(0x2e8b3e),
+            warning: rgb(0xb7791f),
+            checkbox_selected: rgb(0x2e8b3e),
+            checkbox_unselected: rgb(0x8c8fa1),
+            item_selected_text: rgb(0x1f2328),
+            item_unselected_text: rgb(0x1f2328),
+            footer: rgb(0x1e66f5),
+            header_title: rgb(0x1e66f5),
+            header_hint: rgb(0x6e7781),
+            list_alt1: rgb(0x1f2328),
+            list_alt2: rgb(0x57606a),
+        }
+    }
+
+    /// Dark counterpart of [`Theme::truecolor_light`]
+    pub fn truecolor_dark() -> Self {
+        Self {
+            accent: rgb(0xe5e9f0),
+            success: rgb(0xa3d977),
+            warning: rgb(0xf2c14e),
+            checkbox_selected: rgb(0xa3d977),
+            checkbox_unselected: rgb(0x6c7086),
+            item_selected_text: rgb(0xeceff4),
+            item_unselected_text: rgb(0xeceff4),
+            footer: rgb(0xc0c8d8),
+            header_title: rgb(0x89b4fa),
+            header_hint: rgb(0x7f849c),
+            list_alt1: rgb(0xeceff4),
+            list_alt2: rgb(0xb4bcd0),
+        }
+    }
+
+    /// Palette for `kind` at the color depth `palette` resolves to
+    pub fn new(kind: ThemeKind, palette: Palette) -> Self {
+        match (kind, palette.resolve()) {
+            (ThemeKind::Light, Palette::Truecolor) => Self::truecolor_light(),
+            (ThemeKind::Dark, Palette::Truecolor) => Self::truecolor_dark(),
+            (ThemeKind::Light, Palette::Ansi8) => Self::basic_light(),
+            (ThemeKind::Dark, Palette::Ansi8) => Self::basic_dark(),
+            (ThemeKind::Light, _) => Self::light(),
+            (ThemeKind::Dark, _) => Self::dark(),
+        }
+    }
+
+    /// Each themed element with the name used for it in the settings file
+    pub fn entries_mut(&mut self) -> [(&'static str, &mut Color); 12] {
+        [
+            ("accent", &mut self.accent),
+            ("success", &mut self.success),
+            ("warning", &mut self.warning),
+            ("checkbox_selected", &mut self.checkbox_selected),
+            ("checkbox_unselected", &mut self.checkbox_unselected),
+            ("item_selected_text", &mut self.item_selected_text),
+            ("item_unselected_text", &mut self.item_unselected_text),
+            ("footer", &mut self.footer),
+            ("header_title", &mut self.header_title),
+            ("header_hint", &mut self.header_hint),
+            ("list_alt1", &mut self.list_alt1),
+            ("list_alt2", &mut self.list_alt2),
+        ]
+    }
+
+    /// Palette for `kind` at the depth `settings` ask for, with their color
+    /// overrides applied
+    pub fn with_settings(kind: ThemeKind, settings: &ThemeSettings) -> Self {
+        let mut theme = Self::new(kind, settings.palette);
+        theme.apply(&settings.colors, settings.palette);
+        theme
+    }
+
+    /// Replaces entries named in `colors`, approximating RGB colors with
+    /// named ones when `palette` lacks 24-bit color. Unknown names and
+    /// colors are skipped; [`ThemeSettings::validate`] reports them.
+    fn apply(&mut self, colors: &BTreeMap<String, String>, palette: Palette) {
+        let palette = palette.resolve();
+        for (name, color) in self.entries_mut() {
+            let Some(value) = colors.get(name) else {
+                continue;
+            };
+            if let Ok(value) = parse_color(value) {
+                *color = palette.downgrade(value);
+            }
+        }
+    }
+}
+
 impl From<ThemeKind> for Theme {
     fn from(kind: ThemeKind) -> Self {
         match kind {
@@ -67,31 +207,226 @@ impl From<ThemeKind> for Theme {
 
 static THEME: OnceCell<Theme> = OnceCell::new();
 
-pub fn configure_theme(kind: ThemeKind) {
-    let _ = THEME.set(Theme::from(kind));
+/// Sets the theme from the `theme` settings, guessing what they leave out
+/// from the terminal, and turns colors off where they are unwanted
+pub fn configure_theme(settings: &ThemeSettings) {
+    style::force_color_output(capabilities().color);
+    let _ = THEME.set(Theme::with_settings(settings.kind(), settings));
 }
 
 pub fn get_theme() -> &'static Theme {
     THEME.get_or_init(Theme::light)
 }
 
+/// Background chosen with `LIGNORE_THEME`, which wins over the settings
+fn env_theme_kind() -> Option<ThemeKind> {
+    match std::env::var("LIGNORE_THEME").as_deref() {
+        Ok("light") => Some(ThemeKind::Light),
+        Ok("dark") => Some(ThemeKind::Dark),
+        _ => None,
+    }
+}
+
 pub fn detect_theme_kind_from_env() -> ThemeKind {
-    // Try to detect via COLORFGBG like "15;0" (fg;background) or "default;8"
-    if let Ok(val) = std::env::var("COLORFGBG") {
-        // Take last component as background
-        if let Some(bg_str) = val.split(';').last() {
-            if let Ok(bg) = bg_str.parse::<u8>() {
-                // Common dark backgrounds are 0-7 range; 0 (black), 1-7 dark colors
-                // Light backgrounds often 15 (white) or >7
-                if bg >= 8 || bg == 15 {
-                    return ThemeKind::Light;
-                } else {
-                    return ThemeKind::Dark;
-                }
+    env_theme_kind()
+        .or(capabilities().background)
+        .unwrap_or(ThemeKind::Dark)
+}
+
+/// What the terminal supports, probed once per run from the environment
+/// and whether stdout is a terminal. Later checks read the cached result,
+/// so every message and picker frame agrees with the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Whether to write colors at all
+    pub color: bool,
+    /// Colors the terminal can show; `u16::MAX` stands for 24-bit color
+    pub color_count: u16,
+    /// Background the terminal reports, when it does
+    pub background: Option<ThemeKind>,
+}
+
+static CAPABILITIES: OnceCell<Capabilities> = OnceCell::new();
+
+pub fn capabilities() -> &'static Capabilities {
+    CAPABILITIES.get_or_init(|| {
+        let var = |name: &str| env::var(name).ok();
+        Capabilities {
+            color: color_wanted(var, io::stdout().is_terminal()),
+            color_count: style::available_color_count(),
+            background: reported_background(var),
+        }
+    })
+}
+
+/// Whether to color output, following the NO_COLOR (no-color.org) and
+/// CLICOLOR (bixense.com/clicolors) conventions: `CLICOLOR_FORCE` set to
+/// anything but `0` colors even pipes and wins over the others, a
+/// non-empty `NO_COLOR` or `CLICOLOR=0` turns colors off, and otherwise
+/// only a terminal other than `TERM=dumb` gets them
+fn color_wanted(var: impl Fn(&str) -> Option<String>, is_terminal: bool) -> bool {
+    let set = |name: &str| var(name).filter(|value| !value.is_empty());
+    if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        return true;
+    }
+    if set("NO_COLOR").is_some() || set("CLICOLOR").as_deref() == Some("0") {
+        return false;
+    }
+    is_terminal && var("TERM").as_deref() != Some("dumb")
+}
+
+/// Background from `COLORFGBG`, such as "15;0" (foreground;background) or
+/// "default;8", which rxvt and Konsole set. Windows consoles never set it,
+/// and a value inherited from elsewhere, such as over SSH, says nothing
+/// about the console.
+fn reported_background(var: impl Fn(&str) -> Option<String>) -> Option<ThemeKind> {
+    if cfg!(windows) {
+        return None;
+    }
+    let value = var("COLORFGBG")?;
+    let background: u8 = value.split(';').next_back()?.parse().ok()?;
+    // 0-7 are the dark basic colors; 8 and up, such as 15 (white), are light
+    Some(if background >= 8 {
+        ThemeKind::Light
+    } else {
+        ThemeKind::Dark
+    })
+}
+
+/// The `theme` section of the settings file
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThemeSettings {
+    /// Light or dark terminal background; guessed when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<ThemeKind>,
+    /// Color depth of the palette
+    pub palette: Palette,
+    /// Colors replacing individual theme entries, as `#rrggbb` or a color
+    /// name such as `dark_cyan`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub colors: BTreeMap<String, String>,
+}
+
+impl ThemeSettings {
+    /// Background to theme for: `LIGNORE_THEME`, then the setting, then a
+    /// guess from the terminal
+    pub fn kind(&self) -> ThemeKind {
+        env_theme_kind()
+            .or(self.background)
+            .unwrap_or_else(detect_theme_kind_from_env)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut theme = Theme::light();
+        let names: Vec<&str> = theme.entries_mut().map(|(name, _)| name).to_vec();
+        for (name, value) in &self.colors {
+            if !names.contains(&name.as_str()) {
+                anyhow::bail!(
+                    "Unknown theme color '{}'; expected one of: {}",
+                    name,
+                    names.join(", ")
+                );
             }
+            parse_color(value)?;
         }
+        Ok(())
     }
+}
+
+/// How many colors the theme may use
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// 24-bit color when the terminal advertises it through `COLORTERM`,
+    /// otherwise the 16 or 8 named colors
+    #[default]
+    Auto,
+    Truecolor,
+    /// The 16 named colors
+    Ansi16,
+    /// The 8 basic colors
+    Ansi8,
+}
 
-    // Fallback: if NO_COLOR set, still pick based on terminal default; assume dark as typical
-    ThemeKind::Dark
+impl Palette {
+    /// The depth `Auto` stands for on this terminal
+    pub fn resolve(self) -> Self {
+        match self {
+            Palette::Auto => match capabilities().color_count {
+                u16::MAX => Palette::Truecolor,
+                count if count >= 256 => Palette::Ansi16,
+                _ => Palette::Ansi8,
+            },
+            palette => palette,
+        }
+    }
+
+    /// Closest color this palette can show
+    fn downgrade(self, color: Color) -> Color {
+        let Color::Rgb { r, g, b } = color else {
+            return color;
+        };
+        let named: &[(Color, [u8; 3])] = match self {
+            Palette::Ansi16 => &NAMED_COLORS,
+            Palette::Ansi8 => &NAMED_COLORS[..8],
+            _ => return color,
+        };
+        let distance = |[nr, ng, nb]: [u8; 3]| {
+            [(r, nr), (g, ng), (b, nb)]
+                .iter()
+                .map(|&(a, b)| (i32::from(a) - i32::from(b)).pow(2))
+                .sum::<i32>()
+        };
+        named
+            .iter()
+            .min_by_key(|(_, value)| distance(*value))
+            .map(|(named, _)| *named)
+            .unwrap_or(color)
+    }
+}
+
+/// The named colors with their usual xterm values, basic ones first
+const NAMED_COLORS: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::DarkRed, [128, 0, 0]),
+    (Color::DarkGreen, [0, 128, 0]),
+    (Color::DarkYellow, [128, 128, 0]),
+    (Color::DarkBlue, [0, 0, 128]),
+    (Color::DarkMagenta, [128, 0, 128]),
+    (Color::DarkCyan, [0, 128, 128]),
+    (Color::Grey, [192, 192, 192]),
+    (Color::DarkGrey, [128, 128, 128]),
+    (Color::Red, [255, 0, 0]),
+    (Color::Green, [0, 255, 0]),
+    (Color::Yellow, [255, 255, 0]),
+    (Color::Blue, [0, 0, 255]),
+    (Color::Magenta, [255, 0, 255]),
+    (Color::Cyan, [0, 255, 255]),
+    (Color::White, [255, 255, 255]),
+];
+
+fn rgb(value: u32) -> Color {
+    Color::Rgb {
+        r: (value >> 16) as u8,
+        g: (value >> 8) as u8,
+        b: value as u8,
+    }
+}
+
+/// Parses `#rrggbb` or a crossterm color name such as `dark_cyan`
+pub fn parse_color(value: &str) -> Result<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        if hex.len() == 6
+            && let Ok(value) = u32::from_str_radix(hex, 16)
+        {
+            return Ok(rgb(value));
+        }
+    } else if let Ok(color) = Color::try_from(value) {
+        return Ok(color);
+    }
+    anyhow::bail!(
+        "Invalid color '{}': expected #rrggbb or a name such as dark_cyan",
+        value
+    )
 }
@@ -0,0 +1,106 @@
+//! Local usage statistics: how often templates were generated and when they
+//! were last used. They stay in the cache directory and are never sent
+//! anywhere; the `usage_stats` setting turns them off.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::atomic::write_atomic;
+use crate::sources::state::now_secs;
+
+pub const USAGE_FILE: &str = "usage.json";
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct UsageStats {
+    /// Number of times a .gitignore was generated
+    pub generations: u64,
+    /// Unix time of the first recorded generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(default)]
+    pub templates: BTreeMap<String, TemplateUsage>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+pub struct TemplateUsage {
+    /// Generations that included the template
+    pub count: u64,
+    /// Unix time of the last generation that included it
+    pub last_used: u64,
+}
+
+impl UsageStats {
+    /// Reads the statistics in `cache_dir`, or empty ones
+    pub fn read(cache_dir: &Path) -> Self {
+        fs::read(cache_dir.join(USAGE_FILE))
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the statistics, creating `cache_dir` for projects that
+    /// generate without ever downloading templates
+    pub fn write(&self, cache_dir: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir)?;
+        let data = serde_json::to_vec_pretty(self)?;
+        write_atomic(cache_dir.join(USAGE_FILE), data)
+    }
+
+    /// Counts one generation of `templates`
+    pub fn record(&mut self, templates: &[String]) {
+        let now = now_secs();
+        self.generations += 1;
+        self.since.get_or_insert(now);
+        for name in templates {
+            let usage = self.templates.entry(name.clone()).or_default();
+            usage.count += 1;
+            usage.last_used = now;
+        }
+    }
+
+    /// Templates ordered from most to least used, ties broken by recency
+    pub fn most_used(&self) -> Vec<(&str, TemplateUsage)> {
+        let mut templates: Vec<(&str, TemplateUsage)> = self
+            .templates
+            .iter()
+            .map(|(name, usage)| (name.as_str(), *usage))
+            .collect();
+        templates.sort_by(|a, b| {
+            b.1.count
+                .cmp(&a.1.count)
+                .then(b.1.last_used.cmp(&a.1.last_used))
+        });
+        templates
+    }
+
+    /// Sorts `names` with the most recently used first; templates never
+    /// used keep their order after the used ones
+    pub fn sort_recent(&self, names: &mut [String]) {
+        names.sort_by_key(|name| self.recency_key(name));
+    }
+
+    /// Recency rank of each name (0 = used most recently), aligned with
+    /// `names`
+    pub fn recent_ranks(&self, names: &[String]) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..names.len()).collect();
+        order.sort_by_key(|&idx| self.recency_key(&names[idx]));
+
+        let mut ranks = vec![0; names.len()];
+        for (rank, idx) in order.into_iter().enumerate() {
+            ranks[idx] = rank;
+        }
+        ranks
+    }
+
+    fn recency_key(&self, name: &str) -> (bool, Reverse<Option<(u64, u64)>>) {
+        let usage = self.templates.get(name);
+        (
+            usage.is_none(),
+            Reverse(usage.map(|usage| (usage.last_used, usage.count))),
+        )
+    }
+}
@@ -1,5 +1,6 @@
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{Context, Result};
+use reqwest::Url;
+use std::path::{Component, Path, PathBuf};
 
 /// Validates template key to prevent path traversal attacks
 pub fn validate_template_key(key: &str) -> Result<()> {
@@ -30,6 +31,27 @@ pub fn validate_template_key(key: &str) -> Result<()> {
     Ok(())
 }
 
+/// Checks that templates are downloaded over HTTPS. Plain HTTP is allowed
+/// only to this machine, for fixture servers standing in for GitHub in tests.
+pub fn validate_remote_url(url: &str) -> Result<()> {
+    let parsed = Url::parse(url).with_context(|| format!("parsing URL {}", url))?;
+    let loopback = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match parsed.scheme() {
+        "https" => Ok(()),
+        "http" if loopback => Ok(()),
+        _ => anyhow::bail!("URL must use HTTPS: {}", url),
+    }
+}
+
+/// The directory a `file://` URL names, for mirrors read from disk instead
+/// of over the network; `None` for any other URL
+pub fn file_url_path(url: &str) -> Option<PathBuf> {
+    Url::parse(url)
+        .ok()
+        .filter(|parsed| parsed.scheme() == "file")
+        .and_then(|parsed| parsed.to_file_path().ok())
+}
+
 /// Validates output path to prevent writing to dangerous locations
 pub fn validate_output_path(path: &Path) -> Result<()> {
     let abs_path = if path.is_absolute() {
@@ -50,8 +72,35 @@ pub fn validate_output_path(path: &Path) -> Result<()> {
         }
     }
 
-    // Prevent writing to system directories
-    let path_str = canonical_path.to_string_lossy();
+    check_system_directory(&canonical_path)
+}
+
+/// Resolves a cache directory, which may be relative to the project, to an
+/// absolute path so the index stays valid from any working directory
+pub fn resolve_cache_dir(path: &Path) -> Result<PathBuf> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()?.join(path)
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other),
+        }
+    }
+    check_system_directory(&resolved.join(""))?;
+    Ok(resolved)
+}
+
+/// Prevents writing to system directories
+fn check_system_directory(path: &Path) -> Result<()> {
+    let path_str = path.to_string_lossy();
     let dangerous_paths = [
         "/etc/",
         "/sys/",
@@ -0,0 +1,92 @@
+//! Placeholders such as `{{project_name}}` in custom templates, filled in
+//! when generating so one organization template fits every project
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Replaces each `{{name}}` in `content` with its value: the entry of
+/// `variables` (from lignore.json) when there is one, otherwise a built-in
+/// one. Braces around anything but a name, such as `{{ }}`, are left as
+/// they are.
+pub fn substitute(content: &str, variables: &BTreeMap<String, String>) -> Result<String> {
+    if !content.contains("{{") {
+        return Ok(content.to_string());
+    }
+
+    let mut filled = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let name = after[..end].trim();
+        if !is_name(name) {
+            filled.push_str(&rest[..start + 2]);
+            rest = after;
+            continue;
+        }
+        filled.push_str(&rest[..start]);
+        match variables.get(name) {
+            Some(value) => filled.push_str(value),
+            None => filled.push_str(&builtin(name)?),
+        }
+        rest = &after[end + 2..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// Values worked out from the repository: `project_name` is the name of
+/// its top directory, `author` and `author_email` come from git's config
+fn builtin(name: &str) -> Result<String> {
+    let value = match name {
+        "project_name" => project_name(),
+        "author" => git_config("user.name"),
+        "author_email" => git_config("user.email"),
+        _ => anyhow::bail!(
+            "Unknown variable {{{{{}}}}}; define it under `variables` in lignore.json",
+            name
+        ),
+    };
+    value.with_context(|| {
+        format!(
+            "{{{{{}}}}} has no value here; define it under `variables` in lignore.json",
+            name
+        )
+    })
+}
+
+/// Name of the repository's top directory, or of the current directory
+/// outside a repository
+fn project_name() -> Option<String> {
+    let root = git(&["rev-parse", "--show-toplevel"])
+        .map(Into::into)
+        .or_else(|| std::env::current_dir().ok())?;
+    Path::new(&root)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+fn git_config(key: &str) -> Option<String> {
+    git(&["config", "--get", key])
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|value| !value.is_empty())
+}
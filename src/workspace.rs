@@ -0,0 +1,244 @@
+//! Workspaces: a root lignore.json lists member directories that each have
+//! their own lignore.json and .gitignore. Git applies every .gitignore on a
+//! path together, so a member's rules can fight the ones of a file above it.
+
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{CONFIG_FILE, HIDDEN_CONFIG_FILE};
+use crate::matcher::{Matcher, Rule};
+
+/// The rules one ignore file of the workspace would contain
+#[derive(Debug)]
+pub struct IgnoreFile {
+    /// Directory of the file relative to the workspace root, `/`-separated
+    /// and empty for the root itself
+    pub dir: String,
+    /// The file, for messages
+    pub path: PathBuf,
+    pub matcher: Matcher,
+}
+
+/// How a rule of a nested ignore file clashes with one above it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The nested file re-includes what the outer one ignores
+    Reincluded,
+    /// The nested file re-includes a path inside a directory the outer one
+    /// ignores, which git never looks into
+    Unreachable,
+    /// The nested file ignores what the outer one re-includes
+    Reignored,
+    /// The outer file ignores the member directory itself
+    MemberIgnored,
+}
+
+#[derive(Debug)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub outer: PathBuf,
+    pub outer_rule: String,
+    pub inner: PathBuf,
+    pub inner_rule: String,
+    /// The path the rules disagree on, relative to the workspace root
+    pub path: String,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (inner, outer) = (self.inner.display(), self.outer.display());
+        match self.kind {
+            ConflictKind::Reincluded => write!(
+                f,
+                "{}: `{}` re-includes {}, which `{}` in {} ignores",
+                inner, self.inner_rule, self.path, self.outer_rule, outer
+            ),
+            ConflictKind::Unreachable => write!(
+                f,
+                "{}: `{}` has no effect because `{}` in {} ignores the directory {}",
+                inner, self.inner_rule, self.outer_rule, outer, self.path
+            ),
+            ConflictKind::Reignored => write!(
+                f,
+                "{}: `{}` ignores {}, which `{}` in {} re-includes",
+                inner, self.inner_rule, self.path, self.outer_rule, outer
+            ),
+            ConflictKind::MemberIgnored => write!(
+                f,
+                "`{}` in {} ignores {}, so {} has no effect",
+                self.outer_rule, outer, self.path, inner
+            ),
+        }
+    }
+}
+
+/// A member directory as a `/`-separated path relative to the workspace
+/// root, e.g. `crates/core` for `./crates/core/`
+pub fn member_dir(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The project config of a member directory, lignore.json or .lignore.json
+pub fn member_config_path(dir: &Path) -> Option<PathBuf> {
+    [CONFIG_FILE, HIDDEN_CONFIG_FILE]
+        .into_iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Compares every ignore file with each one in a directory above it and
+/// returns the rules of the nested file that the outer one overrides or
+/// that override it
+pub fn find_conflicts(files: &[IgnoreFile]) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for inner in files {
+        for outer in files {
+            if let Some(rel) = nested_dir(&outer.dir, &inner.dir) {
+                compare(outer, inner, &rel, &mut conflicts);
+            }
+        }
+    }
+    conflicts
+}
+
+/// `inner` relative to `outer` when it is strictly inside it
+fn nested_dir(outer: &str, inner: &str) -> Option<String> {
+    if outer.is_empty() {
+        return (!inner.is_empty()).then(|| inner.to_string());
+    }
+    inner
+        .strip_prefix(outer)
+        .and_then(|rest| rest.strip_prefix('/'))
+        .filter(|rest| !rest.is_empty())
+        .map(str::to_string)
+}
+
+fn compare(outer: &IgnoreFile, inner: &IgnoreFile, rel: &str, conflicts: &mut Vec<Conflict>) {
+    let conflict = |kind, outer_rule: &Rule, inner_rule: &str, path: &str| Conflict {
+        kind,
+        outer: outer.path.clone(),
+        outer_rule: outer_rule.line.clone(),
+        inner: inner.path.clone(),
+        inner_rule: inner_rule.to_string(),
+        path: join(&outer.dir, path),
+    };
+
+    let member = format!("{}/", rel);
+    if let Some((dir, rule)) = outer.matcher.excluding_parent(&member) {
+        conflicts.push(conflict(ConflictKind::MemberIgnored, rule, "", dir));
+        return;
+    }
+
+    for rule in &inner.matcher.rules {
+        let Some(example) = rule.example() else {
+            continue;
+        };
+        let path = format!("{}/{}", rel, example);
+        if rule.negated {
+            if let Some((dir, parent_rule)) = outer.matcher.excluding_parent(&path) {
+                conflicts.push(conflict(
+                    ConflictKind::Unreachable,
+                    parent_rule,
+                    &rule.line,
+                    dir,
+                ));
+            } else if let Some(outer_rule) = outer.matcher.decide(&path, rule.dir_only)
+                && !outer_rule.negated
+            {
+                conflicts.push(conflict(
+                    ConflictKind::Reincluded,
+                    outer_rule,
+                    &rule.line,
+                    &path,
+                ));
+            }
+        } else if let Some(outer_rule) = outer.matcher.decide(&path, rule.dir_only)
+            && outer_rule.negated
+        {
+            conflicts.push(conflict(
+                ConflictKind::Reignored,
+                outer_rule,
+                &rule.line,
+                &path,
+            ));
+        }
+    }
+}
+
+fn join(dir: &str, path: &str) -> String {
+    if dir.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(dir: &str, content: &str) -> IgnoreFile {
+        IgnoreFile {
+            dir: dir.to_string(),
+            path: Path::new(dir).join(".gitignore"),
+            matcher: Matcher::new(content),
+        }
+    }
+
+    #[test]
+    fn member_negating_a_root_pattern_is_reported() {
+        let files = [file("", "*.log\n"), file("app", "!keep.log\n")];
+        let conflicts = find_conflicts(&files);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::Reincluded);
+        assert_eq!(conflicts[0].path, "app/keep.log");
+        assert_eq!(conflicts[0].outer_rule, "*.log");
+    }
+
+    #[test]
+    fn negation_inside_an_ignored_directory_has_no_effect() {
+        let files = [file("", "build/\n"), file("app", "!build/keep.txt\n")];
+        let conflicts = find_conflicts(&files);
+        assert_eq!(conflicts[0].kind, ConflictKind::Unreachable);
+        assert_eq!(conflicts[0].path, "app/build");
+    }
+
+    #[test]
+    fn member_ignoring_what_the_root_re_includes_is_reported() {
+        let files = [
+            file("", "*.env\n!example.env\n"),
+            file("app", "example.env\n"),
+        ];
+        let conflicts = find_conflicts(&files);
+        assert_eq!(conflicts[0].kind, ConflictKind::Reignored);
+    }
+
+    #[test]
+    fn ignored_member_directory_is_reported() {
+        let files = [file("", "/crates/\n"), file("crates/core", "target/\n")];
+        let conflicts = find_conflicts(&files);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].kind, ConflictKind::MemberIgnored);
+    }
+
+    #[test]
+    fn independent_rules_do_not_conflict() {
+        let files = [
+            file("", "target/\n*.log\n"),
+            file("web", "node_modules/\ndist/\n"),
+            file("docs", "_build/\n"),
+        ];
+        assert!(find_conflicts(&files).is_empty());
+    }
+
+    #[test]
+    fn member_dirs_are_normalized() {
+        assert_eq!(member_dir(Path::new("./crates/core/")), "crates/core");
+    }
+}
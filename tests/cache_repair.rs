@@ -0,0 +1,57 @@
+//! `lignore cache repair` with a local template source
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Runs `lignore` with its config and cache kept in `dir`, reading templates
+/// from `dir/templates` and hiding Xcode
+fn lignore(dir: &Path, args: &[&str]) -> Output {
+    let config_dir = dir.join("config/lightignore");
+    fs::create_dir_all(&config_dir).unwrap();
+    let settings = serde_json::json!({
+        "sources": [{ "name": "local", "type": "local", "path": dir.join("templates") }],
+        "hidden": ["Xcode"],
+    });
+    fs::write(config_dir.join("config.json"), settings.to_string()).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_lignore"))
+        .args(args)
+        .current_dir(dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn index(dir: &Path) -> serde_json::Value {
+    let index = fs::read_to_string(dir.join("cache/lightignore/index.json")).unwrap();
+    serde_json::from_str(&index).unwrap()
+}
+
+#[test]
+fn repair_keeps_hidden_templates_in_the_index() {
+    let dir = tempfile::tempdir().unwrap();
+    let templates = dir.path().join("templates");
+    fs::create_dir_all(&templates).unwrap();
+    fs::write(templates.join("Rust.gitignore"), "target/\n").unwrap();
+    fs::write(templates.join("Xcode.gitignore"), "xcuserdata/\n").unwrap();
+
+    let output = lignore(dir.path(), &["--yes", "update"]);
+    assert!(output.status.success(), "{:?}", output);
+    let before = index(dir.path());
+    assert!(before.get("Xcode").is_some());
+
+    // A damaged template makes repair download it again and rewrite the index
+    let rust = dir
+        .path()
+        .join("cache/lightignore")
+        .join(before["Rust"]["path"].as_str().unwrap());
+    fs::write(&rust, "damaged\n").unwrap();
+    let output = lignore(dir.path(), &["--yes", "cache", "repair"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    let after = index(dir.path());
+    assert!(after.get("Xcode").is_some(), "{}", after);
+    assert_eq!(fs::read_to_string(&rust).unwrap(), "target/\n");
+}
@@ -0,0 +1,225 @@
+//! `lignore update` against a local stand-in for the GitHub API, so the
+//! rate-limit and retry handling is exercised without the network
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use lightignore::exit;
+
+const TREE: &str = r#"{"tree":[{"path":"Rust.gitignore","type":"blob"}],"truncated":false}"#;
+
+/// A canned response: status, extra headers and body
+struct Reply {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+impl Reply {
+    fn ok(body: &str) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.to_string(),
+        }
+    }
+
+    fn status(status: u16, headers: Vec<(&'static str, String)>, body: &str) -> Self {
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Serves HTTP/1.1 on a loopback port, answering each request with what
+/// `handler` returns for its path and how many times that path was asked
+/// for before
+struct MockGithub {
+    url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockGithub {
+    fn start(handler: impl Fn(&str, usize) -> Reply + Send + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut reader = BufReader::new(&mut stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                // Skip the headers; lignore only sends GET requests
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+                let path = request_line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap_or_default()
+                    .to_string();
+                let attempt = {
+                    let mut seen = seen.lock().unwrap();
+                    let attempt = seen.iter().filter(|seen| **seen == path).count();
+                    seen.push(path.clone());
+                    attempt
+                };
+                let reply = handler(&path, attempt);
+                let mut response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    reply.status,
+                    reply.body.len()
+                );
+                for (name, value) in &reply.headers {
+                    response.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                response.push_str("\r\n");
+                response.push_str(&reply.body);
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// How many requests were made for `path`
+    fn count(&self, path: &str) -> usize {
+        let requests = self.requests.lock().unwrap();
+        requests.iter().filter(|seen| seen.as_str() == path).count()
+    }
+}
+
+/// Answers the tree listing with `tree` and serves Rust.gitignore
+fn serve_templates(path: &str, attempt: usize, tree: impl Fn(usize) -> Reply) -> Reply {
+    match path {
+        "/repos/github/gitignore/git/trees/HEAD?recursive=1" => tree(attempt),
+        "/raw/github/gitignore/HEAD/Rust.gitignore" => Reply::ok("target/\n"),
+        _ => Reply::status(404, Vec::new(), r#"{"message":"Not Found"}"#),
+    }
+}
+
+/// Runs `lignore` in `dir` with its config and cache kept there, using the
+/// mock server as the only template source
+fn lignore(dir: &Path, server: &MockGithub, args: &[&str]) -> Output {
+    let config_dir = dir.join("config/lightignore");
+    std::fs::create_dir_all(&config_dir).unwrap();
+    let settings = format!(
+        r#"{{"sources":[{{"name":"github","type":"github","repo":"github/gitignore","api_url":"{}"}}]}}"#,
+        server.url
+    );
+    std::fs::write(config_dir.join("config.json"), settings).unwrap();
+    let project = dir.join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_lignore"))
+        .args(args)
+        .current_dir(&project)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env("XDG_CACHE_HOME", dir.join("cache"))
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap()
+}
+
+fn reset_in_an_hour() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    (now.as_secs() + 3600).to_string()
+}
+
+#[test]
+fn update_downloads_the_listed_templates() {
+    let server =
+        MockGithub::start(|path, attempt| serve_templates(path, attempt, |_| Reply::ok(TREE)));
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lignore(dir.path(), &server, &["--yes", "update"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+
+    let output = lignore(
+        dir.path(),
+        &server,
+        &["--yes", "--offline", "generate", "--from", "Rust"],
+    );
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    let generated = std::fs::read_to_string(dir.path().join("project/.gitignore")).unwrap();
+    assert!(generated.contains("# ===== Rust =====\ntarget/\n"));
+}
+
+#[test]
+fn exhausted_quota_exits_with_the_rate_limited_status() {
+    let server = MockGithub::start(|path, attempt| {
+        serve_templates(path, attempt, |_| {
+            Reply::status(
+                403,
+                vec![
+                    ("x-ratelimit-limit", "60".to_string()),
+                    ("x-ratelimit-remaining", "0".to_string()),
+                    ("x-ratelimit-reset", reset_in_an_hour()),
+                ],
+                r#"{"message":"API rate limit exceeded"}"#,
+            )
+        })
+    });
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lignore(dir.path(), &server, &["--yes", "update"]);
+    assert_eq!(
+        output.status.code(),
+        Some(i32::from(exit::RATE_LIMITED)),
+        "{:?}",
+        output
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Rate Limit Information"));
+}
+
+#[test]
+fn secondary_limit_exits_with_the_rate_limited_status() {
+    let server = MockGithub::start(|path, attempt| {
+        serve_templates(path, attempt, |_| {
+            Reply::status(
+                403,
+                vec![("retry-after", "60".to_string())],
+                r#"{"message":"You have exceeded a secondary rate limit"}"#,
+            )
+        })
+    });
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lignore(dir.path(), &server, &["--yes", "update"]);
+    assert_eq!(
+        output.status.code(),
+        Some(i32::from(exit::RATE_LIMITED)),
+        "{:?}",
+        output
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Secondary Rate Limit"));
+}
+
+#[test]
+fn server_errors_are_retried() {
+    let server = MockGithub::start(|path, attempt| {
+        serve_templates(path, attempt, |attempt| match attempt {
+            0 => Reply::status(502, Vec::new(), "Bad Gateway"),
+            _ => Reply::ok(TREE),
+        })
+    });
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = lignore(dir.path(), &server, &["--yes", "update"]);
+    assert_eq!(output.status.code(), Some(0), "{:?}", output);
+    assert_eq!(
+        server.count("/repos/github/gitignore/git/trees/HEAD?recursive=1"),
+        2
+    );
+    assert_eq!(server.count("/raw/github/gitignore/HEAD/Rust.gitignore"), 1);
+}